@@ -6,7 +6,7 @@ use aries_model::assignments::Assignment;
 struct JobShop {
     pub num_jobs: usize,
     pub num_machines: usize,
-    times: Vec<i32>,
+    times: Vec<IntCst>,
     machines: Vec<usize>,
 }
 
@@ -17,7 +17,7 @@ impl JobShop {
     pub fn tvar(&self, job: usize, op: usize) -> TVar {
         TVar(self.op_id(job, op) + 2)
     }
-    pub fn duration(&self, job: usize, op: usize) -> i32 {
+    pub fn duration(&self, job: usize, op: usize) -> IntCst {
         self.times[job * self.num_machines + op]
     }
     pub fn machine(&self, job: usize, op: usize) -> usize {
@@ -34,13 +34,17 @@ impl JobShop {
 
     /// Computes a lower bound on the makespan as the maximum of the operation durations in each
     /// job and on each machine.
-    pub fn makespan_lower_bound(&self) -> i32 {
-        let max_by_jobs: i32 = (0..self.num_jobs)
-            .map(|job| (0..self.num_machines).map(|task| self.duration(job, task)).sum::<i32>())
+    pub fn makespan_lower_bound(&self) -> IntCst {
+        let max_by_jobs: IntCst = (0..self.num_jobs)
+            .map(|job| {
+                (0..self.num_machines)
+                    .map(|task| self.duration(job, task))
+                    .sum::<IntCst>()
+            })
             .max()
             .unwrap();
 
-        let max_by_machine: i32 = (1..self.num_machines + 1)
+        let max_by_machine: IntCst = (1..self.num_machines + 1)
             .map(|m| {
                 (0..self.num_jobs)
                     .map(|job| self.duration(job, self.op_with_machine(job, m)))
@@ -62,7 +66,7 @@ impl Into<usize> for TVar {
     }
 }
 
-use aries_model::lang::{BAtom, IVar};
+use aries_model::lang::{BAtom, IVar, IntCst};
 use aries_solver::solver::Solver;
 
 use aries_model::Model;
@@ -162,8 +166,8 @@ fn parse(input: &str) -> JobShop {
 }
 
 fn encode(pb: &JobShop, lower_bound: u32, upper_bound: u32) -> (Model, Vec<BAtom>, IVar) {
-    let lower_bound = lower_bound as i32;
-    let upper_bound = upper_bound as i32;
+    let lower_bound = lower_bound as IntCst;
+    let upper_bound = upper_bound as IntCst;
     let mut m = Model::new();
     let mut hmap: HashMap<TVar, IVar> = HashMap::new();
     let mut constraints = Vec::new();
@@ -175,7 +179,7 @@ fn encode(pb: &JobShop, lower_bound: u32, upper_bound: u32) -> (Model, Vec<BAtom
             let task_start = m.new_ivar(0, upper_bound, format!("start({}, {})", j, i));
             hmap.insert(tji, task_start);
 
-            let left_on_job: i32 = (i..pb.num_machines).map(|t| pb.duration(j, t)).sum();
+            let left_on_job: IntCst = (i..pb.num_machines).map(|t| pb.duration(j, t)).sum();
             constraints.push(m.leq(task_start + left_on_job, makespan_variable));
 
             if i > 0 {
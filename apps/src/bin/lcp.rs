@@ -9,25 +9,30 @@ use aries_collections::ref_store::{Ref, RefVec};
 use aries_planning::chronicles::constraints::ConstraintType;
 
 use aries_model::assignments::{Assignment, SavedAssignment};
-use aries_model::lang::{Atom, BAtom, BVar, IAtom, IVar, SAtom, Variable};
+use aries_model::lang::{Atom, BAtom, BVar, IAtom, IVar, IntCst, SAtom, Variable, INT_CST_MAX};
 use aries_model::symbols::SymId;
-use aries_model::Model;
+use aries_model::{Model, UnifiabilityTable};
 use aries_planning::chronicles::Task;
 use aries_planning::classical::from_chronicles;
 use aries_planning::parsing::pddl::{parse_pddl_domain, parse_pddl_problem, PddlFeature};
-use aries_planning::parsing::pddl_to_chronicles;
+use aries_planning::parsing::{goal_alternatives, pddl, pddl_to_chronicles};
 use aries_solver::*;
 use aries_tnet::stn::{Edge, IncSTN, Timepoint};
 use aries_tnet::*;
 use aries_utils::input::Input;
 use aries_utils::Fmt;
 use env_param::EnvParam;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::convert::{TryFrom, TryInto};
 use std::fmt::Write as FmtWrite;
 use std::fs::File;
 use std::io::Write;
+use std::panic::{self, AssertUnwindSafe};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
 use std::time::Instant;
 use structopt::StructOpt;
 
@@ -40,17 +45,38 @@ struct Opt {
     problem: PathBuf,
     #[structopt(long = "output", short = "o")]
     plan_out_file: Option<PathBuf>,
+    /// If set, the plan will be written to this file in the format given by `--plan-format`
+    #[structopt(long)]
+    plan_output: Option<PathBuf>,
+    /// Format of the plan written to `--plan-output`: `text` for the IPC format `start: (action args...) [duration]`
+    /// or `json` for a list of `{ "start": ..., "action": ..., "args": [...] }` objects
+    /// or `timeline` for a JSON list of `{ "action": ..., "start": ..., "end": ..., "state_variables": [...] }` objects
+    #[structopt(long, default_value = "text")]
+    plan_format: PlanFormat,
     #[structopt(long, default_value = "0")]
     min_actions: u32,
     #[structopt(long)]
     max_actions: Option<u32>,
     #[structopt(long = "optimize")]
     optimize_makespan: bool,
+    /// If set, the solver will look for the plan of minimal cost, as given by the `:metric` of the problem
+    #[structopt(long)]
+    optimize_cost: bool,
+    /// Maximal makespan of a plan. All timepoints of the plan must be placed before this value.
+    #[structopt(long, default_value = "999999")]
+    horizon: u32,
+    /// Number of solver threads, each solving the problem with a different number of actions.
+    #[structopt(long, default_value = "1")]
+    jobs: u32,
+    /// If set, only the listed templates are instantiated, as a comma-separated list of `name=count` pairs
+    /// (e.g. `move=2,unlock=1`), and this single problem is solved. Intended for debugging.
+    #[structopt(long)]
+    only_templates: Option<TemplateCounts>,
 }
 
 /// Parameter that defines the symmetry breaking strategy to use.
 /// The value of this parameter is loaded from the environment variable `ARIES_LCP_SYMMETRY_BREAKING`.
-/// Possible values are `none` and `simple` (default).
+/// Possible values are `none`, `simple` (default) and `lexicographic`.
 static SYMMETRY_BREAKING: EnvParam<SymmetryBreakingType> = EnvParam::new("ARIES_LCP_SYMMETRY_BREAKING", "simple");
 
 /// The type of symmetry breaking to apply to problems.
@@ -63,6 +89,12 @@ enum SymmetryBreakingType {
     ///  - is always present if the second instance is present
     ///  - starts before the second instance
     Simple,
+    /// Symmetry breaking keyed on the arguments of actions.
+    /// This enforces that for any two instances of the same template. The first one (in arbitrary total order)
+    ///  - is always present if the second instance is present
+    ///  - has an argument vector that is lexicographically smaller or equal to the one of the second instance,
+    ///    ties being broken by their start times.
+    Lexicographic,
 }
 impl std::str::FromStr for SymmetryBreakingType {
     type Err = String;
@@ -71,14 +103,73 @@ impl std::str::FromStr for SymmetryBreakingType {
         match s {
             "none" => Ok(SymmetryBreakingType::None),
             "simple" => Ok(SymmetryBreakingType::Simple),
+            "lexicographic" => Ok(SymmetryBreakingType::Lexicographic),
             x => Err(format!("Unknown symmetry breaking type: {}", s)),
         }
     }
 }
 
+/// Quantity to minimize once a first plan is found.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Objective {
+    /// End time of the last action of the plan
+    Makespan,
+    /// Sum of the costs of all actions in the plan
+    Cost,
+}
+
+/// Format in which to write a plan to a file.
+#[derive(Copy, Clone, Debug)]
+enum PlanFormat {
+    Text,
+    Json,
+    Timeline,
+}
+impl std::str::FromStr for PlanFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(PlanFormat::Text),
+            "json" => Ok(PlanFormat::Json),
+            "timeline" => Ok(PlanFormat::Timeline),
+            _ => Err(format!("Unknown plan format: {}", s)),
+        }
+    }
+}
+
+/// Number of instances of each template, identified by its label.
+#[derive(Clone, Debug)]
+struct TemplateCounts(HashMap<String, u32>);
+impl std::str::FromStr for TemplateCounts {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut counts = HashMap::new();
+        for entry in s.split(',') {
+            let (name, count) = match entry.find('=') {
+                Some(i) => (&entry[..i], &entry[i + 1..]),
+                None => return Err(format!("Expected `name=count` but got: {}", entry)),
+            };
+            let count = count
+                .parse::<u32>()
+                .map_err(|_| format!("Invalid number of instances for {}: {}", name, count))?;
+            counts.insert(name.to_string(), count);
+        }
+        Ok(TemplateCounts(counts))
+    }
+}
+
 fn main() -> Result<()> {
     let opt: Opt = Opt::from_args();
 
+    // any `u32` is within the bounds of integer variables, no check is needed
+    let horizon = IntCst::from(opt.horizon);
+    ensure!(
+        !(opt.optimize_makespan && opt.optimize_cost),
+        "Options --optimize and --optimize-cost are mutually exclusive"
+    );
+
     let problem_file = &opt.problem;
     ensure!(
         problem_file.exists(),
@@ -87,8 +178,8 @@ fn main() -> Result<()> {
     );
 
     let problem_file = problem_file.canonicalize().unwrap();
-    let domain_file = match opt.domain {
-        Some(name) => name,
+    let domain_file = match &opt.domain {
+        Some(name) => name.clone(),
         None => aries::find_domain_of(&problem_file)
             .context("Consider specifying the domain with the option -d/--domain")?,
     };
@@ -102,53 +193,231 @@ fn main() -> Result<()> {
     // true if we are doing HTN planning, false otherwise
     let htn_mode = dom.features.contains(&PddlFeature::Hierarchy);
 
-    let mut spec = pddl_to_chronicles(&dom, &prob)?;
-
-    println!("===== Preprocessing ======");
-    aries_planning::chronicles::preprocessing::preprocess(&mut spec);
-    println!("==========================");
-
-    for n in opt.min_actions..opt.max_actions.unwrap_or(u32::max_value()) {
-        println!("{} Solving with {} actions", n, n);
-        let start = Instant::now();
-        let mut pb = FiniteProblem {
-            model: spec.context.model.clone(),
-            origin: spec.context.origin(),
-            horizon: spec.context.horizon(),
-            chronicles: spec.chronicles.clone(),
-            tables: spec.context.tables.clone(),
-        };
-        if htn_mode {
-            populate_with_task_network(&mut pb, &spec, n)?;
+    // a disjunctive goal is split into alternatives that are solved one after the other
+    let alternatives = goal_alternatives(&prob);
+
+    if let Some((pb, x)) = find_plan_for_goal_alternatives(&dom, &alternatives, htn_mode, &opt, horizon)? {
+        println!("  Solution found");
+        if let Err(e) = validate_solution(&pb, &x) {
+            bail!("Invalid solution: {}", e);
+        }
+        let plan = if htn_mode {
+            format_hddl_plan(&pb, &x)?
         } else {
-            populate_with_template_instances(&mut pb, &spec, |_| Some(n))?;
-        }
-        println!("  [{:.3}s] Populated", start.elapsed().as_secs_f32());
-        let start = Instant::now();
-        let result = solve(&pb, opt.optimize_makespan);
-        println!("  [{:.3}s] solved", start.elapsed().as_secs_f32());
-        match result {
-            Some(x) => {
-                println!("  Solution found");
-                let plan = if htn_mode {
-                    format_hddl_plan(&pb, &x)?
-                } else {
-                    format_pddl_plan(&pb, &x)?
-                };
-                println!("{}", plan);
-                if let Some(plan_out_file) = opt.plan_out_file {
-                    let mut file = File::create(plan_out_file)?;
-                    file.write_all(plan.as_bytes())?;
-                }
-                break;
-            }
-            None => (),
+            format_pddl_plan(&pb, &x)?
+        };
+        println!("{}", plan);
+        if let Some(plan_out_file) = opt.plan_out_file {
+            let mut file = File::create(plan_out_file)?;
+            file.write_all(plan.as_bytes())?;
+        }
+        if let Some(plan_output) = &opt.plan_output {
+            write_plan(&pb, &x, plan_output, opt.plan_format)?;
         }
     }
 
     Ok(())
 }
 
+/// Looks for a plan for each goal alternative of the problem, in order, and returns the first one found.
+/// An alternative is only abandoned once it is proven to have no plan with up to `max_actions` actions.
+/// As the search would never move past the first alternative otherwise, a maximal number of actions is required
+/// when there are several of them.
+fn find_plan_for_goal_alternatives(
+    dom: &pddl::Domain,
+    alternatives: &[pddl::Problem],
+    htn_mode: bool,
+    opt: &Opt,
+    horizon: IntCst,
+) -> Result<Option<(FiniteProblem, SavedAssignment)>> {
+    ensure!(
+        alternatives.len() <= 1 || opt.max_actions.is_some(),
+        "The goal has {} alternatives, option --max-actions is required to bound the search for each of them",
+        alternatives.len()
+    );
+    for (i, prob) in alternatives.iter().enumerate() {
+        if alternatives.len() > 1 {
+            println!("===== Goal alternative {}/{} =====", i + 1, alternatives.len());
+        }
+        let mut spec = pddl_to_chronicles(dom, prob)?;
+
+        println!("===== Preprocessing ======");
+        aries_planning::chronicles::preprocessing::preprocess(&mut spec)?;
+        println!("==========================");
+
+        if let Some(result) = find_plan(&spec, htn_mode, opt, horizon)? {
+            return Ok(Some(result));
+        }
+    }
+    Ok(None)
+}
+
+/// Looks for a plan with the smallest number of actions, starting from `min_actions`.
+/// Returns the finite problem in which the plan was found, together with the solution.
+///
+/// If more than one job is requested, the problems with consecutive numbers of actions are solved concurrently,
+/// each in its own thread. Once a plan is found, the threads working on larger numbers of actions are cancelled.
+fn find_plan(
+    spec: &Problem,
+    htn_mode: bool,
+    opt: &Opt,
+    horizon: IntCst,
+) -> Result<Option<(FiniteProblem, SavedAssignment)>> {
+    let max_actions = opt.max_actions.unwrap_or(u32::max_value());
+    let optimize = if opt.optimize_cost {
+        Some(Objective::Cost)
+    } else if opt.optimize_makespan {
+        Some(Objective::Makespan)
+    } else {
+        None
+    };
+    if let Some(TemplateCounts(counts)) = &opt.only_templates {
+        ensure!(!htn_mode, "Option --only-templates is not supported in HTN mode");
+        for name in counts.keys() {
+            ensure!(
+                spec.templates.iter().any(|t| t.label.as_ref() == Some(name)),
+                "No template named {}",
+                name
+            );
+        }
+        println!("Solving with selected templates");
+        let encoding = Encoding::new(instantiate_selected(spec, counts)?, horizon)?;
+        let result = solve_encoding(&encoding, optimize, None)?;
+        return Ok(result.map(|x| (encoding.pb, x)));
+    }
+    if opt.jobs <= 1 && htn_mode {
+        for n in opt.min_actions..max_actions {
+            if let Some(result) = solve_with_actions(spec, n, htn_mode, optimize, horizon, None)? {
+                return Ok(Some(result));
+            }
+        }
+        return Ok(None);
+    }
+    if opt.jobs <= 1 {
+        // a single encoding is extended with new action instances each time the number of actions is increased
+        let mut encoding: Option<Encoding> = None;
+        for n in opt.min_actions..max_actions {
+            println!("{} Solving with {} actions", n, n);
+            let start = Instant::now();
+            match &mut encoding {
+                Some(encoding) => encoding.add_template_instances(spec, n as usize - 1)?,
+                None => encoding = Some(Encoding::with_template_instances(spec, n, horizon)?),
+            }
+            let encoding = encoding.as_ref().unwrap();
+            println!("  [{:.3}s] Populated", start.elapsed().as_secs_f32());
+            let start = Instant::now();
+            let result = solve_encoding(encoding, optimize, None)?;
+            println!("  [{:.3}s] solved", start.elapsed().as_secs_f32());
+            if let Some(solution) = result {
+                return Ok(Some((encoding.pb.clone(), solution)));
+            }
+        }
+        return Ok(None);
+    }
+
+    let (sender, receiver) = mpsc::channel();
+    // for each thread still running, the number of actions it considers and its cancellation flag
+    let mut running: BTreeMap<u32, Arc<AtomicBool>> = BTreeMap::new();
+    let mut next = opt.min_actions;
+    // smallest number of actions for which a thread terminated with a plan or an error
+    let mut best: Option<(u32, Result<Option<(FiniteProblem, SavedAssignment)>>)> = None;
+    loop {
+        let bound = best.as_ref().map(|(n, _)| *n).unwrap_or(max_actions);
+        while running.len() < opt.jobs as usize && next < bound {
+            let n = next;
+            let flag = Arc::new(AtomicBool::new(false));
+            running.insert(n, flag.clone());
+            let spec = spec.clone();
+            let sender = sender.clone();
+            thread::spawn(move || {
+                // report a panic of the solver as an error, the main thread would otherwise wait for it forever
+                let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                    solve_with_actions(&spec, n, htn_mode, optimize, horizon, Some(&flag))
+                }))
+                .unwrap_or_else(|_| Err(anyhow!("Solver thread panicked with {} actions", n)));
+                // the receiver may have been dropped if a plan was already found with fewer actions
+                let _ = sender.send((n, result));
+            });
+            next += 1;
+        }
+        if running.range(..bound).next().is_none() {
+            // no thread is working on a smaller number of actions than the best one
+            break;
+        }
+
+        let (n, result) = receiver.recv().expect("Solver thread disconnected");
+        running.remove(&n);
+        if n < bound && !matches!(result, Ok(None)) {
+            for (_, flag) in running.range(n..) {
+                flag.store(true, Ordering::Relaxed);
+            }
+            best = Some((n, result));
+        }
+    }
+    match best {
+        Some((_, result)) => result,
+        None => Ok(None),
+    }
+}
+
+/// Builds and solves the finite problem with `n` actions.
+/// The solver gives up at its next restart if the `interrupt` flag is raised.
+fn solve_with_actions(
+    spec: &Problem,
+    n: u32,
+    htn_mode: bool,
+    objective: Option<Objective>,
+    horizon: IntCst,
+    interrupt: Option<&Arc<AtomicBool>>,
+) -> Result<Option<(FiniteProblem, SavedAssignment)>> {
+    println!("{} Solving with {} actions", n, n);
+    let start = Instant::now();
+    let encoding = if htn_mode {
+        Encoding::new(finite_problem(spec, n, htn_mode)?, horizon)?
+    } else {
+        Encoding::with_template_instances(spec, n, horizon)?
+    };
+    println!("  [{:.3}s] Populated", start.elapsed().as_secs_f32());
+    let start = Instant::now();
+    let result = solve_encoding(&encoding, objective, interrupt)?;
+    println!("  [{:.3}s] solved", start.elapsed().as_secs_f32());
+    Ok(result.map(|x| (encoding.pb, x)))
+}
+
+/// Builds a finite problem from the specification, with at most `n` instances of each template
+/// (or a task network decomposition of depth `n` when `htn_mode` is true).
+fn finite_problem(spec: &Problem, n: u32, htn_mode: bool) -> Result<FiniteProblem> {
+    let mut pb = uninstantiated_problem(spec);
+    if htn_mode {
+        populate_with_task_network(&mut pb, spec, n)?;
+    } else {
+        populate_with_template_instances(&mut pb, spec, |_| Some(n))?;
+    }
+    Ok(pb)
+}
+
+/// Builds a finite problem from the specification, where each template is instantiated the number of times
+/// associated to its label in `counts`. Templates that are not listed (or have no label) are not instantiated.
+fn instantiate_selected(spec: &Problem, counts: &HashMap<String, u32>) -> Result<FiniteProblem> {
+    let mut pb = uninstantiated_problem(spec);
+    populate_with_template_instances(&mut pb, spec, |template| {
+        let count = template.label.as_ref().and_then(|label| counts.get(label));
+        Some(count.copied().unwrap_or(0))
+    })?;
+    Ok(pb)
+}
+
+/// Finite problem with the chronicles of the specification, but no instance of its templates.
+fn uninstantiated_problem(spec: &Problem) -> FiniteProblem {
+    FiniteProblem {
+        model: spec.context.model.clone(),
+        origin: spec.context.origin(),
+        horizon: spec.context.horizon(),
+        chronicles: spec.chronicles.clone(),
+        tables: spec.context.tables.clone(),
+    }
+}
+
 fn populate_with_template_instances<F: Fn(&ChronicleTemplate) -> Option<u32>>(
     pb: &mut FiniteProblem,
     spec: &Problem,
@@ -254,18 +523,52 @@ fn refinements_of_task<'a>(task: &Task, pb: &FiniteProblem, spec: &'a Problem) -
     candidates
 }
 
-fn solve(pb: &FiniteProblem, optimize_makespan: bool) -> Option<SavedAssignment> {
-    let (mut model, constraints) = encode(&pb).unwrap(); // TODO: report error
+/// Searches for a plan whose makespan does not exceed the `horizon`.
+///
+/// Returns an error if no such plan exists but the problem would be solvable with a larger horizon.
+fn solve(
+    pb: &FiniteProblem,
+    objective: Option<Objective>,
+    horizon: IntCst,
+    interrupt: Option<&Arc<AtomicBool>>,
+) -> Result<Option<SavedAssignment>> {
+    solve_encoding(&Encoding::new(pb.clone(), horizon)?, objective, interrupt)
+}
+
+/// Solves the problem of the `encoding`, whose makespan is bounded by the horizon of the encoding.
+///
+/// Returns an error if no plan exists but the problem would be solvable with a larger horizon.
+/// This is only checked if the horizon constraint is part of the unsat core of the problem, by retracting it
+/// and solving the problem again with the same solver.
+fn solve_encoding(
+    encoding: &Encoding,
+    objective: Option<Objective>,
+    interrupt: Option<&Arc<AtomicBool>>,
+) -> Result<Option<SavedAssignment>> {
+    let pb = &encoding.pb;
+    let (mut model, mut constraints) = encoding.encoded()?;
+    let objective = match objective {
+        Some(Objective::Makespan) => Some(("makespan", pb.horizon)),
+        Some(Objective::Cost) => Some(("cost", plan_cost(pb, &mut model, &mut constraints))),
+        None => None,
+    };
     let stn = Box::new(IncSTN::new(model.new_write_token()));
     let mut solver = aries_solver::solver::Solver::new(model);
     solver.add_theory(stn);
+    if let Some(flag) = interrupt {
+        solver.set_interrupt_flag(flag.clone());
+    }
     solver.enforce_all(&constraints);
+    // the horizon is imposed in its own assertion level, to be retracted if it prevents finding a plan
+    solver.push();
+    solver.enforce_all_tracked(&[encoding.end_before_horizon]);
 
-    let found_plan = if optimize_makespan {
-        let res = solver.minimize_with(pb.horizon, |makespan, ass| {
+    let found_plan = if let Some((objective_name, objective)) = objective {
+        let res = solver.minimize_with(objective, |value, ass| {
             println!(
-                "\nFound plan with makespan: {}\n{}",
-                makespan,
+                "\nFound plan with {}: {}\n{}",
+                objective_name,
+                value,
                 format_pddl_plan(&pb, ass).unwrap_or_else(|e| format!("Error while formatting:\n{}", e))
             );
         });
@@ -280,35 +583,56 @@ fn solve(pb: &FiniteProblem, optimize_makespan: bool) -> Option<SavedAssignment>
 
     if let Some(solution) = found_plan {
         println!("{}", &solver.stats);
-        Some(solution)
+        Ok(Some(solution))
     } else {
-        None
+        if solver.unsat_core().contains(&encoding.end_before_horizon) {
+            solver.pop();
+            if solver.solve() {
+                bail!(
+                    "Horizon too small: a plan exists but its makespan exceeds the horizon ({}), consider increasing it with --horizon",
+                    encoding.horizon
+                );
+            }
+        }
+        Ok(None)
     }
 }
 
+/// Returns an integer atom whose value is the total cost of the present chronicles,
+/// adding to `constraints` the ones necessary to define it.
+///
+/// The cost is computed as a sequence of partial sums, where each partial sum is
+/// either equal to the previous one, or to the previous one plus the cost of the chronicle if it is present.
+fn plan_cost(pb: &FiniteProblem, model: &mut Model, constraints: &mut Vec<BAtom>) -> IAtom {
+    let costs: Vec<_> = pb
+        .chronicles
+        .iter()
+        .filter_map(|ch| ch.chronicle.cost.map(|cost| (ch.chronicle.presence, cost)))
+        .filter(|&(_, cost)| cost != 0)
+        .collect();
+    let max_cost = costs.iter().map(|&(_, cost)| cost).sum();
+
+    let mut total = IAtom::from(0);
+    for (presence, cost) in costs {
+        let next: IAtom = model.new_ivar(0, max_cost, "partial_cost").into();
+        let with_cost = [model.leq(next, total + cost), model.leq(total + cost, next)];
+        let with_cost = model.and(&with_cost);
+        constraints.push(model.implies(presence, with_cost));
+        let without_cost = [model.leq(next, total), model.leq(total, next)];
+        let without_cost = model.and(&without_cost);
+        constraints.push(model.implies(!presence, without_cost));
+        total = next;
+    }
+    total
+}
+
 #[derive(Eq, PartialEq, Hash, Copy, Clone)]
 enum Var {
     Boolean(BAtom, IAtom),
     Integer(IAtom),
 }
 
-fn effects(pb: &FiniteProblem) -> impl Iterator<Item = (BAtom, &Effect)> {
-    pb.chronicles
-        .iter()
-        .flat_map(|ch| ch.chronicle.effects.iter().map(move |eff| (ch.chronicle.presence, eff)))
-}
-
-fn conditions(pb: &FiniteProblem) -> impl Iterator<Item = (BAtom, &Condition)> {
-    pb.chronicles.iter().flat_map(|ch| {
-        ch.chronicle
-            .conditions
-            .iter()
-            .map(move |cond| (ch.chronicle.presence, cond))
-    })
-}
-
-const ORIGIN: i32 = 0;
-const HORIZON: i32 = 999999;
+const ORIGIN: IntCst = 0;
 
 struct TaskRef<'a> {
     presence: BAtom,
@@ -388,18 +712,18 @@ fn add_symmetry_breaking(
     constraints: &mut Vec<BAtom>,
     tpe: SymmetryBreakingType,
 ) -> Result<()> {
+    let chronicles = || {
+        pb.chronicles.iter().filter_map(|c| match c.origin {
+            ChronicleOrigin::FreeAction {
+                template_id,
+                generation_id,
+            } => Some((c, template_id, generation_id)),
+            _ => None,
+        })
+    };
     match tpe {
         SymmetryBreakingType::None => {}
         SymmetryBreakingType::Simple => {
-            let chronicles = || {
-                pb.chronicles.iter().filter_map(|c| match c.origin {
-                    ChronicleOrigin::FreeAction {
-                        template_id,
-                        generation_id,
-                    } => Some((c, template_id, generation_id)),
-                    _ => None,
-                })
-            };
             for (instance1, template_id1, generation_id1) in chronicles() {
                 for (instance2, template_id2, generation_id2) in chronicles() {
                     if template_id1 == template_id2 && generation_id1 < generation_id2 {
@@ -409,142 +733,230 @@ fn add_symmetry_breaking(
                 }
             }
         }
+        SymmetryBreakingType::Lexicographic => {
+            for (instance1, template_id1, generation_id1) in chronicles() {
+                for (instance2, template_id2, generation_id2) in chronicles() {
+                    if template_id1 == template_id2 && generation_id1 < generation_id2 {
+                        constraints.push(model.implies(instance1.chronicle.presence, instance2.chronicle.presence));
+                        lex_leq(instance1, instance2, model, constraints);
+                    }
+                }
+            }
+        }
     };
 
     Ok(())
 }
 
-fn encode(pb: &FiniteProblem) -> anyhow::Result<(Model, Vec<BAtom>)> {
-    let mut model = pb.model.clone();
-    let symmetry_breaking_tpe = *SYMMETRY_BREAKING.get();
+/// Enforces that the arguments of `instance1` are lexicographically smaller or equal to the ones of `instance2`.
+/// The start times of the two instances are used as the last element of the compared vectors.
+///
+/// Each element `i` is constrained to be smaller or equal to its counterpart,
+/// provided that all previous elements are pairwise equal.
+fn lex_leq(
+    instance1: &ChronicleInstance,
+    instance2: &ChronicleInstance,
+    model: &mut Model,
+    constraints: &mut Vec<BAtom>,
+) {
+    let args1 = instance1.chronicle.name[1..].iter().map(|a| a.int_view());
+    let args2 = instance2.chronicle.name[1..].iter().map(|a| a.int_view());
+    let keys1 = args1.chain(std::iter::once(IAtom::from(instance1.chronicle.start)));
+    let keys2 = args2.chain(std::iter::once(IAtom::from(instance2.chronicle.start)));
+
+    // conjunction of the equalities of all elements before the current one
+    let mut prefix_equal: Vec<BAtom> = Vec::new();
+    for (a, b) in keys1.zip(keys2) {
+        let leq = model.leq(a, b);
+        if prefix_equal.is_empty() {
+            constraints.push(leq);
+        } else {
+            let equal = model.and(&prefix_equal);
+            constraints.push(model.implies(equal, leq));
+        }
+        prefix_equal.push(model.int_eq(a, b));
+    }
+}
 
-    // the set of constraints that should be enforced
-    let mut constraints: Vec<BAtom> = Vec::new();
+/// Incremental encoding of a finite problem into a set of constraints.
+///
+/// New chronicle instances can be added to an existing encoding: only the variables and constraints involving
+/// the new chronicle are then created, in the model of the encoded problem that is shared by all of them.
+struct Encoding {
+    /// Problem whose chronicles have been encoded so far. Its model holds all variables of the encoding.
+    pb: FiniteProblem,
+    horizon: IntCst,
+    /// Constraint that the plan ends before the horizon. It is kept apart from `constraints` to be tracked
+    /// by the solver, telling whether it is responsible for the absence of a plan, and retracted if it is.
+    end_before_horizon: BAtom,
+    /// Constraints that are not affected by the addition of new chronicles.
+    constraints: Vec<BAtom>,
+    /// All effects encoded so far, as `(instance_id, effect_id, end of the effect's persistence)`.
+    effects: Vec<(usize, usize, IAtom)>,
+    /// All conditions encoded so far, as `(instance_id, condition_id, supports)`,
+    /// where each support is a conjunction that holds if the condition is supported by one particular effect.
+    conditions: Vec<(usize, usize, Vec<BAtom>)>,
+    /// Unifiability of all atoms appearing in the state variables and values of the encoded effects and conditions.
+    unifiable: UnifiabilityTable,
+}
 
-    let effs: Vec<_> = effects(&pb).collect();
-    let conds: Vec<_> = conditions(&pb).collect();
-    let eff_ends: Vec<_> = effs.iter().map(|_| model.new_ivar(ORIGIN, HORIZON, "")).collect();
+impl Encoding {
+    /// Encodes all chronicles of `pb`, for plans whose makespan does not exceed the `horizon`.
+    fn new(mut pb: FiniteProblem, horizon: IntCst) -> Result<Self> {
+        // the plan must end before the horizon
+        let end_before_horizon = pb.model.leq(pb.horizon, horizon);
+        let mut encoding = Encoding {
+            pb,
+            horizon,
+            end_before_horizon,
+            constraints: Vec::new(),
+            effects: Vec::new(),
+            conditions: Vec::new(),
+            unifiable: UnifiabilityTable::default(),
+        };
+        for instance_id in 0..encoding.pb.chronicles.len() {
+            encoding.encode_chronicle(instance_id)?;
+        }
+        Ok(encoding)
+    }
 
-    // for each condition, make sure the end is after the start
-    for &(prez_cond, cond) in &conds {
-        constraints.push(model.leq(cond.start, cond.end));
+    /// Encodes the problem with `n` instances of each template of `spec`, added one generation at a time
+    /// as would be done when incrementally increasing the number of actions.
+    fn with_template_instances(spec: &Problem, n: u32, horizon: IntCst) -> Result<Self> {
+        let mut encoding = Encoding::new(finite_problem(spec, 0, false)?, horizon)?;
+        for generation_id in 0..n as usize {
+            encoding.add_template_instances(spec, generation_id)?;
+        }
+        Ok(encoding)
     }
 
-    // for each effect, make sure the three time points are ordered
-    for ieff in 0..effs.len() {
-        let (prez_eff, eff) = effs[ieff];
-        constraints.push(model.leq(eff.persistence_start, eff_ends[ieff]));
-        constraints.push(model.leq(eff.transition_start, eff.persistence_start))
+    /// Adds the `generation_id`-th instance of each template of `spec` to the problem, and encodes them.
+    fn add_template_instances(&mut self, spec: &Problem, generation_id: usize) -> Result<()> {
+        for (template_id, template) in spec.templates.iter().enumerate() {
+            let origin = ChronicleOrigin::FreeAction {
+                template_id,
+                generation_id,
+            };
+            let instance = instantiate(template, origin, &mut self.pb)?;
+            self.pb.chronicles.push(instance);
+            self.encode_chronicle(self.pb.chronicles.len() - 1)?;
+        }
+        Ok(())
     }
 
-    // are two state variables unifiable?
-    let unifiable_sv = |model: &Model, sv1: &SV, sv2: &SV| {
-        if sv1.len() != sv2.len() {
-            false
-        } else {
-            for (&a, &b) in sv1.iter().zip(sv2) {
-                if !model.unifiable(a, b) {
-                    return false;
-                }
+    /// Encodes the constraints between the `instance_id`-th chronicle of the problem and all previously encoded ones.
+    fn encode_chronicle(&mut self, instance_id: usize) -> Result<()> {
+        let chronicles = &self.pb.chronicles;
+        let tables = &self.pb.tables;
+        let model = &mut self.pb.model;
+        let constraints = &mut self.constraints;
+        let instance = &chronicles[instance_id];
+        let ch = &instance.chronicle;
+        let prefix = instance.origin.prefix();
+
+        let unifiable = &mut self.unifiable;
+        for (state_var, value) in ch
+            .effects
+            .iter()
+            .map(|e| (&e.state_var, e.value))
+            .chain(ch.conditions.iter().map(|c| (&c.state_var, c.value)))
+        {
+            for &x in state_var {
+                unifiable.add(model, x);
             }
-            true
+            unifiable.add(model, value);
         }
-    };
+        let unifiable = &*unifiable;
+
+        // for each effect, make sure the three time points are ordered
+        let first_new_effect = self.effects.len();
+        for (effect_id, eff) in ch.effects.iter().enumerate() {
+            // not bounded by the horizon, which must only be imposed by the `end_before_horizon` constraint
+            let end: IAtom = model
+                .new_ivar(ORIGIN, INT_CST_MAX, format!("{}eff_end_{}", prefix, effect_id))
+                .into();
+            constraints.push(model.leq(eff.persistence_start, end));
+            constraints.push(model.leq(eff.transition_start, eff.persistence_start));
+            self.effects.push((instance_id, effect_id, end));
+        }
+        let effect = |&(instance_id, effect_id, end): &(usize, usize, IAtom)| {
+            let ch = &chronicles[instance_id].chronicle;
+            (ch.presence, &ch.effects[effect_id], end)
+        };
 
-    // for each pair of effects, enforce coherence constraints
-    let mut clause = Vec::with_capacity(32);
-    for (i, &(p1, e1)) in effs.iter().enumerate() {
-        for j in i + 1..effs.len() {
-            let &(p2, e2) = &effs[j];
+        // for each pair of effects with at least one new effect, enforce coherence constraints
+        let mut clause = Vec::with_capacity(32);
+        for j in first_new_effect..self.effects.len() {
+            let (p2, e2, end2) = effect(&self.effects[j]);
+            for i in 0..j {
+                let (p1, e1, end1) = effect(&self.effects[i]);
 
-            // skip if they are trivially non-overlapping
-            if !unifiable_sv(&model, &e1.state_var, &e2.state_var) {
-                continue;
-            }
+                // skip if they are trivially non-overlapping
+                if !unifiable.unifiable_seq(&e1.state_var, &e2.state_var) {
+                    continue;
+                }
 
-            clause.clear();
-            clause.push(!p1);
-            clause.push(!p2);
-            assert_eq!(e1.state_var.len(), e2.state_var.len());
-            for idx in 0..e1.state_var.len() {
-                let a = e1.state_var[idx];
-                let b = e2.state_var[idx];
-                // enforce different : a < b || a > b
-                // if they are the same variable, there is nothing we can do to separate them
-                if a != b {
-                    clause.push(model.neq(a, b));
+                clause.clear();
+                clause.push(!p1);
+                clause.push(!p2);
+                assert_eq!(e1.state_var.len(), e2.state_var.len());
+                for (&a, &b) in e1.state_var.iter().zip(&e2.state_var) {
+                    // enforce different : a < b || a > b
+                    // if they are the same variable, there is nothing we can do to separate them
+                    if a != b {
+                        clause.push(model.neq(a, b));
+                    }
                 }
-            }
 
-            clause.push(model.leq(eff_ends[j], e1.transition_start));
-            clause.push(model.leq(eff_ends[i], e2.transition_start));
+                clause.push(model.leq(end2, e1.transition_start));
+                clause.push(model.leq(end1, e2.transition_start));
 
-            // add coherence constraint
-            constraints.push(model.or(&clause));
+                // add coherence constraint
+                constraints.push(model.or(&clause));
+            }
         }
-    }
 
-    // support constraints
-    for (prez_cond, cond) in conds {
-        let mut supported = Vec::with_capacity(128);
-        // no need to support if the condition is not present
-        supported.push(!prez_cond);
+        // for each condition, make sure the end is after the start
+        for (condition_id, cond) in ch.conditions.iter().enumerate() {
+            constraints.push(model.leq(cond.start, cond.end));
+            self.conditions.push((instance_id, condition_id, Vec::new()));
+        }
 
-        for (eff_id, &(prez_eff, eff)) in effs.iter().enumerate() {
-            // quick check that the condition and effect are not trivially incompatible
-            if !unifiable_sv(&model, &cond.state_var, &eff.state_var) {
-                continue;
-            }
-            if !model.unifiable(cond.value, eff.value) {
-                continue;
-            }
-            // vector to store the AND clause
-            let mut supported_by_eff_conjunction = Vec::with_capacity(32);
-            // support only possible if the effect is present
-            supported_by_eff_conjunction.push(prez_eff);
-
-            assert_eq!(cond.state_var.len(), eff.state_var.len());
-            // same state variable
-            for idx in 0..cond.state_var.len() {
-                let a = cond.state_var[idx];
-                let b = eff.state_var[idx];
-
-                supported_by_eff_conjunction.push(model.eq(a, b));
+        // possible supports of the new conditions by all effects, and of the previous conditions by the new effects
+        for (cond_instance, condition_id, supports) in &mut self.conditions {
+            let cond = &chronicles[*cond_instance].chronicle.conditions[*condition_id];
+            let supporters = if *cond_instance == instance_id {
+                &self.effects[..]
+            } else {
+                &self.effects[first_new_effect..]
+            };
+            for supporter in supporters {
+                let (prez_eff, eff, eff_end) = effect(supporter);
+                if let Some(support) = support(model, unifiable, cond, prez_eff, eff, eff_end) {
+                    supports.push(support);
+                }
             }
-            // same value
-            let condition_value = cond.value;
-            let effect_value = eff.value;
-            supported_by_eff_conjunction.push(model.eq(condition_value, effect_value));
-
-            // effect's persistence contains condition
-            supported_by_eff_conjunction.push(model.leq(eff.persistence_start, cond.start));
-            supported_by_eff_conjunction.push(model.leq(cond.end, eff_ends[eff_id]));
-
-            // add this support expression to the support clause
-            supported.push(model.and(&supported_by_eff_conjunction));
         }
 
-        // enforce necessary conditions for condition' support
-        constraints.push(model.or(&supported));
-    }
-
-    // chronicle constraints
-    for instance in &pb.chronicles {
-        for constraint in &instance.chronicle.constraints {
+        // chronicle constraints
+        for constraint in &ch.constraints {
             match constraint.tpe {
                 ConstraintType::InTable { table_id } => {
-                    let mut supported_by_a_line = Vec::with_capacity(256);
-                    supported_by_a_line.push(!instance.chronicle.presence);
+                    let in_table = model.in_table(&constraint.variables, &tables[table_id as usize]);
+                    constraints.push(model.implies(ch.presence, in_table));
+                }
+                ConstraintType::NotInTable { table_id } => {
                     let vars = &constraint.variables;
-                    for values in pb.tables[table_id as usize].lines() {
+                    for values in tables[table_id as usize].lines() {
                         assert_eq!(vars.len(), values.len());
-                        let mut supported_by_this_line = Vec::with_capacity(16);
+                        // the chronicle is absent or at least one variable differs from the forbidden line
+                        let mut not_this_line = Vec::with_capacity(vars.len() + 1);
+                        not_this_line.push(!ch.presence);
                         for (&var, &val) in vars.iter().zip(values.iter()) {
-                            supported_by_this_line.push(model.eq(var, val));
+                            not_this_line.push(model.neq(var, val));
                         }
-                        supported_by_a_line.push(model.and(&supported_by_this_line));
+                        constraints.push(model.or(&not_this_line));
                     }
-                    constraints.push(model.or(&supported_by_a_line));
                 }
                 ConstraintType::LT => match constraint.variables.as_slice() {
                     &[a, b] => {
@@ -574,59 +986,431 @@ fn encode(pb: &FiniteProblem) -> anyhow::Result<(Model, Vec<BAtom>)> {
                 }
             }
         }
-    }
 
-    for ch in &pb.chronicles {
         // make sure the chronicle finishes before the horizon
-        let end_before_horizon = model.leq(ch.chronicle.end, pb.horizon);
-        constraints.push(model.implies(ch.chronicle.presence, end_before_horizon));
+        let end_before_horizon = model.leq(ch.end, self.pb.horizon);
+        constraints.push(model.implies(ch.presence, end_before_horizon));
 
         // enforce temporal coherence between the chronicle and its subtasks
-        constraints.push(model.leq(ch.chronicle.start, ch.chronicle.end));
-        for subtask in &ch.chronicle.subtasks {
+        constraints.push(model.leq(ch.start, ch.end));
+        for subtask in &ch.subtasks {
             let mut conj = Vec::new();
             conj.push(model.leq(subtask.start, subtask.end));
-            conj.push(model.leq(ch.chronicle.start, subtask.start));
-            conj.push(model.leq(subtask.end, ch.chronicle.end));
+            conj.push(model.leq(ch.start, subtask.start));
+            conj.push(model.leq(subtask.end, ch.end));
             let conj = model.and(&conj);
-            // constraints.push(conj);
-            constraints.push(model.implies(ch.chronicle.presence, conj));
+            constraints.push(model.implies(ch.presence, conj));
         }
+        Ok(())
     }
-    add_decomposition_constraints(pb, &mut model, &mut constraints);
-    add_symmetry_breaking(pb, &mut model, &mut constraints, symmetry_breaking_tpe)?;
 
-    Ok((model, constraints))
+    /// Returns the model and the constraints to enforce for all chronicles encoded so far,
+    /// except for the horizon constraint (`end_before_horizon`).
+    ///
+    /// Constraints that would need to be rebuilt when adding new chronicles (support, decomposition and symmetry
+    /// breaking) are declared in a copy of the model, leaving the encoding ready for new chronicles.
+    fn encoded(&self) -> Result<(Model, Vec<BAtom>)> {
+        let mut model = self.pb.model.clone();
+        let mut constraints = self.constraints.clone();
+
+        // support constraints
+        for (instance_id, _, supports) in &self.conditions {
+            let mut supported = Vec::with_capacity(supports.len() + 1);
+            // no need to support if the condition is not present
+            supported.push(!self.pb.chronicles[*instance_id].chronicle.presence);
+            supported.extend_from_slice(supports);
+            // enforce necessary conditions for condition' support
+            constraints.push(model.or(&supported));
+        }
+
+        add_decomposition_constraints(&self.pb, &mut model, &mut constraints);
+        add_symmetry_breaking(&self.pb, &mut model, &mut constraints, *SYMMETRY_BREAKING.get())?;
+
+        Ok((model, constraints))
+    }
 }
 
-fn format_pddl_plan(problem: &FiniteProblem, ass: &impl Assignment) -> Result<String> {
-    let mut out = String::new();
+/// Returns a conjunction that holds iff the condition is supported by the effect,
+/// or `None` if the effect trivially cannot support the condition.
+/// The `unifiable` table must contain all atoms of the condition and effect.
+fn support(
+    model: &mut Model,
+    unifiable: &UnifiabilityTable,
+    cond: &Condition,
+    prez_eff: BAtom,
+    eff: &Effect,
+    eff_end: IAtom,
+) -> Option<BAtom> {
+    // quick check that the condition and effect are not trivially incompatible
+    if !unifiable.unifiable_seq(&cond.state_var, &eff.state_var) || !unifiable.unifiable(cond.value, eff.value) {
+        return None;
+    }
+    // vector to store the AND clause
+    let mut supported_by_eff_conjunction = Vec::with_capacity(32);
+    // support only possible if the effect is present
+    supported_by_eff_conjunction.push(prez_eff);
+
+    assert_eq!(cond.state_var.len(), eff.state_var.len());
+    // same state variable
+    for (&a, &b) in cond.state_var.iter().zip(&eff.state_var) {
+        supported_by_eff_conjunction.push(model.eq(a, b));
+    }
+    // same value
+    supported_by_eff_conjunction.push(model.eq(cond.value, eff.value));
+
+    // effect's persistence contains condition
+    supported_by_eff_conjunction.push(model.leq(eff.persistence_start, cond.start));
+    supported_by_eff_conjunction.push(model.leq(cond.end, eff_end));
+
+    Some(model.and(&supported_by_eff_conjunction))
+}
+
+/// An action of a plan, together with its start time.
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+struct PlanStep {
+    start: IntCst,
+    action: String,
+    args: Vec<String>,
+}
+
+impl PlanStep {
+    /// Formats the action as an s-expression, e.g., `(move a b)`.
+    fn name(&self) -> String {
+        let mut name = format!("({}", self.action);
+        for arg in &self.args {
+            write!(name, " {}", arg).unwrap();
+        }
+        name.push(')');
+        name
+    }
+}
+
+/// Extracts the actions of the plan, sorted by start time.
+fn plan_steps(problem: &FiniteProblem, ass: &impl Assignment) -> Vec<PlanStep> {
     let mut plan = Vec::new();
     for ch in &problem.chronicles {
         if ass.boolean_value_of(ch.chronicle.presence) != Some(true) {
             continue;
         }
-        if ch.origin == ChronicleOrigin::Original {
+        if ch.chronicle.kind != ChronicleKind::Action {
             continue;
         }
         let start = ass.domain_of(ch.chronicle.start).0;
-        let name: Vec<SymId> = ch
-            .chronicle
-            .name
+        let mut name = ch.chronicle.name.iter().map(|satom| {
+            let sym = ass.sym_domain_of(*satom).into_singleton().unwrap();
+            ass.symbols().symbol(sym).to_string()
+        });
+        let action = name.next().unwrap();
+        let args = name.collect();
+        plan.push(PlanStep { start, action, args });
+    }
+    plan.sort();
+    plan
+}
+
+/// An action of a plan with its time span and the state variables it affects, as displayed in a Gantt chart.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+struct TimelineEntry {
+    /// The action with its arguments, e.g., `(move a b)`.
+    action: String,
+    start: IntCst,
+    end: IntCst,
+    /// State variables affected by the action, e.g., `(at r1)`, without duplicates.
+    state_variables: Vec<String>,
+}
+
+/// Extracts the actions of the plan with their start and end times, sorted by start time.
+fn timeline(pb: &FiniteProblem, ass: &impl Assignment) -> Vec<TimelineEntry> {
+    let fmt = |atoms: &[SAtom]| {
+        let symbols: Vec<String> = atoms
             .iter()
-            .map(|satom| ass.sym_domain_of(*satom).into_singleton().unwrap())
+            .map(|satom| {
+                let sym = ass.sym_domain_of(*satom).into_singleton().unwrap();
+                ass.symbols().symbol(sym).to_string()
+            })
             .collect();
-        let name = ass.symbols().format(&name);
-        plan.push((start, name));
+        format!("({})", symbols.join(" "))
+    };
+    let mut entries = Vec::new();
+    for ch in &pb.chronicles {
+        let ch = &ch.chronicle;
+        if ass.boolean_value_of(ch.presence) != Some(true) || ch.kind != ChronicleKind::Action {
+            continue;
+        }
+        let mut state_variables = Vec::new();
+        for eff in &ch.effects {
+            let sv = fmt(&eff.state_var);
+            if !state_variables.contains(&sv) {
+                state_variables.push(sv);
+            }
+        }
+        entries.push(TimelineEntry {
+            action: fmt(&ch.name),
+            start: ass.domain_of(ch.start).0,
+            end: ass.domain_of(ch.end).0,
+            state_variables,
+        });
     }
+    entries.sort_by(|a, b| (a.start, a.end, &a.action).cmp(&(b.start, b.end, &b.action)));
+    entries
+}
 
-    plan.sort();
-    for (start, name) in plan {
-        writeln!(out, "{:>3}: {}", start, name)?;
+fn format_pddl_plan(problem: &FiniteProblem, ass: &impl Assignment) -> Result<String> {
+    let mut out = String::new();
+    for step in plan_steps(problem, ass) {
+        writeln!(out, "{:>3}: {}", step.start, step.name())?;
+    }
+    Ok(out)
+}
+
+/// Checks that the assignment is a solution of the problem, independently of the constraints produced by `encode`.
+///
+/// For all present chronicles, it verifies that:
+///  - each condition is supported by an effect on the same state variable, with the same value,
+///    that is not overridden by another effect before the end of the condition,
+///  - no two effects on the same state variable have overlapping transitions,
+///  - all table, LT, EQ and NEQ constraints are satisfied.
+///
+/// Returns a description of the first violation found, if any.
+fn validate_solution(pb: &FiniteProblem, ass: &impl Assignment) -> std::result::Result<(), String> {
+    let value = |atom: Atom| {
+        let (lb, ub) = ass.int_bounds(atom);
+        if lb == ub {
+            Ok(lb)
+        } else {
+            Err(format!("{} has no single value [{}, {}]", pb.model.fmt(atom), lb, ub))
+        }
+    };
+    let sv_values = |sv: &[SAtom]| {
+        sv.iter()
+            .map(|&x| value(x.into()))
+            .collect::<std::result::Result<Vec<_>, _>>()
+    };
+    let fmt_sv = |sv: &[IntCst]| {
+        let symbols: Vec<String> = sv
+            .iter()
+            .map(|&s| ass.symbols().symbol(SymId::from(s as usize)).to_string())
+            .collect();
+        format!("({})", symbols.join(" "))
+    };
+
+    let mut present = Vec::new();
+    for ch in &pb.chronicles {
+        match ass.boolean_value_of(ch.chronicle.presence) {
+            Some(true) => present.push(&ch.chronicle),
+            Some(false) => {}
+            None => return Err(format!("Undetermined presence of chronicle {:?}", ch.chronicle.kind)),
+        }
+    }
+
+    // evaluated effects: (state variable, value, transition start, persistence start)
+    let mut effects = Vec::new();
+    for eff in present.iter().flat_map(|ch| ch.effects.iter()) {
+        let sv = sv_values(&eff.state_var)?;
+        let trans = value(eff.transition_start.into())?;
+        let pers = value(eff.persistence_start.into())?;
+        if trans > pers {
+            return Err(format!(
+                "Effect on {} has its persistence before its transition",
+                fmt_sv(&sv)
+            ));
+        }
+        effects.push((sv, value(eff.value)?, trans, pers));
+    }
+
+    for (i, (sv1, v1, t1, p1)) in effects.iter().enumerate() {
+        for (sv2, v2, t2, p2) in &effects[i + 1..] {
+            if sv1 == sv2 && p1 > t2 && p2 > t1 {
+                return Err(format!(
+                    "Overlapping effects on {}: [{}, {}] := {} and [{}, {}] := {}",
+                    fmt_sv(sv1),
+                    t1,
+                    p1,
+                    v1,
+                    t2,
+                    p2,
+                    v2
+                ));
+            }
+        }
+    }
+
+    for cond in present.iter().flat_map(|ch| ch.conditions.iter()) {
+        let sv = sv_values(&cond.state_var)?;
+        let val = value(cond.value)?;
+        let start = value(cond.start.into())?;
+        let end = value(cond.end.into())?;
+        if start > end {
+            return Err(format!("Condition on {} ends before its start", fmt_sv(&sv)));
+        }
+        // an effect supports the condition if it holds at the start of the condition
+        // and no later effect on the same state variable starts a transition before the condition's end
+        let supported = effects.iter().any(|(esv, eval, etrans, epers)| {
+            let interferes = |(osv, _, otrans, opers): &(Vec<IntCst>, IntCst, IntCst, IntCst)| {
+                osv == &sv && epers <= otrans && *otrans < end && opers > etrans
+            };
+            esv == &sv && *eval == val && *epers <= start && !effects.iter().any(interferes)
+        });
+        if !supported {
+            return Err(format!(
+                "Condition [{}, {}] {} == {} is not supported by any effect",
+                start,
+                end,
+                fmt_sv(&sv),
+                val
+            ));
+        }
+    }
+
+    for ch in &present {
+        for constraint in &ch.constraints {
+            let values = constraint
+                .variables
+                .iter()
+                .map(|&x| value(x))
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            let satisfied = match constraint.tpe {
+                ConstraintType::InTable { table_id } => pb.tables[table_id as usize]
+                    .lines()
+                    .any(|line| line == values.as_slice()),
+                ConstraintType::NotInTable { table_id } => pb.tables[table_id as usize]
+                    .lines()
+                    .all(|line| line != values.as_slice()),
+                ConstraintType::LT => matches!(values.as_slice(), &[a, b] if a < b),
+                ConstraintType::EQ => matches!(values.as_slice(), &[a, b] if a == b),
+                ConstraintType::NEQ => matches!(values.as_slice(), &[a, b] if a != b),
+            };
+            if !satisfied {
+                return Err(format!(
+                    "Constraint {:?} violated by values {:?} in chronicle {}",
+                    constraint.tpe,
+                    values,
+                    fmt_sv(&sv_values(&ch.name)?)
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Reference to the `effect_id`-th effect of the `instance_id`-th chronicle of a finite problem.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+struct EffectRef {
+    instance_id: usize,
+    effect_id: usize,
+}
+
+/// Reference to the `condition_id`-th condition of the `instance_id`-th chronicle of a finite problem.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+struct ConditionRef {
+    instance_id: usize,
+    condition_id: usize,
+}
+
+/// Returns the causal links of a solution: each condition of a present chronicle is associated to the effect that
+/// supports it, i.e., the latest present effect on the same state variable and with the same value, whose
+/// persistence starts before the condition and that is not interfered with until the end of the condition.
+///
+/// Conditions that are not supported (or whose variables are not bound) do not appear in the result.
+fn causal_links(pb: &FiniteProblem, ass: &impl Assignment) -> Vec<(EffectRef, ConditionRef)> {
+    let value = |atom: Atom| {
+        let (lb, ub) = ass.int_bounds(atom);
+        if lb == ub {
+            Some(lb)
+        } else {
+            None
+        }
+    };
+    let sv_values = |sv: &[SAtom]| sv.iter().map(|&x| value(x.into())).collect::<Option<Vec<_>>>();
+    let present = || {
+        pb.chronicles
+            .iter()
+            .enumerate()
+            .filter(|(_, ch)| ass.boolean_value_of(ch.chronicle.presence) == Some(true))
+    };
+
+    // evaluated effects: (effect, state variable, value, transition start, persistence start)
+    let mut effects = Vec::new();
+    for (instance_id, ch) in present() {
+        for (effect_id, eff) in ch.chronicle.effects.iter().enumerate() {
+            let evaluated = (
+                sv_values(&eff.state_var),
+                value(eff.value),
+                value(eff.transition_start.into()),
+                value(eff.persistence_start.into()),
+            );
+            if let (Some(sv), Some(val), Some(trans), Some(pers)) = evaluated {
+                let eff = EffectRef { instance_id, effect_id };
+                effects.push((eff, sv, val, trans, pers));
+            }
+        }
+    }
+
+    let mut links = Vec::new();
+    for (instance_id, ch) in present() {
+        for (condition_id, cond) in ch.chronicle.conditions.iter().enumerate() {
+            let evaluated = (
+                sv_values(&cond.state_var),
+                value(cond.value),
+                value(cond.start.into()),
+                value(cond.end.into()),
+            );
+            let (sv, val, start, end) = match evaluated {
+                (Some(sv), Some(val), Some(start), Some(end)) => (sv, val, start, end),
+                _ => continue,
+            };
+            let interferes = |etrans: IntCst, epers: IntCst| {
+                effects.iter().any(|(_, osv, _, otrans, opers)| {
+                    osv == &sv && epers <= *otrans && *otrans < end && *opers > etrans
+                })
+            };
+            let supporter = effects
+                .iter()
+                .filter(|(_, esv, eval, _, epers)| esv == &sv && *eval == val && *epers <= start)
+                .filter(|(_, _, _, etrans, epers)| !interferes(*etrans, *epers))
+                .max_by_key(|(_, _, _, _, epers)| *epers);
+            if let Some((eff, ..)) = supporter {
+                links.push((
+                    *eff,
+                    ConditionRef {
+                        instance_id,
+                        condition_id,
+                    },
+                ));
+            }
+        }
+    }
+    links
+}
+
+/// Formats a plan in the IPC format (`start: (action args...) [duration]`) accepted by plan validators.
+fn format_ipc_plan(problem: &FiniteProblem, ass: &impl Assignment) -> Result<String> {
+    let mut out = String::new();
+    for step in plan_steps(problem, ass) {
+        // all actions are instantaneous for now, and are given a unit duration
+        writeln!(out, "{}: {} [{}]", step.start, step.name(), 1)?;
     }
     Ok(out)
 }
 
+/// Formats a plan as a JSON list of steps.
+fn format_json_plan(problem: &FiniteProblem, ass: &impl Assignment) -> Result<String> {
+    Ok(serde_json::to_string_pretty(&plan_steps(problem, ass))?)
+}
+
+/// Writes the plan to the given file, in the requested format.
+fn write_plan(problem: &FiniteProblem, ass: &impl Assignment, path: &Path, format: PlanFormat) -> Result<()> {
+    let plan = match format {
+        PlanFormat::Text => format_ipc_plan(problem, ass)?,
+        PlanFormat::Json => format_json_plan(problem, ass)?,
+        PlanFormat::Timeline => serde_json::to_string_pretty(&timeline(problem, ass))?,
+    };
+    let mut file = File::create(path).with_context(|| format!("Could not create plan file {}", path.display()))?;
+    file.write_all(plan.as_bytes())?;
+    Ok(())
+}
+
 /// Formats a hierarchical plan into the format expected by pandaPIparser's verifier
 fn format_hddl_plan(problem: &FiniteProblem, ass: &impl Assignment) -> Result<String> {
     let mut f = String::new();
@@ -688,3 +1472,512 @@ fn format_hddl_plan(problem: &FiniteProblem, ass: &impl Assignment) -> Result<St
     writeln!(f, "<==")?;
     Ok(f)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aries_model::int_model::Cause;
+    use aries_model::lang::{BExpr, Fun, Type, VarRef};
+    use aries_model::table::Table;
+    use aries_planning::chronicles::constraints::Constraint;
+
+    const DOMAIN: &str = "(define (domain switch)
+      (:requirements :strips)
+      (:predicates (on))
+      (:action turn-on
+        :parameters ()
+        :precondition ()
+        :effect (on)))";
+
+    const PROBLEM: &str = "(define (problem switch-1)
+      (:domain switch)
+      (:objects)
+      (:init)
+      (:goal (on)))";
+
+    /// Domain where a robot must unlock a room before moving into it.
+    const ROOMS_DOMAIN: &str = "(define (domain rooms)
+      (:requirements :strips)
+      (:predicates (at ?r) (open ?r))
+      (:action unlock
+        :parameters (?r)
+        :precondition ()
+        :effect (open ?r))
+      (:action move
+        :parameters (?from ?to)
+        :precondition (and (at ?from) (open ?to))
+        :effect (and (not (at ?from)) (at ?to))))";
+
+    const ROOMS_PROBLEM: &str = "(define (problem rooms-1)
+      (:domain rooms)
+      (:objects r1 r2)
+      (:init (at r1))
+      (:goal (at r2)))";
+
+    /// Solves the tiny problem above with a single action.
+    fn solved_problem() -> (FiniteProblem, SavedAssignment) {
+        let dom = parse_pddl_domain(Input::from_string(DOMAIN)).unwrap();
+        let prob = parse_pddl_problem(Input::from_string(PROBLEM)).unwrap();
+        let spec = pddl_to_chronicles(&dom, &prob).unwrap();
+        let pb = finite_problem(&spec, 1, false).unwrap();
+        let solution = solve(&pb, None, 999999, None).unwrap().expect("No solution found");
+        (pb, solution)
+    }
+
+    #[test]
+    fn ipc_plan_file() {
+        let (pb, solution) = solved_problem();
+        let path = std::env::temp_dir().join(format!("lcp-plan-{}.txt", std::process::id()));
+        write_plan(&pb, &solution, &path, PlanFormat::Text).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 1);
+        let (start, rest) = lines[0].split_at(lines[0].find(':').unwrap());
+        assert!(start.parse::<IntCst>().is_ok());
+        assert_eq!(rest, ": (turn-on) [1]");
+    }
+
+    #[test]
+    fn plan_timeline() {
+        let (pb, solution) = solved_problem();
+        let entries = timeline(&pb, &solution);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].action, "(turn-on)");
+        assert_eq!(entries[0].state_variables, vec!["(on)".to_string()]);
+        assert!(entries.iter().all(|e| e.end >= e.start));
+        assert_eq!(plan_steps(&pb, &solution)[0].start, entries[0].start);
+    }
+
+    #[test]
+    fn solution_validation() {
+        let (pb, solution) = solved_problem();
+        assert_eq!(validate_solution(&pb, &solution), Ok(()));
+
+        // same solution, except that the action is absent: the goal is left unsupported
+        let action = pb
+            .chronicles
+            .iter()
+            .find(|c| c.chronicle.kind == ChronicleKind::Action)
+            .unwrap();
+        let presence = match action.chronicle.presence {
+            BAtom::Bound(b) => b.variable(),
+            _ => panic!("Expected a presence variable"),
+        };
+        let mut corrupted = pb.model.clone();
+        for var in pb.model.discrete.variables() {
+            let (lb, ub) = if var == presence {
+                (0, 0)
+            } else {
+                solution.discrete.domain_of(var)
+            };
+            corrupted.discrete.set_lb(var, lb, Cause::Decision).unwrap();
+            corrupted.discrete.set_ub(var, ub, Cause::Decision).unwrap();
+        }
+        let err = validate_solution(&pb, &corrupted).unwrap_err();
+        assert!(err.contains("(on) == 1 is not supported"), "{}", err);
+    }
+
+    #[test]
+    fn forbidden_tuples() {
+        let domain = "(define (domain pairs)
+          (:requirements :strips :typing)
+          (:types obj)
+          (:predicates (done))
+          (:action pick
+            :parameters (?x - obj ?y - obj)
+            :precondition ()
+            :effect (done)))";
+        let problem = "(define (problem pairs-1)
+          (:domain pairs)
+          (:objects a b - obj)
+          (:init)
+          (:goal (done)))";
+        let dom = parse_pddl_domain(Input::from_string(domain)).unwrap();
+        let prob = parse_pddl_problem(Input::from_string(problem)).unwrap();
+        let spec = pddl_to_chronicles(&dom, &prob).unwrap();
+        let mut pb = finite_problem(&spec, 1, false).unwrap();
+
+        // forbid all pairs except (b, b)
+        let sym = |name: &str| pb.model.symbols.id(name).unwrap();
+        let (a, b) = (sym("a"), sym("b"));
+        let mut table = Table::new(vec![Type::Sym(pb.model.symbols.type_of(a)); 2]);
+        for &(x, y) in &[(a, a), (a, b), (b, a)] {
+            table.push(&[x.int_value(), y.int_value()]);
+        }
+        pb.tables.push(table);
+        let table_id = (pb.tables.len() - 1) as u32;
+        let action = pb
+            .chronicles
+            .iter_mut()
+            .find(|c| c.chronicle.kind == ChronicleKind::Action)
+            .unwrap();
+        let params: Vec<SAtom> = action.chronicle.name[1..].to_vec();
+        action.chronicle.constraints.push(Constraint {
+            // as for tables built from static state functions, symbols are encoded with their integer view
+            variables: params.iter().map(|&p| Atom::from(p.int_view())).collect(),
+            tpe: ConstraintType::NotInTable { table_id },
+        });
+
+        let solution = solve(&pb, None, 999999, None).unwrap().expect("No solution found");
+        assert_eq!(validate_solution(&pb, &solution), Ok(()));
+        let values: Vec<SymId> = params.iter().map(|&p| solution.sym_value_of(p).unwrap()).collect();
+        assert_eq!(values, vec![b, b]);
+
+        // same solution, except that the first parameter is set to `a`, hitting the forbidden tuple (a, b)
+        let first = match params[0] {
+            SAtom::Var(v) => VarRef::from(v),
+            _ => panic!("Expected a variable parameter"),
+        };
+        let mut corrupted = pb.model.clone();
+        for var in pb.model.discrete.variables() {
+            let (lb, ub) = if var == first {
+                (a.int_value(), a.int_value())
+            } else {
+                solution.discrete.domain_of(var)
+            };
+            corrupted.discrete.set_lb(var, lb, Cause::Decision).unwrap();
+            corrupted.discrete.set_ub(var, ub, Cause::Decision).unwrap();
+        }
+        let err = validate_solution(&pb, &corrupted).unwrap_err();
+        assert!(err.contains("NotInTable"), "{}", err);
+    }
+
+    #[test]
+    fn json_plan() {
+        let (pb, solution) = solved_problem();
+        let json = format_json_plan(&pb, &solution).unwrap();
+        let steps: Vec<PlanStep> = serde_json::from_str(&json).unwrap();
+        assert_eq!(steps, plan_steps(&pb, &solution));
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0].action, "turn-on");
+        assert!(steps[0].args.is_empty());
+
+        let empty: Vec<PlanStep> = Vec::new();
+        assert_eq!(serde_json::to_string_pretty(&empty).unwrap(), "[]");
+    }
+
+    #[test]
+    fn horizon_too_small() {
+        let dom = parse_pddl_domain(Input::from_string(DOMAIN)).unwrap();
+        let prob = parse_pddl_problem(Input::from_string(PROBLEM)).unwrap();
+        let spec = pddl_to_chronicles(&dom, &prob).unwrap();
+        let pb = finite_problem(&spec, 1, false).unwrap();
+        // the action ends at least one time unit after the origin, the plan cannot fit in an empty horizon
+        match solve(&pb, Some(Objective::Makespan), 0, None) {
+            Err(e) => assert!(e.to_string().starts_with("Horizon too small")),
+            Ok(_) => panic!("Expected a horizon error"),
+        }
+        assert!(solve(&pb, Some(Objective::Makespan), 1, None).unwrap().is_some());
+    }
+
+    #[test]
+    fn cost_optimization() {
+        let domain = "(define (domain delivery)
+          (:requirements :strips :action-costs)
+          (:predicates (delivered))
+          (:functions (total-cost) - number)
+          (:action truck
+            :parameters ()
+            :precondition ()
+            :effect (and (delivered) (increase (total-cost) 2)))
+          (:action plane
+            :parameters ()
+            :precondition ()
+            :effect (and (delivered) (increase (total-cost) 10))))";
+        let problem = "(define (problem delivery-1)
+          (:domain delivery)
+          (:init (= (total-cost) 0))
+          (:goal (delivered))
+          (:metric minimize (total-cost)))";
+        let dom = parse_pddl_domain(Input::from_string(domain)).unwrap();
+        let prob = parse_pddl_problem(Input::from_string(problem)).unwrap();
+        let spec = pddl_to_chronicles(&dom, &prob).unwrap();
+        let costs: Vec<_> = spec.templates.iter().map(|t| t.chronicle.cost).collect();
+        assert_eq!(costs, vec![Some(2), Some(10)]);
+
+        let pb = finite_problem(&spec, 1, false).unwrap();
+        let solution = solve(&pb, Some(Objective::Cost), 999999, None)
+            .unwrap()
+            .expect("No solution found");
+        let steps = plan_steps(&pb, &solution);
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0].action, "truck");
+    }
+
+    #[test]
+    fn parallel_solving() {
+        let dom = parse_pddl_domain(Input::from_string(DOMAIN)).unwrap();
+        let prob = parse_pddl_problem(Input::from_string(PROBLEM)).unwrap();
+        let spec = pddl_to_chronicles(&dom, &prob).unwrap();
+        let opt = Opt::from_iter(&["lcp", "--jobs", "2", "problem.pddl"]);
+        let (pb, solution) = find_plan(&spec, false, &opt, 999999)
+            .unwrap()
+            .expect("No solution found");
+        // no plan with 0 actions, the plan must come from the problem with a single action instance
+        let instances = pb
+            .chronicles
+            .iter()
+            .filter(|c| matches!(c.origin, ChronicleOrigin::FreeAction { .. }))
+            .count();
+        assert_eq!(instances, 1);
+        let steps = plan_steps(&pb, &solution);
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0].action, "turn-on");
+    }
+
+    #[test]
+    fn selected_instantiation() {
+        let dom = parse_pddl_domain(Input::from_string(ROOMS_DOMAIN)).unwrap();
+        let prob = parse_pddl_problem(Input::from_string(ROOMS_PROBLEM)).unwrap();
+        let spec = pddl_to_chronicles(&dom, &prob).unwrap();
+        assert_eq!(spec.templates.len(), 2);
+
+        let mut counts = HashMap::new();
+        counts.insert("unlock".to_string(), 2);
+        let pb = instantiate_selected(&spec, &counts).unwrap();
+        assert_eq!(pb.chronicles.len(), spec.chronicles.len() + 2);
+        let instantiated: Vec<_> = pb
+            .chronicles
+            .iter()
+            .filter_map(|c| match c.origin {
+                ChronicleOrigin::FreeAction { template_id, .. } => spec.templates[template_id].label.as_deref(),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(instantiated, vec!["unlock", "unlock"]);
+    }
+
+    #[test]
+    fn only_templates_option() {
+        let dom = parse_pddl_domain(Input::from_string(ROOMS_DOMAIN)).unwrap();
+        let prob = parse_pddl_problem(Input::from_string(ROOMS_PROBLEM)).unwrap();
+        let spec = pddl_to_chronicles(&dom, &prob).unwrap();
+
+        let opt = Opt::from_iter(&["lcp", "--only-templates", "move=1,unlock=1", "problem.pddl"]);
+        let (pb, solution) = find_plan(&spec, false, &opt, 999999)
+            .unwrap()
+            .expect("No solution found");
+        let mut actions: Vec<String> = plan_steps(&pb, &solution).into_iter().map(|s| s.action).collect();
+        actions.sort();
+        assert_eq!(actions, vec!["move", "unlock"]);
+
+        // the door cannot be opened without an unlock action
+        let opt = Opt::from_iter(&["lcp", "--only-templates", "move=2", "problem.pddl"]);
+        assert!(find_plan(&spec, false, &opt, 999999).unwrap().is_none());
+
+        let opt = Opt::from_iter(&["lcp", "--only-templates", "jump=1", "problem.pddl"]);
+        assert!(find_plan(&spec, false, &opt, 999999).is_err());
+        assert!(Opt::from_iter_safe(&["lcp", "--only-templates", "move", "problem.pddl"]).is_err());
+    }
+
+    #[test]
+    fn disjunctive_goal() {
+        // being in both rooms is unachievable, the plan must be the one reaching the second disjunct
+        let problem = ROOMS_PROBLEM.replace("(:goal (at r2))", "(:goal (or (and (at r1) (at r2)) (at r2)))");
+        let dom = parse_pddl_domain(Input::from_string(ROOMS_DOMAIN)).unwrap();
+        let prob = parse_pddl_problem(Input::from_string(problem)).unwrap();
+        let alternatives = goal_alternatives(&prob);
+        assert_eq!(alternatives.len(), 2);
+        let opt = Opt::from_iter(&["lcp", "--max-actions", "4", "problem.pddl"]);
+        let (pb, solution) = find_plan_for_goal_alternatives(&dom, &alternatives, false, &opt, 999999)
+            .unwrap()
+            .expect("No solution found");
+        let mut actions: Vec<String> = plan_steps(&pb, &solution).into_iter().map(|s| s.action).collect();
+        actions.sort();
+        assert_eq!(actions, vec!["move", "unlock"]);
+
+        // without a bound on the number of actions, the first alternative would be searched forever
+        let opt = Opt::from_iter(&["lcp", "problem.pddl"]);
+        assert!(find_plan_for_goal_alternatives(&dom, &alternatives, false, &opt, 999999).is_err());
+    }
+
+    /// Formats the atom independently of the order in which its variables were created: the model writes
+    /// `a <= b` as `!(b + 1 <= a)` if `b` was created before `a`, and may order the arguments of an expression
+    /// by variable.
+    fn canonical(model: &Model, atom: Atom) -> String {
+        let (expr, negated) = match atom {
+            Atom::Bool(BAtom::Expr(BExpr { expr, negated })) => (model.expressions.get(expr), negated),
+            _ => return model.fmt(atom).to_string(),
+        };
+        if let (Fun::Leq, &[Atom::Int(a), Atom::Int(b)]) = (expr.fun, expr.args.as_slice()) {
+            // `x - y <= d` with `x` and `y` ordered by label
+            let (x, y, d) = if negated {
+                (b, a, a.shift - b.shift - 1)
+            } else {
+                (a, b, b.shift - a.shift)
+            };
+            let x = model.fmt(IAtom { var: x.var, shift: 0 }).to_string();
+            let y = model.fmt(IAtom { var: y.var, shift: 0 }).to_string();
+            return if x <= y {
+                format!("(<= (- {} {}) {})", x, y, d)
+            } else {
+                format!("(>= (- {} {}) {})", y, x, -d)
+            };
+        }
+        let mut args: Vec<String> = expr.args.iter().map(|&arg| canonical(model, arg)).collect();
+        if matches!(expr.fun, Fun::Or | Fun::Eq) {
+            args.sort();
+        }
+        let formatted = format!("({} {})", expr.fun, args.join(" "));
+        if negated {
+            format!("(not {})", formatted)
+        } else {
+            formatted
+        }
+    }
+
+    #[test]
+    fn incremental_encoding() {
+        let dom = parse_pddl_domain(Input::from_string(ROOMS_DOMAIN)).unwrap();
+        let prob = parse_pddl_problem(Input::from_string(ROOMS_PROBLEM)).unwrap();
+        let spec = pddl_to_chronicles(&dom, &prob).unwrap();
+        let formatted = |(model, constraints): (Model, Vec<BAtom>)| {
+            let mut constraints: Vec<String> = constraints.iter().map(|&c| canonical(&model, c.into())).collect();
+            constraints.sort();
+            constraints
+        };
+
+        let mut incremental = Encoding::with_template_instances(&spec, 1, 999999).unwrap();
+        incremental.add_template_instances(&spec, 1).unwrap();
+        let incremental = formatted(incremental.encoded().unwrap());
+        let from_scratch = formatted(
+            Encoding::with_template_instances(&spec, 2, 999999)
+                .unwrap()
+                .encoded()
+                .unwrap(),
+        );
+        assert_eq!(incremental, from_scratch);
+
+        // same constraints as when encoding all chronicles at once
+        let all_at_once = formatted(
+            Encoding::new(finite_problem(&spec, 2, false).unwrap(), 999999)
+                .unwrap()
+                .encoded()
+                .unwrap(),
+        );
+        assert_eq!(incremental, all_at_once);
+    }
+
+    #[test]
+    fn pinned_instances() {
+        let domain = "(define (domain blocks)
+          (:requirements :strips)
+          (:predicates (on ?x ?y))
+          (:action stack
+            :parameters (?x ?y)
+            :precondition ()
+            :effect (on ?x ?y)))";
+        let problem = "(define (problem blocks-1)
+          (:domain blocks)
+          (:objects a b)
+          (:init)
+          (:goal (on a b)))";
+        let dom = parse_pddl_domain(Input::from_string(domain)).unwrap();
+        let prob = parse_pddl_problem(Input::from_string(problem)).unwrap();
+        let spec = pddl_to_chronicles(&dom, &prob).unwrap();
+        let mut pb = finite_problem(&spec, 2, false).unwrap();
+
+        // force the last instance to be present, stacking `b` on `a` which is not needed to achieve the goal
+        let instance = pb.chronicles.len() - 1;
+        assert!(matches!(
+            pb.chronicles[instance].origin,
+            ChronicleOrigin::FreeAction { .. }
+        ));
+        pb.force_present(instance).unwrap();
+        for (arg, name) in [(1, "b"), (2, "a")].iter() {
+            let param = pb.chronicles[instance].chronicle.name[*arg];
+            let param_index = pb.chronicles[instance]
+                .parameters
+                .iter()
+                .position(|&p| p == Atom::from(param))
+                .unwrap();
+            let value = pb.model.symbols.id(*name).unwrap().int_value();
+            pb.fix_parameter(instance, param_index, value).unwrap();
+        }
+
+        let solution = solve(&pb, None, 999999, None).unwrap().expect("No solution found");
+        assert_eq!(validate_solution(&pb, &solution), Ok(()));
+        let mut actions: Vec<_> = plan_steps(&pb, &solution)
+            .into_iter()
+            .map(|step| format!("{} {}", step.action, step.args.join(" ")))
+            .collect();
+        actions.sort();
+        assert_eq!(actions, vec!["stack a b", "stack b a"]);
+    }
+
+    #[test]
+    fn causal_structure() {
+        let dom = parse_pddl_domain(Input::from_string(ROOMS_DOMAIN)).unwrap();
+        let prob = parse_pddl_problem(Input::from_string(ROOMS_PROBLEM)).unwrap();
+        let spec = pddl_to_chronicles(&dom, &prob).unwrap();
+        let pb = finite_problem(&spec, 1, false).unwrap();
+        let solution = solve(&pb, None, 999999, None).unwrap().expect("No solution found");
+
+        // (at r1) and (open r2) for the move, and the goal (at r2)
+        let links = causal_links(&pb, &solution);
+        assert_eq!(links.len(), 3);
+        let name = |instance_id: usize| {
+            let ch = &pb.chronicles[instance_id].chronicle;
+            match ch.kind {
+                ChronicleKind::Action => solution
+                    .symbols()
+                    .symbol(solution.sym_value_of(ch.name[0]).unwrap())
+                    .to_string(),
+                _ => format!("{:?}", ch.kind),
+            }
+        };
+        let mut links: Vec<(String, String)> = links
+            .iter()
+            .map(|(eff, cond)| (name(eff.instance_id), name(cond.instance_id)))
+            .collect();
+        links.sort();
+        let expected = [("Problem", "move"), ("move", "Problem"), ("unlock", "move")];
+        let expected: Vec<(String, String)> = expected.iter().map(|(a, b)| (a.to_string(), b.to_string())).collect();
+        assert_eq!(links, expected);
+    }
+
+    #[test]
+    fn lexicographic_symmetry_breaking() {
+        let domain = "(define (domain blocks)
+          (:requirements :strips)
+          (:predicates (on ?x ?y))
+          (:action stack
+            :parameters (?x ?y)
+            :precondition ()
+            :effect (on ?x ?y)))";
+        let problem = "(define (problem blocks-1)
+          (:domain blocks)
+          (:objects a b)
+          (:init)
+          (:goal (on a b)))";
+        let dom = parse_pddl_domain(Input::from_string(domain)).unwrap();
+        let prob = parse_pddl_problem(Input::from_string(problem)).unwrap();
+        let spec = pddl_to_chronicles(&dom, &prob).unwrap();
+        let pb = finite_problem(&spec, 2, false).unwrap();
+        let mut model = pb.model.clone();
+
+        let mut simple = Vec::new();
+        add_symmetry_breaking(&pb, &mut model, &mut simple, SymmetryBreakingType::Simple).unwrap();
+        // presence implication and ordering of start times
+        assert_eq!(simple.len(), 2);
+
+        let mut lex = Vec::new();
+        add_symmetry_breaking(&pb, &mut model, &mut lex, SymmetryBreakingType::Lexicographic).unwrap();
+        // presence implication and one ordering constraint for each of ?x, ?y and the start time
+        assert_eq!(lex.len(), 4);
+        let instances: Vec<_> = pb
+            .chronicles
+            .iter()
+            .filter(|c| matches!(c.origin, ChronicleOrigin::FreeAction { .. }))
+            .collect();
+        assert_eq!(instances.len(), 2);
+        let (x1, x2) = (instances[0].chronicle.name[1], instances[1].chronicle.name[1]);
+        assert_eq!(
+            lex[0],
+            model.implies(instances[0].chronicle.presence, instances[1].chronicle.presence)
+        );
+        assert_eq!(lex[1], model.leq(x1.int_view(), x2.int_view()));
+    }
+}
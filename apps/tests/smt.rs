@@ -1,8 +1,16 @@
+use aries_backtrack::{Backtrack, DecLvl};
 use aries_model::assignments::Assignment;
-use aries_model::lang::{BAtom, IVar};
+use aries_model::bounds::Bound;
+use aries_model::int_model::Cause;
+use aries_model::lang::{BAtom, BVar, IVar, IntCst, Type, INT_CST_MAX};
+use aries_model::table::Table;
 use aries_model::Model;
-use aries_solver::solver::Solver;
+use aries_solver::solver::{ProbeResult, SolveLimit, SolveOutcome, Solver, TraceEvent};
 use aries_tnet::stn::IncSTN;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+use std::time::Instant;
 
 #[test]
 fn sat() {
@@ -25,6 +33,474 @@ fn sat() {
     assert!(!solver.solve());
 }
 
+#[test]
+fn iff() {
+    let mut model = Model::new();
+    let a = model.new_bvar("a");
+    let b = model.new_bvar("b");
+    assert_eq!(model.iff(a, a), BAtom::Cst(true));
+    assert_eq!(model.iff(a, !BAtom::from(a)), BAtom::Cst(false));
+
+    let mut solver = Solver::new(model);
+    solver.enforce_iff(a, b);
+    solver.enforce(a);
+    assert!(solver.solve());
+    assert_eq!(solver.model.boolean_value_of(b), Some(true));
+
+    solver.enforce_iff(b, !BAtom::from(a));
+    assert!(!solver.solve());
+}
+
+#[test]
+fn optional_variables() {
+    let mut model = Model::new();
+    let p = model.new_bvar("p");
+    let x = model.new_optional_ivar(0, 10, p, "x");
+    let y = model.new_ivar(0, 10, "y");
+    assert_eq!(model.presence_of(x), BAtom::from(p));
+    assert_eq!(model.presence_of(y), BAtom::Cst(true));
+
+    // constraints without optional variables are left untouched
+    let y_neg = model.leq(y, -1);
+    assert_eq!(model.guarded(y_neg), y_neg);
+
+    // x cannot be negative: the constraint can only hold if x is absent
+    let x_neg = model.leq(x, -1);
+    let x_neg_or_absent = model.guarded(x_neg);
+    assert_eq!(x_neg_or_absent, model.implies(p, x_neg));
+
+    let mut solver = Solver::new(model);
+    solver.enforce(x_neg_or_absent);
+    assert!(solver.solve());
+    assert_eq!(solver.model.boolean_value_of(p), Some(false));
+
+    solver.enforce(p);
+    assert!(!solver.solve());
+}
+
+#[test]
+fn minimize_doubling() {
+    // minimize the end of a chain of three tasks with fixed durations, where the first one cannot start before 3
+    let problem = || {
+        let mut model = Model::new();
+        let starts: Vec<IVar> = (0..3).map(|i| model.new_ivar(0, 1000, format!("s{}", i))).collect();
+        let end = model.new_ivar(0, 1000, "end");
+        let mut constraints = vec![model.leq(3, starts[0])];
+        for (i, &duration) in [5, 2, 9].iter().enumerate() {
+            let next = if i + 1 < starts.len() { starts[i + 1] } else { end };
+            constraints.push(model.leq(starts[i] + duration, next));
+        }
+        let theory = IncSTN::new(model.new_write_token());
+        let mut solver = Solver::new(model);
+        solver.add_theory(Box::new(theory));
+        solver.enforce_all(&constraints);
+        (solver, end)
+    };
+
+    let (mut solver, end) = problem();
+    let (optimum, _) = solver.minimize(end).unwrap();
+    assert_eq!(optimum, 19);
+
+    let (mut solver, end) = problem();
+    let (value, solution) = solver.minimize_doubling(end).unwrap();
+    assert_eq!(value, optimum);
+    assert_eq!(solution.domain_of(end), (optimum, optimum));
+    // the shift of the objective is taken into account
+    let (value, _) = solver.minimize_doubling(end + 10).unwrap();
+    assert_eq!(value, optimum + 10);
+
+    // an unsatisfiable problem has no optimum
+    let (mut solver, end) = problem();
+    let impossible = solver.model.leq(end, 10);
+    solver.enforce(impossible);
+    assert!(solver.minimize_doubling(end).is_none());
+}
+
+#[test]
+fn maxsat() {
+    let mut model = Model::new();
+    let a = model.new_bvar("a");
+    let b = model.new_bvar("b");
+    let mut solver = Solver::new(model);
+
+    // two conflicting soft constraints: exactly one of them can be satisfied
+    solver.add_soft(a, 1);
+    solver.add_soft(!BAtom::from(a), 1);
+    let (cost, solution) = solver.solve_maxsat().unwrap();
+    assert_eq!(cost, 1);
+    assert!(solution.boolean_value_of(a).is_some());
+
+    // the heavier soft constraint is preferred over the lighter one it conflicts with
+    solver.add_clause(&[!b.true_lit(), !a.true_lit()]);
+    solver.add_soft(b, 3);
+    let (cost, solution) = solver.solve_maxsat().unwrap();
+    assert_eq!(cost, 1);
+    assert_eq!(solution.boolean_value_of(b), Some(true));
+    assert_eq!(solution.boolean_value_of(a), Some(false));
+
+    // hard constraints are never violated
+    solver.enforce(a);
+    let (cost, solution) = solver.solve_maxsat().unwrap();
+    assert_eq!(cost, 4);
+    assert_eq!(solution.boolean_value_of(a), Some(true));
+    assert_eq!(solution.boolean_value_of(b), Some(false));
+
+    solver.enforce(!BAtom::from(a));
+    assert!(solver.solve_maxsat().is_none());
+}
+
+#[test]
+fn maxsat_large_weights() {
+    let mut model = Model::new();
+    let vars: Vec<_> = (0..10).map(|i| model.new_bvar(format!("x{}", i))).collect();
+    let mut solver = Solver::new(model);
+
+    // the size of the encoding does not depend on the magnitude of the weights
+    for &x in &vars {
+        solver.add_soft(x, 1_000_000);
+        solver.add_soft(!BAtom::from(x), 1);
+    }
+    let (cost, solution) = solver.solve_maxsat().unwrap();
+    assert_eq!(cost, 10);
+    for &x in &vars {
+        assert_eq!(solution.boolean_value_of(x), Some(true));
+    }
+}
+
+#[test]
+fn maxsat_stratified() {
+    let mut model = Model::new();
+    let vars: Vec<_> = (0..20).map(|i| model.new_bvar(format!("x{}", i))).collect();
+    let mut solver = Solver::new(model);
+
+    // the 2^20 subsets of the weights have distinct totals, forcing the stratification by weight
+    for (i, &x) in vars.iter().enumerate() {
+        solver.add_soft(x, 1 << i);
+    }
+    for pair in vars.windows(2) {
+        solver.add_clause(&[!pair[0].true_lit(), !pair[1].true_lit()]);
+    }
+    let (cost, solution) = solver.solve_maxsat().unwrap();
+    // each weight exceeds the total of the lighter ones: only the odd variables are set
+    let expected: IntCst = (0..20).step_by(2).map(|i| 1 << i).sum();
+    assert_eq!(cost, expected);
+    for (i, &x) in vars.iter().enumerate() {
+        assert_eq!(solution.boolean_value_of(x), Some(i % 2 == 1));
+    }
+}
+
+#[test]
+#[should_panic]
+fn maxsat_weight_overflow() {
+    let mut model = Model::new();
+    let a = model.new_bvar("a");
+    let mut solver = Solver::new(model);
+    solver.add_soft(a, INT_CST_MAX as u64);
+    solver.add_soft(!BAtom::from(a), INT_CST_MAX as u64);
+    solver.solve_maxsat();
+}
+
+#[test]
+fn in_table() {
+    let mut model = Model::new();
+    let x = model.new_ivar(0, 10, "x");
+    let y = model.new_ivar(0, 10, "y");
+    let mut table = Table::new(vec![Type::Int, Type::Int]);
+    table.push(&[1, 2]);
+    table.push(&[3, 4]);
+    let in_table = model.in_table(&[x.into(), y.into()], &table);
+    let x_is_3 = model.eq(x, 3);
+    let y_is_4 = model.eq(y, 4);
+    let y_is_2 = model.eq(y, 2);
+
+    let theory = IncSTN::new(model.new_write_token());
+    let mut solver = Solver::new(model);
+    solver.add_theory(Box::new(theory));
+    // (3, 4) is the only line of the table with x = 3
+    solver.push();
+    solver.enforce_all(&[in_table, x_is_3]);
+    assert!(solver.solve());
+    assert_eq!(solver.model.bounds(y), (4, 4));
+    solver.enforce(y_is_2);
+    assert!(!solver.solve());
+    solver.pop();
+
+    // the reified literal is false for any other value of y
+    solver.push();
+    solver.enforce_all(&[!in_table, x_is_3]);
+    assert!(solver.solve());
+    assert_ne!(solver.model.bounds(y), (4, 4));
+    solver.enforce(y_is_4);
+    assert!(!solver.solve());
+    solver.pop();
+}
+
+#[test]
+fn clauses() {
+    let mut model = Model::new();
+    let a = model.new_bvar("a");
+    let b = model.new_bvar("b");
+    let mut solver = Solver::new(model);
+    // a => b
+    solver.add_clause(&[a.false_lit(), b.true_lit()]);
+    solver.enforce(a);
+    assert!(solver.solve());
+    assert_eq!(solver.model.boolean_value_of(b), Some(true));
+    assert!(solver.solve_under(&[b.false_lit()]).is_err());
+}
+
+#[test]
+fn learned_clauses_reuse() {
+    // pigeon hole problem: 4 pigeons in 3 holes
+    let mut model = Model::new();
+    let clauses = pigeon_hole(&mut model, 4, 3);
+
+    let mut solver = Solver::new(model.clone());
+    for clause in &clauses {
+        solver.add_clause(clause);
+    }
+    assert!(!solver.solve());
+    let learned: Vec<Vec<Bound>> = solver.learned_clauses().collect();
+    assert!(!learned.is_empty());
+
+    // learned clauses are implied by the problem and can be added to another solver
+    let mut solver = Solver::new(model);
+    for clause in clauses.iter().chain(learned.iter()) {
+        solver.add_clause(clause);
+    }
+    assert!(!solver.solve());
+}
+
+/// Clauses of the pigeon hole problem, stating that each pigeon is in a hole and no two pigeons share a hole.
+fn pigeon_hole(model: &mut Model, pigeons: usize, holes: usize) -> Vec<Vec<Bound>> {
+    let in_hole: Vec<Vec<BVar>> = (0..pigeons)
+        .map(|p| (0..holes).map(|h| model.new_bvar(format!("p{}_h{}", p, h))).collect())
+        .collect();
+    let mut clauses = Vec::new();
+    for pigeon in &in_hole {
+        clauses.push(pigeon.iter().map(|v| v.true_lit()).collect::<Vec<_>>());
+    }
+    for (i, p1) in in_hole.iter().enumerate() {
+        for p2 in &in_hole[i + 1..] {
+            // p1 and p2 are not in the same hole
+            for (x, y) in p1.iter().zip(p2) {
+                clauses.push(vec![x.false_lit(), y.false_lit()]);
+            }
+        }
+    }
+    clauses
+}
+
+#[test]
+fn clause_db_policy() {
+    let mut model = Model::new();
+    let clauses = pigeon_hole(&mut model, 8, 7);
+
+    let mut unbounded = Solver::new(model.clone());
+    let mut bounded = Solver::new(model);
+    bounded.set_clause_db_policy(50, 0.5);
+    for solver in &mut [&mut unbounded, &mut bounded] {
+        for clause in &clauses {
+            solver.add_clause(clause);
+        }
+    }
+    // a deadline in the past interrupts the search on the first restart, after the database reduction
+    let first_restart = SolveLimit {
+        max_conflicts: None,
+        deadline: Some(Instant::now()),
+    };
+    for _ in 0..3 {
+        assert_eq!(unbounded.solve_with_limit(first_restart), SolveOutcome::Unknown);
+        assert_eq!(bounded.solve_with_limit(first_restart), SolveOutcome::Unknown);
+        assert!(bounded.learned_clauses().count() <= 50);
+    }
+    assert!(unbounded.learned_clauses().count() > 50);
+
+    assert!(!unbounded.solve());
+    assert!(!bounded.solve());
+}
+
+#[test]
+fn sat_under_assumptions() {
+    let mut model = Model::new();
+    let a = model.new_bvar("a");
+    let b = model.new_bvar("b");
+    let c = model.new_bvar("c");
+    let d = model.new_bvar("d");
+
+    // !(a & b) and d => c
+    let a_and_b = model.and2(a.into(), b.into());
+    let d_implies_c = model.implies(d, c);
+    let mut solver = Solver::new(model);
+    solver.enforce_all(&[!a_and_b, d_implies_c]);
+
+    assert_eq!(solver.solve_under(&[a.true_lit(), c.true_lit()]), Ok(()));
+    assert_eq!(solver.model.boolean_value_of(a), Some(true));
+    assert_eq!(solver.model.boolean_value_of(b), Some(false));
+
+    // a and b cannot hold together, c and d are not part of the conflict
+    match solver.solve_under(&[c.true_lit(), a.true_lit(), d.true_lit(), b.true_lit()]) {
+        Ok(()) => panic!("Expected a conflict"),
+        Err(core) => {
+            let core: HashSet<_> = core.into_iter().collect();
+            let expected: HashSet<_> = vec![a.true_lit(), b.true_lit()].into_iter().collect();
+            assert_eq!(core, expected);
+        }
+    }
+    // contradictory assumptions on the same variable
+    match solver.solve_under(&[c.true_lit(), a.true_lit(), a.false_lit()]) {
+        Ok(()) => panic!("Expected a conflict"),
+        Err(core) => {
+            let core: HashSet<_> = core.into_iter().collect();
+            let expected: HashSet<_> = vec![a.true_lit(), a.false_lit()].into_iter().collect();
+            assert_eq!(core, expected);
+        }
+    }
+
+    // assumptions are not permanent
+    assert_eq!(solver.solve_under(&[b.true_lit()]), Ok(()));
+    assert!(solver.solve());
+
+    // not satisfiable, independently of the assumptions
+    solver.enforce(!c);
+    solver.enforce(d);
+    assert_eq!(solver.solve_under(&[a.true_lit()]), Err(Vec::new()));
+}
+
+#[test]
+fn enumerate() {
+    let mut model = Model::new();
+    let a = model.new_bvar("a");
+    let b = model.new_bvar("b");
+    let c = model.new_bvar("c");
+
+    let mut solver = Solver::new(model);
+    let mut solutions = HashSet::new();
+    solver.enumerate(&[a, b], |sol| {
+        // each projection on (a, b) should only be found once, regardless of the value of c
+        assert!(sol.boolean_value_of(c).is_some());
+        assert!(solutions.insert((sol.boolean_value_of(a), sol.boolean_value_of(b))));
+        true
+    });
+    assert_eq!(solutions.len(), 4);
+
+    // stop the enumeration on the first solution
+    let mut model = Model::new();
+    let a = model.new_bvar("a");
+    let mut solver = Solver::new(model);
+    let mut count = 0;
+    solver.enumerate(&[a], |_| {
+        count += 1;
+        false
+    });
+    assert_eq!(count, 1);
+    assert!(solver.solve());
+}
+
+#[test]
+fn solve_with_limit() {
+    // pigeon hole problem: n+1 pigeons cannot fit in n holes
+    let n = 7;
+    let mut model = Model::new();
+    let mut constraints = Vec::new();
+    let in_hole: Vec<Vec<BAtom>> = (0..=n)
+        .map(|p| (0..n).map(|h| model.new_bvar(format!("p{}h{}", p, h)).into()).collect())
+        .collect();
+    for holes in &in_hole {
+        constraints.push(model.or(holes));
+    }
+    for h in 0..n {
+        for (p1, holes1) in in_hole.iter().enumerate() {
+            for holes2 in &in_hole[(p1 + 1)..] {
+                constraints.push(model.or2(!holes1[h], !holes2[h]));
+            }
+        }
+    }
+    let mut solver = Solver::new(model);
+    solver.enforce_all(&constraints);
+    let limit = SolveLimit {
+        max_conflicts: Some(10),
+        deadline: None,
+    };
+    assert_eq!(solver.solve_with_limit(limit), SolveOutcome::Unknown);
+    assert!(solver.stats.num_conflicts >= 10);
+
+    // an expired deadline is only noticed on restarts, so the search will eventually stop
+    let limit = SolveLimit {
+        max_conflicts: None,
+        deadline: Some(Instant::now()),
+    };
+    assert_eq!(solver.solve_with_limit(limit), SolveOutcome::Unknown);
+
+    // no limit, the search is carried to the end
+    assert_eq!(solver.solve_with_limit(SolveLimit::default()), SolveOutcome::Unsat);
+}
+
+#[test]
+fn initial_guess() {
+    let mut model = Model::new();
+    let a = model.new_bvar("a");
+    let b = model.new_bvar("b");
+    let a_or_b = model.or2(a.into(), b.into());
+
+    for &(forced, other) in &[(a, b), (b, a)] {
+        // solution of a first problem, where one of the variables is enforced
+        let mut solver = Solver::new(model.clone());
+        solver.enforce_all(&[a_or_b, forced.into()]);
+        assert!(solver.solve());
+        let previous = solver.model.clone();
+        assert_eq!(previous.boolean_value_of(forced), Some(true));
+        assert_eq!(previous.boolean_value_of(other), Some(false));
+
+        // warm-started from the previous solution, the same values are tried first
+        let mut solver = Solver::new(model.clone());
+        solver.enforce(a_or_b);
+        solver.set_initial_guess(&previous);
+        assert!(solver.solve());
+        assert_eq!(solver.model.boolean_value_of(forced), Some(true));
+        assert_eq!(solver.model.boolean_value_of(other), Some(false));
+    }
+}
+
+#[test]
+fn push_pop() {
+    let mut model = Model::new();
+    let a = model.new_bvar("a");
+    let b = model.new_ivar(0, 10, "b");
+    let c = model.new_ivar(0, 10, "c");
+    let b_before_c = model.lt(b, c);
+    let c_before_b = model.lt(c, b);
+    let b_late = model.geq(b, 5);
+
+    let theory = IncSTN::new(model.new_write_token());
+    let mut solver = Solver::new(model);
+    solver.add_theory(Box::new(theory));
+    solver.enforce_all(&[a.into(), b_before_c]);
+    assert!(solver.solve());
+
+    solver.push();
+    solver.enforce(!BAtom::from(a));
+    assert!(!solver.solve());
+    solver.pop();
+    assert!(solver.solve());
+    assert_eq!(solver.model.boolean_value_of(a), Some(true));
+
+    // nested levels
+    solver.push();
+    solver.enforce(b_late);
+    assert!(solver.solve());
+    assert!(solver.model.domain_of(b).0 >= 5);
+    solver.push();
+    solver.enforce(c_before_b);
+    assert!(!solver.solve());
+    solver.pop();
+    assert!(solver.solve());
+    assert!(solver.model.domain_of(b).0 >= 5);
+    solver.pop();
+    assert!(solver.solve());
+    assert!(solver.model.domain_of(b).0 < solver.model.domain_of(c).0);
+}
+
 #[test]
 fn diff_logic() {
     let mut model = Model::new();
@@ -40,6 +516,148 @@ fn diff_logic() {
     solver.add_theory(Box::new(theory));
     solver.enforce_all(&constraints);
     assert!(!solver.solve());
+
+    let stats = solver.theory_stats();
+    assert_eq!(stats.len(), 1);
+    assert_eq!(stats[0].num_bindings, 3);
+    assert!(stats[0].num_propagations > 0);
+    assert!(stats[0].num_conflicts > 0);
+}
+
+#[test]
+fn diff_logic_large_bounds() {
+    // values and distances beyond the range of 32 bits integers
+    let horizon: IntCst = 10_000_000_000;
+    let mut model = Model::new();
+    let a = model.new_ivar(0, horizon, "a");
+    let b = model.new_ivar(0, horizon, "b");
+
+    let constraints = vec![model.geq(a, 3_000_000_000), model.leq(a + 4_000_000_000, b)];
+    let too_early = model.leq(b, 6_999_999_999);
+
+    let theory = IncSTN::new(model.new_write_token());
+    let mut solver = Solver::new(model.clone());
+    solver.add_theory(Box::new(theory));
+    solver.enforce_all(&constraints);
+    let (value, solution) = solver.minimize(b).unwrap();
+    assert_eq!(value, 7_000_000_000);
+    assert_eq!(solution.domain_of(b), (7_000_000_000, 7_000_000_000));
+    assert!(solution.domain_of(a).0 >= 3_000_000_000);
+
+    let theory = IncSTN::new(model.new_write_token());
+    let mut solver = Solver::new(model);
+    solver.add_theory(Box::new(theory));
+    solver.enforce_all(&constraints);
+    solver.enforce(too_early);
+    assert!(!solver.solve());
+}
+
+#[test]
+fn diff_logic_explanation() {
+    let mut model = Model::new();
+    let a = model.new_ivar(0, 10, "a");
+    let b = model.new_ivar(0, 10, "b");
+    let x = model.new_bvar("x");
+    let y = model.new_bvar("y");
+    let z = model.new_bvar("z");
+
+    // x => a + 3 <= b, which lets the STN infer b >= 3
+    let delay = model.leq(a + 3, b);
+    let x_implies_delay = model.implies(x, delay);
+    // y => b <= 2, handled by the SAT solver
+    let early = model.leq(b, 2);
+    let y_implies_early = model.implies(y, early);
+    // z => a <= 5, unrelated to the conflict
+    let bounded = model.leq(a, 5);
+    let z_implies_bounded = model.implies(z, bounded);
+
+    let theory = IncSTN::new(model.new_write_token());
+    let mut solver = Solver::new(model);
+    solver.add_theory(Box::new(theory));
+    solver.enforce_all(&[x_implies_delay, y_implies_early, z_implies_bounded]);
+
+    // the conflict on b can only be explained through the bound inferred by the STN from the edge enabled by x
+    match solver.solve_under(&[z.true_lit(), x.true_lit(), y.true_lit()]) {
+        Ok(()) => panic!("Expected a conflict"),
+        Err(core) => {
+            let core: HashSet<_> = core.into_iter().collect();
+            let expected: HashSet<_> = vec![x.true_lit(), y.true_lit()].into_iter().collect();
+            assert_eq!(core, expected);
+        }
+    }
+    assert_eq!(solver.solve_under(&[x.true_lit(), z.true_lit()]), Ok(()));
+}
+
+#[test]
+fn unsat_core() {
+    let mut model = Model::new();
+    let a = model.new_ivar(0, 10, "a");
+    let b = model.new_ivar(0, 10, "b");
+    let c = model.new_ivar(0, 10, "c");
+    let d = model.new_ivar(0, 10, "d");
+
+    let constraints = vec![
+        model.lt(a, d),
+        model.lt(a, b),
+        model.leq(d, 5),
+        model.leq(b, c),
+        model.lt(c, a),
+        model.leq(c, d),
+    ];
+
+    let theory = IncSTN::new(model.new_write_token());
+    let mut solver = Solver::new(model.clone());
+    solver.add_theory(Box::new(theory));
+    solver.enforce_all_tracked(&constraints);
+    assert!(!solver.solve());
+
+    let core = solver.unsat_core();
+    assert!(!core.is_empty());
+    assert!(core.iter().all(|c| constraints.contains(c)));
+    // the cycle a < b <= c < a is the only source of inconsistency
+    assert!(core.contains(&constraints[1]));
+    assert!(core.contains(&constraints[3]));
+    assert!(core.contains(&constraints[4]));
+
+    // the core itself should be unsatisfiable
+    let theory = IncSTN::new(model.new_write_token());
+    let mut solver = Solver::new(model);
+    solver.add_theory(Box::new(theory));
+    solver.enforce_all(&core);
+    assert!(!solver.solve());
+}
+
+#[test]
+fn unsat_core_push_pop() {
+    let mut model = Model::new();
+    let a = model.new_ivar(0, 10, "a");
+    let b = model.new_ivar(0, 10, "b");
+    let c = model.new_ivar(0, 10, "c");
+
+    let a_before_b = model.lt(a, b);
+    let b_before_c = model.leq(b, c);
+    let c_before_a = model.lt(c, a);
+    let c_with_a = model.leq(c, a);
+
+    let theory = IncSTN::new(model.new_write_token());
+    let mut solver = Solver::new(model);
+    solver.add_theory(Box::new(theory));
+    solver.enforce_all_tracked(&[a_before_b, b_before_c]);
+    assert!(solver.solve());
+
+    solver.push();
+    solver.enforce_all_tracked(&[c_before_a]);
+    assert!(!solver.solve());
+    assert!(solver.unsat_core().contains(&c_before_a));
+    solver.pop();
+
+    // constraints tracked in a popped level are no longer part of the core
+    assert!(solver.unsat_core().is_empty());
+    solver.enforce_all_tracked(&[c_with_a]);
+    assert!(!solver.solve());
+    let core = solver.unsat_core();
+    assert!(core.contains(&c_with_a));
+    assert!(!core.contains(&c_before_a));
 }
 
 #[test]
@@ -89,6 +707,35 @@ fn minimize_small() {
     solver.print_stats()
 }
 
+#[test]
+fn maximize() {
+    let mut model = Model::new();
+    let a = model.new_ivar(0, 10, "a");
+    let b = model.new_ivar(0, 10, "b");
+
+    let x = model.leq(a, 4);
+    let y = model.leq(a, 7);
+
+    let constraints = vec![model.lt(a, b), model.leq(b, 8), model.or2(x, y)];
+    let theory = IncSTN::new(model.new_write_token());
+    let mut solver = Solver::new(model);
+
+    solver.add_theory(Box::new(theory));
+    solver.enforce_all(&constraints);
+    assert!(solver.solve());
+    let mut values = Vec::new();
+    match solver.maximize_with(a, |val, _| values.push(val)) {
+        None => panic!(),
+        Some((val, sol)) => {
+            assert_eq!(val, 7);
+            assert_eq!(sol.domain_of(a), (7, 7));
+        }
+    }
+    // each new solution should strictly improve on the previous one
+    assert!(values.windows(2).all(|w| w[0] < w[1]));
+    assert_eq!(values.last(), Some(&7));
+}
+
 #[test]
 fn int_bounds() {
     let mut model = Model::new();
@@ -213,3 +860,183 @@ fn ints_and_bools() {
     assert_eq!(solver.model.domain_of(ia), (1, 1));
     assert_eq!(solver.model.boolean_value_of(a), Some(true));
 }
+
+#[test]
+fn trace() {
+    let mut model = Model::new();
+    let a = model.new_bvar("a");
+    let b = model.new_bvar("b");
+    let c = model.new_bvar("c");
+
+    let mut solver = Solver::new(model);
+    // any decision on one of the variables is propagated to the other ones
+    solver.enforce_iff(a, b);
+    solver.enforce_iff(b, c);
+
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let sink = events.clone();
+    solver.set_trace(Box::new(move |ev| sink.borrow_mut().push(ev)));
+    assert!(solver.solve());
+
+    let events = events.borrow();
+    let decisions: Vec<Bound> = events
+        .iter()
+        .filter_map(|ev| match ev {
+            TraceEvent::Decision(lit) => Some(*lit),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(decisions.len(), 1);
+    assert!(solver.model.discrete.entails(decisions[0]));
+    let propagated: Vec<Bound> = events
+        .iter()
+        .filter_map(|ev| match ev {
+            TraceEvent::Propagation { lit, .. } => Some(*lit),
+            _ => None,
+        })
+        .collect();
+    assert!(!propagated.is_empty());
+    assert!(propagated.iter().all(|&lit| solver.model.discrete.entails(lit)));
+    // every inference of the final state was reported
+    for ev in solver.model.discrete.trail().events() {
+        if let Cause::Inference(_) = ev.cause {
+            assert!(propagated.contains(&ev.new_literal()));
+        }
+    }
+
+    // pigeon hole problem: 5 pigeons in 4 holes, which requires learning clauses
+    let mut model = Model::new();
+    let clauses = pigeon_hole(&mut model, 5, 4);
+    let mut solver = Solver::new(model);
+    for clause in &clauses {
+        solver.add_clause(clause);
+    }
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let sink = events.clone();
+    solver.set_trace(Box::new(move |ev| sink.borrow_mut().push(ev)));
+    assert!(!solver.solve());
+
+    // the asserting literal of each learnt clause is reported before the next decision or conflict,
+    // even if its propagation immediately leads to another conflict
+    let events = events.borrow();
+    let conflicts: Vec<usize> = (0..events.len())
+        .filter(|&i| matches!(events[i], TraceEvent::Conflict(_)))
+        .collect();
+    assert!(conflicts.len() > 1);
+    // the last conflict is at the root and no clause is learnt from it
+    for &i in &conflicts[..conflicts.len() - 1] {
+        let clause = match &events[i] {
+            TraceEvent::Conflict(clause) => clause,
+            _ => unreachable!(),
+        };
+        let asserted = events[i + 1..]
+            .iter()
+            .take_while(|ev| !matches!(ev, TraceEvent::Decision(_) | TraceEvent::Conflict(_)))
+            .any(|ev| matches!(ev, TraceEvent::Propagation { lit, .. } if clause.contains(lit)));
+        assert!(asserted);
+    }
+}
+
+#[test]
+fn at_most_one() {
+    // below and above the size limit of the pairwise encoding
+    for &n in &[3, 20] {
+        let mut model = Model::new();
+        let vars: Vec<BVar> = (0..n).map(|i| model.new_bvar(format!("x{}", i))).collect();
+        let lits: Vec<BAtom> = vars.iter().map(|&v| v.into()).collect();
+        let amo = model.at_most_one(&lits);
+        let exo = model.exactly_one(&lits);
+        let mut solver = Solver::new(model);
+
+        let check = |solver: &mut Solver, constraint: BAtom, true_vars: &[usize]| -> bool {
+            solver.push();
+            solver.enforce(constraint);
+            for (i, &v) in vars.iter().enumerate() {
+                if true_vars.contains(&i) {
+                    solver.enforce(v);
+                } else {
+                    solver.enforce(!v);
+                }
+            }
+            let sat = solver.solve();
+            solver.pop();
+            sat
+        };
+        let last = n - 1;
+        assert!(check(&mut solver, amo, &[]));
+        assert!(check(&mut solver, amo, &[last]));
+        assert!(!check(&mut solver, amo, &[0, last]));
+        assert!(!check(&mut solver, amo, &[1, 2]));
+        assert!(!check(&mut solver, exo, &[]));
+        assert!(check(&mut solver, exo, &[1]));
+        assert!(!check(&mut solver, exo, &[0, 1, last]));
+    }
+}
+
+#[test]
+fn minimize_with_constraints() {
+    let mut model = Model::new();
+    let x = model.new_ivar(0, 10, "x");
+    let fast = model.new_bvar("fast");
+    // taking the fast option is the only way to have x below 6
+    let geq_2 = model.geq(x, 2);
+    let fast_constraint = model.implies(fast, geq_2);
+    let geq_6 = model.geq(x, 6);
+    let slow_constraint = model.implies(!fast, geq_6);
+    let not_fast: BAtom = (!fast).into();
+
+    let mut solver = Solver::new(model);
+    solver.enforce(fast_constraint);
+    solver.enforce(slow_constraint);
+
+    let mut num_calls = 0;
+    let (cost, sol) = solver
+        .minimize_with_constraints(x, |_, sol| {
+            num_calls += 1;
+            // forbid the fast option whenever it is used
+            if sol.boolean_value_of(fast) == Some(true) {
+                vec![not_fast]
+            } else {
+                Vec::new()
+            }
+        })
+        .unwrap();
+    assert_eq!(cost, 6);
+    assert_eq!(sol.boolean_value_of(fast), Some(false));
+    assert!(num_calls >= 2);
+}
+
+#[test]
+fn probe() {
+    let mut model = Model::new();
+    let a = model.new_bvar("a");
+    let b = model.new_bvar("b");
+    let c = model.new_bvar("c");
+    let d = model.new_bvar("d");
+    let vars = [a, b, c, d];
+
+    // a => b => c, and d requires both b and !c
+    let constraints = [
+        model.implies(a, b),
+        model.implies(b, c),
+        model.implies(d, b),
+        model.implies(d, !c),
+    ];
+    let mut solver = Solver::new(model);
+    solver.enforce_all(&constraints);
+    assert!(solver.propagate_and_backtrack_to_consistent());
+    let num_events = solver.model.discrete.num_events();
+
+    // b, c and !d are implied by a
+    assert_eq!(solver.probe(a.true_lit()), ProbeResult::Implied(3));
+    assert_eq!(solver.probe(c.true_lit()), ProbeResult::Implied(1));
+    assert_eq!(solver.probe(d.true_lit()), ProbeResult::Conflict);
+
+    // the probes left no trace
+    assert_eq!(solver.model.discrete.num_events(), num_events);
+    assert_eq!(solver.current_decision_level(), DecLvl::ROOT);
+    for &v in &vars {
+        assert_eq!(solver.model.boolean_value_of(v), None);
+    }
+    assert!(solver.solve());
+}
@@ -1,5 +1,8 @@
 use itertools::Itertools;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Serializer};
 use std::borrow::Borrow;
+use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use std::fmt::{Debug, Error, Formatter};
 use std::hash::Hash;
@@ -76,9 +79,11 @@ macro_rules! create_ref_type {
 ///
 /// A new key can be obtained by `push`ing a value into the store.
 ///
+/// Values can be removed from the pool. To keep all other keys valid, a removed value leaves
+/// a tombstone in its slot: its key will never be reused and `get` will return `None` for it.
 #[derive(Clone)]
 pub struct RefPool<Key, Val> {
-    internal: Vec<Val>,
+    internal: Vec<Option<Val>>,
     rev: HashMap<Val, Key>,
 }
 impl<K, V: Hash + Eq> Default for RefPool<K, V> {
@@ -91,7 +96,12 @@ impl<K, V: Hash + Eq> Default for RefPool<K, V> {
 }
 impl<K, V: Debug> Debug for RefPool<K, V> {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
-        write!(f, "{}", format!("{:?}", self.internal.iter().enumerate().format(", ")))
+        let entries = self
+            .internal
+            .iter()
+            .enumerate()
+            .filter_map(|(i, v)| v.as_ref().map(|v| (i, v)));
+        write!(f, "{}", format!("{:?}", entries.format(", ")))
     }
 }
 
@@ -99,6 +109,8 @@ impl<K, V> RefPool<K, V>
 where
     K: Ref,
 {
+    /// Number of keys that were created by this pool, including the ones whose value was removed.
+    /// All keys are in the range `0..len()`.
     pub fn len(&self) -> usize {
         self.internal.len()
     }
@@ -107,8 +119,11 @@ where
         self.internal.is_empty()
     }
 
-    pub fn keys(&self) -> impl Iterator<Item = K> {
-        (0..self.len()).map(K::from)
+    /// Iterates over all keys whose value was not removed.
+    pub fn keys(&self) -> impl Iterator<Item = K> + '_ {
+        (0..self.len())
+            .filter(move |&i| self.internal[i].is_some())
+            .map(K::from)
     }
 
     pub fn last_key(&self) -> Option<K> {
@@ -126,12 +141,40 @@ where
         assert!(!self.rev.contains_key(&v), "Duplicated value: {:?}", &v);
         let id: K = self.internal.len().into();
         self.rev.insert(v.clone(), id);
-        self.internal.push(v);
+        self.internal.push(Some(v));
         id
     }
 
-    pub fn get(&self, k: K) -> &V {
-        &self.internal[k.into()]
+    /// Returns the key of the value, interning it if it is not already present in the pool.
+    pub fn get_or_insert(&mut self, v: V) -> K
+    where
+        V: Eq + Hash + Clone,
+    {
+        match self.rev.entry(v) {
+            Entry::Occupied(e) => *e.get(),
+            Entry::Vacant(e) => {
+                let id: K = self.internal.len().into();
+                self.internal.push(Some(e.key().clone()));
+                e.insert(id);
+                id
+            }
+        }
+    }
+
+    /// Returns the value associated to the key, or `None` if it was removed.
+    pub fn get(&self, k: K) -> Option<&V> {
+        self.internal[k.into()].as_ref()
+    }
+
+    /// Removes the value associated to the key, returning it if it was not already removed.
+    /// The key is left as a tombstone: it will not be reused for subsequent insertions.
+    pub fn remove(&mut self, k: K) -> Option<V>
+    where
+        V: Eq + Hash,
+    {
+        let v = self.internal[k.into()].take()?;
+        self.rev.remove(&v);
+        Some(v)
     }
 
     pub fn get_ref<W: ?Sized>(&self, v: &W) -> Option<K>
@@ -141,6 +184,25 @@ where
     {
         self.rev.get(v).copied()
     }
+
+    /// Removes all values from the pool, while retaining the allocated memory.
+    /// Keys created afterwards start again from the first one, which invalidates all previous keys.
+    pub fn clear(&mut self) {
+        self.internal.clear();
+        self.rev.clear();
+    }
+}
+
+/// A pool is serialized as the list of its values, in the order of their keys.
+/// Removed values are serialized as `None`.
+/// The reverse mapping from values to keys is omitted as it can be rebuilt from this list.
+///
+/// Only available with the `serde` feature.
+#[cfg(feature = "serde")]
+impl<K, V: Serialize> Serialize for RefPool<K, V> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.internal.iter())
+    }
 }
 
 impl<K: Ref, V> Index<K> for RefPool<K, V> {
@@ -148,6 +210,7 @@ impl<K: Ref, V> Index<K> for RefPool<K, V> {
 
     fn index(&self, index: K) -> &Self::Output {
         self.get(index)
+            .expect("No value for this key: it was removed from the pool")
     }
 }
 
@@ -227,6 +290,18 @@ where
     pub fn get_mut(&mut self, k: K) -> &mut V {
         &mut self.internal[k.into()]
     }
+
+    /// Number of values the store can hold without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.internal.capacity()
+    }
+
+    /// Removes all values from the store, while retaining the allocated memory so that it can be reused,
+    /// e.g., for scratch data across solve iterations.
+    /// Keys created afterwards start again from the first one, which invalidates all previous keys.
+    pub fn clear(&mut self) {
+        self.internal.clear()
+    }
 }
 
 impl<K: Ref, V> Index<K> for RefStore<K, V> {
@@ -274,6 +349,17 @@ impl<K, V> RefVec<K, V> {
         }
     }
 
+    /// Creates a new RefVec with `num_items` values, where the value of each key `k` is given by `f(k)`.
+    pub fn from_fn(num_items: usize, f: impl FnMut(K) -> V) -> Self
+    where
+        K: From<usize>,
+    {
+        RefVec {
+            values: (0..num_items).map(K::from).map(f).collect(),
+            phantom: PhantomData,
+        }
+    }
+
     pub fn contains(&self, k: K) -> bool
     where
         usize: From<K>,
@@ -304,6 +390,11 @@ impl<K, V> RefVec<K, V> {
         self.values.pop()
     }
 
+    /// Reserves capacity for at least `additional` more values.
+    pub fn reserve(&mut self, additional: usize) {
+        self.values.reserve(additional)
+    }
+
     /// Same as push but panics if `key` is not the result of the push.
     pub fn set_next(&mut self, key: K, value: V)
     where
@@ -326,6 +417,16 @@ impl<K, V> RefVec<K, V> {
     {
         (0..(self.values.len())).map(move |i| (K::from(i), &self.values[i]))
     }
+
+    /// Iterates over all values, in the order of their keys.
+    pub fn iter(&self) -> impl Iterator<Item = &V> {
+        self.values.iter()
+    }
+
+    /// Iterates mutably over all values, in the order of their keys.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut V> {
+        self.values.iter_mut()
+    }
 }
 
 impl<K: Into<usize>, V> Index<K> for RefVec<K, V> {
@@ -408,12 +509,25 @@ impl<K: Ref, V> RefMap<K, V> {
         self.entries.iter_mut().filter_map(|x| x.as_mut())
     }
 
+    /// Iterates over all keys with an entry in the map, together with their values.
+    ///
+    /// The order of iteration is unspecified, `keys_sorted` should be used when a deterministic order is required.
     pub fn entries(&self) -> impl Iterator<Item = (K, &V)> {
         self.entries
             .iter()
             .enumerate()
             .filter_map(|(idx, value)| value.as_ref().map(|v| (K::from(idx), v)))
     }
+
+    /// Returns all keys with an entry in the map, in ascending order.
+    pub fn keys_sorted(&self) -> impl Iterator<Item = K>
+    where
+        K: Ord,
+    {
+        let mut keys: Vec<K> = self.keys().collect();
+        keys.sort();
+        keys.into_iter()
+    }
 }
 
 impl<K: Ref, V> Index<K> for RefMap<K, V> {
@@ -429,3 +543,126 @@ impl<K: Ref, V> IndexMut<K> for RefMap<K, V> {
         self.get_mut(index).expect("No such key")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    create_ref_type!(Id);
+
+    #[test]
+    fn test_keys_sorted() {
+        let mut map: RefMap<Id, &str> = Default::default();
+        for &i in &[7usize, 2, 5, 0] {
+            map.insert(Id::from(i), "x");
+        }
+        map.remove(Id::from(5usize));
+        let keys: Vec<usize> = map.keys_sorted().map(usize::from).collect();
+        assert_eq!(keys, vec![0, 2, 7]);
+    }
+
+    #[test]
+    fn test_pool_removal() {
+        let mut pool: RefPool<Id, String> = Default::default();
+        let a = pool.push("a".to_string());
+        let b = pool.push("b".to_string());
+        let c = pool.push("c".to_string());
+
+        assert_eq!(pool.remove(b), Some("b".to_string()));
+        assert_eq!(pool.remove(b), None);
+        assert_eq!(pool.get(b), None);
+        assert_eq!(pool.get_ref("b"), None);
+
+        // other keys are still valid
+        assert_eq!(pool.get(a).map(|s| s.as_str()), Some("a"));
+        assert_eq!(&pool[c], "c");
+        assert_eq!(pool.get_ref("a"), Some(a));
+        assert_eq!(pool.get_ref("c"), Some(c));
+        assert_eq!(pool.keys().collect::<Vec<_>>(), vec![a, c]);
+
+        // the removed value can be interned again, with a new key
+        let b2 = pool.push("b".to_string());
+        assert_ne!(b, b2);
+        assert_eq!(pool.get_ref("b"), Some(b2));
+        assert_eq!(pool.len(), 4);
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut store: RefStore<Id, usize> = RefStore::new();
+        let first = store.push(0);
+        for i in 1..100 {
+            store.push(i);
+        }
+        let capacity = store.capacity();
+        store.clear();
+        assert!(store.is_empty());
+        assert_eq!(store.capacity(), capacity);
+        assert_eq!(store.push(42), first);
+        assert_eq!(store[first], 42);
+        assert_eq!(store.len(), 1);
+
+        let mut pool: RefPool<Id, String> = Default::default();
+        let a = pool.push("a".to_string());
+        pool.push("b".to_string());
+        pool.clear();
+        assert!(pool.is_empty());
+        assert_eq!(pool.get_ref("a"), None);
+        // previously interned values can be pushed again
+        assert_eq!(pool.push("b".to_string()), a);
+        assert_eq!(pool.get_ref("b"), Some(a));
+    }
+
+    #[test]
+    fn test_ref_vec_from_fn() {
+        let vec: RefVec<Id, Id> = RefVec::from_fn(10, |k| k);
+        assert_eq!(vec.len(), 10);
+        for k in vec.keys() {
+            assert_eq!(vec[k], k);
+        }
+        let empty: RefVec<Id, Id> = RefVec::from_fn(0, |_| panic!("No value expected"));
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_ref_vec_iteration() {
+        let mut vec: RefVec<Id, &str> = RefVec::new();
+        let a = vec.push("a");
+        let b = vec.push("b");
+        let c = vec.push("c");
+        assert_eq!(vec.entries().collect::<Vec<_>>(), vec![(a, &"a"), (b, &"b"), (c, &"c")]);
+        assert_eq!(vec.iter().copied().collect::<Vec<_>>(), vec!["a", "b", "c"]);
+
+        for v in vec.iter_mut() {
+            *v = "x";
+        }
+        assert!(vec.iter().all(|&v| v == "x"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_pool_serialization() {
+        let mut pool: RefPool<Id, String> = Default::default();
+        pool.push("a".to_string());
+        let b = pool.push("b".to_string());
+        pool.push("c".to_string());
+        assert_eq!(serde_json::to_string(&pool).unwrap(), r#"["a","b","c"]"#);
+
+        pool.remove(b);
+        assert_eq!(serde_json::to_string(&pool).unwrap(), r#"["a",null,"c"]"#);
+    }
+
+    #[test]
+    fn test_get_or_insert() {
+        let mut pool: RefPool<Id, String> = Default::default();
+        let a = pool.get_or_insert("a".to_string());
+        assert_eq!(pool.get_or_insert("a".to_string()), a);
+        assert_eq!(pool.len(), 1);
+        assert_eq!(pool.get_ref("a"), Some(a));
+
+        let b = pool.get_or_insert("b".to_string());
+        assert_ne!(a, b);
+        assert_eq!(&pool[b], "b");
+        assert_eq!(pool.len(), 2);
+    }
+}
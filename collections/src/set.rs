@@ -39,6 +39,14 @@ impl<K: Into<usize>> RefSet<K> {
     pub fn contains(&self, k: K) -> bool {
         self.set.contains(k.into())
     }
+
+    /// Iterates over the values in the set, in increasing order of their integer representation.
+    pub fn iter(&self) -> impl Iterator<Item = K> + '_
+    where
+        K: From<usize>,
+    {
+        self.set.iter().map(K::from)
+    }
 }
 
 impl<K: Into<usize>> Default for RefSet<K> {
@@ -161,4 +161,42 @@ impl SavedAssignment {
     pub fn from_model(model: &Model) -> SavedAssignment {
         model.clone()
     }
+
+    /// Returns all variables whose domain differ between the two assignments, together with their domain
+    /// in `self` and in `other` respectively.
+    /// Variables that are not declared in both assignments are ignored.
+    #[allow(clippy::type_complexity)]
+    pub fn diff(&self, other: &SavedAssignment) -> Vec<(VarRef, (IntCst, IntCst), (IntCst, IntCst))> {
+        self.discrete
+            .variables()
+            .zip(other.discrete.variables())
+            .map(|(var, _)| (var, self.discrete.domain_of(var), other.discrete.domain_of(var)))
+            .filter(|(_, dom1, dom2)| dom1 != dom2)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::int_model::Cause;
+
+    #[test]
+    fn assignment_diff() {
+        let mut model = Model::new();
+        let a = model.new_ivar(0, 10, "a");
+        let b = model.new_ivar(0, 10, "b");
+        let c = model.new_ivar(0, 10, "c");
+        model.discrete.set_lb(a, 3, Cause::Decision).unwrap();
+        let first = SavedAssignment::from_model(&model);
+        assert!(first.diff(&first).is_empty());
+
+        model.discrete.set_ub(b, 5, Cause::Decision).unwrap();
+        // a variable created after the first assignment is not reported
+        model.new_ivar(0, 10, "d");
+        let second = SavedAssignment::from_model(&model);
+        assert_eq!(first.diff(&second), vec![(VarRef::from(b), (0, 10), (0, 5))]);
+        assert_eq!(second.diff(&first), vec![(VarRef::from(b), (0, 5), (0, 10))]);
+        assert_eq!(second.discrete.domain_of(c), first.discrete.domain_of(c));
+    }
 }
@@ -1,7 +1,7 @@
 use crate::lang::IntCst;
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
-pub struct BoundValue(i32);
+pub struct BoundValue(IntCst);
 
 impl BoundValue {
     #[inline]
@@ -87,6 +87,14 @@ impl std::ops::Neg for BoundValue {
     }
 }
 
+impl BoundValue {
+    /// Applies the addition to this bound value, returning `None` if the result overflows.
+    #[inline]
+    pub fn checked_add(self, rhs: BoundValueAdd) -> Option<BoundValue> {
+        self.0.checked_add(rhs.0).map(BoundValue)
+    }
+}
+
 impl std::ops::Add<BoundValueAdd> for BoundValue {
     type Output = BoundValue;
 
@@ -5,6 +5,10 @@ use crate::bounds::{Bound, BoundValue};
 
 /// A set of bounds watches on bound changes.
 /// The event watches are all on the same bound (i.e. the lower or the upper bound) of a single variable.
+///
+/// Watches are kept sorted by decreasing priority, so that watchers with a higher priority are always
+/// triggered first. Watches with the same priority are kept in insertion order, including when
+/// other watches are removed.
 #[derive(Clone)]
 pub struct WatchSet<Watcher> {
     watches: Vec<Watch<Watcher>>,
@@ -15,25 +19,68 @@ impl<Watcher> WatchSet<Watcher> {
     }
 
     pub fn add_watch(&mut self, watcher: Watcher, literal: Bound) {
-        self.watches.push(Watch {
+        self.add_watch_with_priority(watcher, literal, 0)
+    }
+
+    /// Adds a watch that will be triggered before all watches with a lower priority.
+    pub fn add_watch_with_priority(&mut self, watcher: Watcher, literal: Bound, priority: u8) {
+        self.insert(Watch {
             watcher,
             guard: literal.raw_value,
+            priority,
         })
     }
 
+    /// Inserts the watch after all watches with a greater or equal priority.
+    fn insert(&mut self, watch: Watch<Watcher>) {
+        let mut index = self.watches.len();
+        while index > 0 && self.watches[index - 1].priority < watch.priority {
+            index -= 1;
+        }
+        self.watches.insert(index, watch);
+    }
+
+    /// Removes the watch at the given index, preserving the relative order of the remaining watches.
+    fn remove_at(&mut self, index: usize) -> Watch<Watcher> {
+        self.watches.remove(index)
+    }
+
     pub fn clear(&mut self) {
         self.watches.clear();
     }
 
+    pub fn len(&self) -> usize {
+        self.watches.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.watches.is_empty()
+    }
+
     /// Remove the watch of the given watcher from this set.
     /// The method will panic if there is not exactly one watch for this watcher.
     pub fn remove_watch(&mut self, watcher: Watcher)
     where
         Watcher: Eq,
     {
-        let index = self.watches.iter().position(|w| w.watcher == watcher).unwrap();
-        self.watches.swap_remove(index);
-        debug_assert!(self.watches.iter().all(|w| w.watcher != watcher));
+        let removed = self.try_remove_watch(watcher);
+        assert!(removed, "No watch to remove for this watcher");
+    }
+
+    /// Remove the watch of the given watcher from this set, if any.
+    /// Returns true if a watch was removed and false if the watcher had no watch in this set.
+    pub fn try_remove_watch(&mut self, watcher: Watcher) -> bool
+    where
+        Watcher: Eq,
+    {
+        match self.watches.iter().position(|w| w.watcher == watcher) {
+            Some(index) => {
+                self.remove_at(index);
+                debug_assert!(self.watches.iter().all(|w| w.watcher != watcher));
+                true
+            }
+            None => false,
+        }
     }
 
     pub fn is_watched_by(&self, watcher: Watcher, literal: Bound) -> bool
@@ -66,8 +113,8 @@ impl<Watcher> WatchSet<Watcher> {
         let mut i = 0;
         while i < self.watches.len() {
             if literal.raw_value.stronger(self.watches[i].guard) {
-                let w = self.watches.swap_remove(i);
-                out.watches.push(w);
+                let w = self.remove_at(i);
+                out.insert(w);
             } else {
                 i += 1
             }
@@ -85,6 +132,8 @@ impl<Watcher> Default for WatchSet<Watcher> {
 pub struct Watch<Watcher> {
     pub watcher: Watcher,
     guard: BoundValue,
+    /// Watches with a higher priority are triggered first.
+    pub priority: u8,
 }
 impl<Watcher> Watch<Watcher> {
     pub fn to_lit(&self, var_bound: VarBound) -> Bound {
@@ -118,18 +167,29 @@ impl<Watcher> Watches<Watcher> {
         self.watches[literal.affected_bound()].add_watch(watcher, literal);
     }
 
-    // pub fn pop_all_lb_watches(&mut self, var: VarRef) -> Vec<LBWatch<Watcher>> {
-    //     self.ensure_capacity(var);
-    //     let mut tmp = Vec::new();
-    //     std::mem::swap(&mut tmp, &mut self.on_lb[var]);
-    //     tmp
-    // }
-    // pub fn pop_all_ub_watches(&mut self, var: VarRef) -> Vec<UBWatch<Watcher>> {
-    //     self.ensure_capacity(var);
-    //     let mut tmp = Vec::new();
-    //     std::mem::swap(&mut tmp, &mut self.on_ub[var]);
-    //     tmp
-    // }
+    /// Adds a watch that will be triggered before all watches of a lower priority on the same bound.
+    pub fn add_watch_with_priority(&mut self, watcher: Watcher, literal: Bound, priority: u8) {
+        self.ensure_capacity(literal.affected_bound());
+        self.watches[literal.affected_bound()].add_watch_with_priority(watcher, literal, priority);
+    }
+
+    /// Returns the number of watches on the given bound.
+    pub fn count_on(&self, bound: VarBound) -> usize {
+        if self.watches.contains(bound) {
+            self.watches[bound].len()
+        } else {
+            0
+        }
+    }
+
+    /// Removes all watches on the given bound and returns them.
+    pub fn drain_on(&mut self, bound: VarBound) -> Vec<Watch<Watcher>> {
+        if self.watches.contains(bound) {
+            self.watches[bound].watches.drain(..).collect()
+        } else {
+            Vec::new()
+        }
+    }
 
     pub fn is_watched_by(&self, literal: Bound, watcher: Watcher) -> bool
     where
@@ -150,9 +210,24 @@ impl<Watcher> Watches<Watcher> {
         self.watches[literal.affected_bound()].remove_watch(watcher);
     }
 
+    /// Removes the watch of the given watcher on the bound of the literal, if any.
+    /// Returns true if a watch was removed and false if the watcher had no watch on this bound.
+    pub fn try_remove_watch(&mut self, watcher: Watcher, literal: Bound) -> bool
+    where
+        Watcher: Eq,
+    {
+        if self.watches.contains(literal.affected_bound()) {
+            self.watches[literal.affected_bound()].try_remove_watch(watcher)
+        } else {
+            false
+        }
+    }
+
     /// Get the watchers triggered by the literal becoming true
     /// If the literal is (n <= 4), it should trigger watches on (n <= 4), (n <= 5), ...
     /// If the literal is (n > 5), it should trigger watches on (n > 5), (n > 4), (n > 3), ...
+    ///
+    /// Watchers are yielded by decreasing priority and, within a priority, in insertion order.
     pub fn watches_on(&self, literal: Bound) -> impl Iterator<Item = Watcher> + '_
     where
         Watcher: Copy,
@@ -259,4 +334,84 @@ mod tests {
         check_watches_on(watches, Bound::geq(b, 3), vec![]);
         check_watches_on(watches, Bound::geq(b, 4), vec![]);
     }
+
+    #[test]
+    fn test_drain_watches() {
+        let mut model = Model::new();
+        let a = model.new_ivar(0, 10, "a");
+        let lb = VarBound::lb(a.into());
+        let ub = VarBound::ub(a.into());
+
+        let mut watches = Watches::new();
+        assert_eq!(watches.count_on(lb), 0);
+        watches.add_watch(1, Bound::geq(a, 1));
+        watches.add_watch(2, Bound::geq(a, 2));
+        watches.add_watch(3, Bound::geq(a, 3));
+        watches.add_watch(4, Bound::leq(a, 3));
+        assert_eq!(watches.count_on(lb), 3);
+        assert_eq!(watches.count_on(ub), 1);
+
+        let drained = watches.drain_on(lb);
+        let mut lits: Vec<_> = drained.iter().map(|w| (w.watcher, w.to_lit(lb))).collect();
+        lits.sort_by_key(|&(watcher, _)| watcher);
+        assert_eq!(
+            lits,
+            vec![(1, Bound::geq(a, 1)), (2, Bound::geq(a, 2)), (3, Bound::geq(a, 3))]
+        );
+        assert_eq!(watches.count_on(lb), 0);
+        assert_eq!(watches.watches_on(Bound::geq(a, 10)).count(), 0);
+        assert!(watches.drain_on(lb).is_empty());
+
+        // the other bound is unaffected
+        assert_eq!(watches.count_on(ub), 1);
+    }
+
+    #[test]
+    fn test_watch_priorities() {
+        let mut model = Model::new();
+        let a = model.new_ivar(0, 10, "a");
+
+        let mut watches = Watches::new();
+        watches.add_watch(1, Bound::leq(a, 5));
+        watches.add_watch_with_priority(2, Bound::leq(a, 5), 10);
+        watches.add_watch(3, Bound::leq(a, 5));
+        watches.add_watch_with_priority(4, Bound::leq(a, 5), 10);
+        watches.add_watch_with_priority(5, Bound::leq(a, 5), 5);
+
+        watches.add_watch(6, Bound::leq(a, 5));
+
+        // watches with the same priority are yielded in insertion order
+        let order: Vec<_> = watches.watches_on(Bound::leq(a, 4)).collect();
+        assert_eq!(order, vec![2, 4, 5, 1, 3, 6]);
+
+        // removals preserve the ordering on priorities and the insertion order within a priority
+        watches.remove_watch(2, Bound::leq(a, 5));
+        watches.remove_watch(1, Bound::leq(a, 5));
+        let order: Vec<_> = watches.watches_on(Bound::leq(a, 4)).collect();
+        assert_eq!(order, vec![4, 5, 3, 6]);
+
+        let mut out = WatchSet::new();
+        watches.move_watches_to(Bound::leq(a, 4), &mut out);
+        let order: Vec<_> = out.watches_on(Bound::leq(a, 4)).collect();
+        assert_eq!(order, vec![4, 5, 3, 6]);
+    }
+
+    #[test]
+    fn test_try_remove_watch() {
+        let mut model = Model::new();
+        let a = model.new_ivar(0, 10, "a");
+        let b = model.new_ivar(0, 10, "b");
+
+        let mut watches = Watches::new();
+        watches.add_watch(1, Bound::leq(a, 1));
+
+        // absent watchers, on a watched and an unwatched variable
+        assert!(!watches.try_remove_watch(2, Bound::leq(a, 1)));
+        assert!(!watches.try_remove_watch(1, Bound::geq(a, 1)));
+        assert!(!watches.try_remove_watch(1, Bound::leq(b, 1)));
+
+        assert!(watches.try_remove_watch(1, Bound::leq(a, 1)));
+        assert!(!watches.try_remove_watch(1, Bound::leq(a, 1)));
+        assert_eq!(watches.watches_on(Bound::leq(a, 0)).count(), 0);
+    }
 }
@@ -3,9 +3,9 @@ mod explanation;
 
 pub use explanation::*;
 
-use crate::bounds::{Bound, Disjunction, Relation};
+use crate::bounds::{Bound, Disjunction, Relation, VarBound};
 use crate::expressions::ExprHandle;
-use crate::int_model::domains::{Domains, Event};
+use crate::int_model::domains::{BoundsSnapshot, Domains, Event};
 use crate::lang::{BVar, IntCst, VarRef};
 use crate::{Label, WriterId};
 use aries_backtrack::DecLvl;
@@ -25,7 +25,7 @@ impl IntDomain {
     }
 
     pub fn size(&self) -> i64 {
-        (self.ub as i64) - (self.lb as i64)
+        self.ub - self.lb
     }
 
     pub fn is_bound(&self) -> bool {
@@ -82,6 +82,28 @@ pub struct InferenceCause {
 #[derive(Ord, PartialOrd, PartialEq, Eq, Debug, Copy, Clone)]
 pub struct EmptyDomain(pub VarRef);
 
+/// Error raised when attempting to create a variable whose initial domain `[lb, ub]` is empty.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct InvalidDomain {
+    pub label: Option<String>,
+    pub lb: IntCst,
+    pub ub: IntCst,
+}
+
+impl std::error::Error for InvalidDomain {}
+
+impl std::fmt::Display for InvalidDomain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Empty initial domain [{}, {}] for variable {}",
+            self.lb,
+            self.ub,
+            self.label.as_deref().unwrap_or("<unnamed>")
+        )
+    }
+}
+
 #[derive(Default, Clone)]
 pub struct DiscreteModel {
     labels: RefVec<VarRef, Label>,
@@ -108,6 +130,40 @@ impl DiscreteModel {
         id1
     }
 
+    /// Creates a new variable with the initial domain `[lb, ub]`, or returns an error if this domain is empty.
+    ///
+    /// Contrary to `new_discrete_var`, this allows detecting an infeasible variable on its creation rather than
+    /// as an inconsistency on the first propagation.
+    pub fn try_new_discrete_var<L: Into<Label>>(
+        &mut self,
+        lb: IntCst,
+        ub: IntCst,
+        label: L,
+    ) -> Result<VarRef, InvalidDomain> {
+        let label = label.into();
+        if lb > ub {
+            Err(InvalidDomain {
+                label: label.into(),
+                lb,
+                ub,
+            })
+        } else {
+            Ok(self.new_discrete_var(lb, ub, label))
+        }
+    }
+
+    /// Creates `count` new variables with the same initial domain `[lb, ub]`.
+    /// The i-th variable is labeled with `label_prefix` followed by `i`.
+    ///
+    /// The returned variables are contiguous: each one is the successor of the previous one.
+    pub fn new_discrete_vars(&mut self, count: usize, lb: IntCst, ub: IntCst, label_prefix: &str) -> Vec<VarRef> {
+        self.labels.reserve(count);
+        self.domains.reserve(count);
+        (0..count)
+            .map(|i| self.new_discrete_var(lb, ub, format!("{}{}", label_prefix, i)))
+            .collect()
+    }
+
     pub fn variables(&self) -> impl Iterator<Item = VarRef> {
         self.labels.keys()
     }
@@ -128,6 +184,48 @@ impl DiscreteModel {
         self.domains.bounds(var.into())
     }
 
+    /// Iterates over all values in the current domain of the variable, in increasing order.
+    /// Values removed from the interior of the domain (see `remove_value`) are skipped.
+    pub fn domain_values(&self, var: impl Into<VarRef>) -> impl Iterator<Item = IntCst> + '_ {
+        let var = var.into();
+        let (lb, ub) = self.domains.bounds(var);
+        (lb..=ub).filter(move |&v| self.domains.contains(var, v))
+    }
+
+    /// Number of values in the current domain of the variable, excluding the ones removed from
+    /// its interior. Returns 0 if the domain is empty.
+    pub fn domain_size(&self, var: impl Into<VarRef>) -> u64 {
+        let var = var.into();
+        let (lb, ub) = self.domains.bounds(var);
+        if lb > ub {
+            0
+        } else if self.domains.has_holes(var) {
+            self.domain_values(var).count() as u64
+        } else {
+            (ub - lb + 1) as u64
+        }
+    }
+
+    /// Returns the unfixed variable with the fewest values left in its domain (first-fail principle),
+    /// or `None` if all variables are fixed. Ties are broken in favor of the first declared variable.
+    pub fn smallest_domain_unfixed(&self) -> Option<VarRef> {
+        self.variables()
+            .filter(|&v| !self.domains.is_bound(v))
+            .min_by_key(|&v| self.domain_size(v))
+    }
+
+    /// Returns the cause of the change that set the current lower bound of the variable,
+    /// or `None` if the lower bound has its initial value.
+    pub fn cause_of_lb(&self, var: impl Into<VarRef>) -> Option<Cause> {
+        self.domains.cause_of(VarBound::lb(var.into()))
+    }
+
+    /// Returns the cause of the change that set the current upper bound of the variable,
+    /// or `None` if the upper bound has its initial value.
+    pub fn cause_of_ub(&self, var: impl Into<VarRef>) -> Option<Cause> {
+        self.domains.cause_of(VarBound::ub(var.into()))
+    }
+
     pub fn decide(&mut self, literal: Bound) -> Result<bool, EmptyDomain> {
         match literal.relation() {
             Relation::LEQ => self.set_ub(literal.variable(), literal.value(), Cause::Decision),
@@ -163,6 +261,29 @@ impl DiscreteModel {
         self.domains.set_ub(var.into(), ub, cause)
     }
 
+    /// Makes `b` an alias of `a`, so that both variables share the same domain until backtracking.
+    /// See `Domains::alias` for details.
+    pub fn alias(&mut self, a: impl Into<VarRef>, b: impl Into<VarRef>, cause: Cause) -> Result<(), EmptyDomain> {
+        self.domains.alias(a.into(), b.into(), cause)
+    }
+
+    /// Captures the current bounds of all variables. See `Domains::snapshot_bounds` for details.
+    pub fn snapshot_bounds(&self) -> BoundsSnapshot {
+        self.domains.snapshot_bounds()
+    }
+
+    /// Resets the bounds of variables to their value in the snapshot, without cloning any other part of the
+    /// model. This does not restore any learned structure. See `Domains::restore_bounds` for details.
+    pub fn restore_bounds(&mut self, snapshot: &BoundsSnapshot) {
+        self.domains.restore_bounds(snapshot)
+    }
+
+    /// Removes a single value from the domain of a variable, leaving a hole in it if the value is not one
+    /// of its bounds. See `Domains::remove_value` for details.
+    pub fn remove_value(&mut self, var: impl Into<VarRef>, value: IntCst, cause: Cause) -> Result<bool, EmptyDomain> {
+        self.domains.remove_value(var.into(), value, cause)
+    }
+
     // ================== Explanation ==============
 
     pub fn explain_empty_domain(&mut self, var: VarRef, explainer: &mut impl Explainer) -> Disjunction {
@@ -280,10 +401,85 @@ impl DiscreteModel {
         }
     }
 
+    /// Returns the set of decisions that, together with the root-level facts, imply all literals of the explanation.
+    ///
+    /// This is typically used to build a core of assumptions (as opposed to the 1UIP clause of `refine_explanation`):
+    /// all implications are traced back to the decisions they originate from, whatever their decision level.
+    ///
+    /// As a side effect, this will undo all events that occurred after the earliest decision of the result
+    /// (including by discarding decision levels). The caller is responsible for backtracking the other components
+    /// to a consistent state (typically the root level) before proceeding.
+    pub fn implying_decisions(&mut self, explanation: Explanation, explainer: &mut impl Explainer) -> Vec<Bound> {
+        let mut explanation = explanation;
+        self.queue.clear();
+        let mut result: Vec<Bound> = Vec::with_capacity(4);
+
+        loop {
+            for l in explanation.lits.drain(..) {
+                debug_assert!(self.entails(l));
+                // literals with no implying event or implied at the root level are always true and can be discarded
+                if let Some(loc) = self.implying_event(l) {
+                    if self.trail().decision_level(loc) != DecLvl::ROOT {
+                        self.queue.push(InQueueLit { cause: loc, lit: l })
+                    }
+                }
+            }
+            // select latest literal from queue
+            let l = match self.queue.pop() {
+                Some(l) => l,
+                None => return result,
+            };
+            // The queue might contain more than one reference to the same event.
+            // Due to the priority of the queue, they are necessarily contiguous.
+            // Explaining the event explains all of them.
+            while let Some(next) = self.queue.peek() {
+                if next.cause == l.cause {
+                    self.queue.pop();
+                } else {
+                    break;
+                }
+            }
+            debug_assert!(l.cause < self.domains.trail().next_slot());
+
+            // undo all decision levels above the one of the event
+            let dl = self.trail().decision_level(l.cause);
+            while self.domains.current_decision_level() > dl {
+                self.domains.restore_last();
+            }
+            let event = self.get_event(l.cause);
+            let lit = event.new_literal();
+            let mut cause = None;
+            // undo events until the one that entailed the literal, so that the model is in
+            // the state it was when it was inferred
+            while l.cause < self.domains.trail().next_slot() {
+                cause = Some(self.domains.undo_last_event());
+            }
+            match cause.unwrap() {
+                Cause::Decision => result.push(lit),
+                Cause::Inference(cause) => {
+                    // ask for a clause (l1 & l2 & ... & ln) => lit
+                    explainer.explain(cause, l.lit, self, &mut explanation);
+                }
+            }
+        }
+    }
+
+    /// Returns true if the current domain of the variable of `lit` entails it.
+    /// For instance, `x <= 4` is entailed if the upper bound of `x` is `3`.
     pub fn entails(&self, lit: Bound) -> bool {
         self.domains.entails(lit)
     }
 
+    /// Returns:
+    ///  - `Some(true)` if the literal is entailed by the current domain of its variable,
+    ///  - `Some(false)` if its negation is entailed,
+    ///  - `None` if neither is entailed.
+    ///
+    /// This is a synonym of `value`, meant to make simplification code more explicit.
+    pub fn entailed_or_contradicted(&self, lit: Bound) -> Option<bool> {
+        self.value(lit)
+    }
+
     pub fn value(&self, lit: Bound) -> Option<bool> {
         if self.entails(lit) {
             Some(true)
@@ -458,6 +654,181 @@ mod tests {
         assert_eq!(model.discrete.set_ub(a, 0, Cause::Decision), Err(EmptyDomain(a.into())));
     }
 
+    #[test]
+    fn entailment() {
+        let mut model = DiscreteModel::new();
+        let x = model.new_discrete_var(0, 10, "x");
+        model.set_lb(x, 2, Cause::Decision).unwrap();
+        model.set_ub(x, 5, Cause::Decision).unwrap();
+
+        assert!(model.entails(ILit::leq(x, 5)));
+        assert!(model.entails(ILit::leq(x, 7)));
+        assert!(!model.entails(ILit::leq(x, 4)));
+        assert!(model.entails(ILit::geq(x, 2)));
+        assert!(model.entails(ILit::geq(x, 0)));
+        assert!(!model.entails(ILit::geq(x, 3)));
+
+        assert_eq!(model.entailed_or_contradicted(ILit::leq(x, 6)), Some(true));
+        assert_eq!(model.entailed_or_contradicted(ILit::geq(x, 1)), Some(true));
+        assert_eq!(model.entailed_or_contradicted(ILit::leq(x, 1)), Some(false));
+        assert_eq!(model.entailed_or_contradicted(ILit::geq(x, 6)), Some(false));
+        assert_eq!(model.entailed_or_contradicted(ILit::leq(x, 3)), None);
+        assert_eq!(model.entailed_or_contradicted(ILit::geq(x, 5)), None);
+    }
+
+    #[test]
+    fn domain_values() {
+        let mut model = DiscreteModel::new();
+        let x = model.new_discrete_var(0, 10, "x");
+        model.set_lb(x, 2, Cause::Decision).unwrap();
+        model.set_ub(x, 4, Cause::Decision).unwrap();
+        assert_eq!(model.domain_values(x).collect::<Vec<_>>(), vec![2, 3, 4]);
+
+        model.save_state();
+        model.remove_value(x, 3, Cause::Decision).unwrap();
+        assert_eq!(model.domain_values(x).collect::<Vec<_>>(), vec![2, 4]);
+        model.restore_last();
+        assert_eq!(model.domain_values(x).collect::<Vec<_>>(), vec![2, 3, 4]);
+
+        model.set_lb(x, 5, Cause::Decision).unwrap_err();
+        assert_eq!(model.domain_values(x).count(), 0);
+    }
+
+    #[test]
+    fn first_fail() {
+        let mut model = DiscreteModel::new();
+        let a = model.new_discrete_var(0, 10, "a");
+        let b = model.new_discrete_var(0, 4, "b");
+        let c = model.new_discrete_var(3, 3, "c");
+        let d = model.new_discrete_var(0, 6, "d");
+        assert_eq!(model.domain_size(a), 11);
+        assert_eq!(model.domain_size(b), 5);
+        assert_eq!(model.domain_size(c), 1);
+        assert_eq!(model.domain_size(d), 7);
+
+        // `c` is fixed and thus never selected
+        assert_eq!(model.smallest_domain_unfixed(), Some(b));
+
+        // holes are accounted for
+        model.remove_value(d, 2, Cause::Decision).unwrap();
+        model.remove_value(d, 3, Cause::Decision).unwrap();
+        model.remove_value(d, 4, Cause::Decision).unwrap();
+        assert_eq!(model.domain_size(d), 4);
+        assert_eq!(model.smallest_domain_unfixed(), Some(d));
+
+        model.set_lb(d, 6, Cause::Decision).unwrap();
+        model.set_lb(b, 4, Cause::Decision).unwrap();
+        model.set_lb(a, 8, Cause::Decision).unwrap();
+        assert_eq!(model.smallest_domain_unfixed(), Some(a));
+
+        model.set_lb(a, 10, Cause::Decision).unwrap();
+        assert_eq!(model.smallest_domain_unfixed(), None);
+    }
+
+    #[test]
+    fn bounds_snapshot() {
+        let mut model = DiscreteModel::new();
+        let x = model.new_discrete_var(0, 10, "x");
+        let y = model.new_discrete_var(0, 10, "y");
+        model.set_lb(x, 2, Cause::Decision).unwrap();
+        model.set_ub(y, 8, Cause::Decision).unwrap();
+
+        let snapshot = model.snapshot_bounds();
+        model.set_lb(x, 5, Cause::Decision).unwrap();
+        model.set_ub(x, 7, Cause::Decision).unwrap();
+        model.set_ub(y, 1, Cause::Decision).unwrap();
+        let z = model.new_discrete_var(3, 4, "z");
+
+        model.restore_bounds(&snapshot);
+        assert_eq!(model.domain_of(x), (2, 10));
+        assert_eq!(model.domain_of(y), (0, 8));
+        // not part of the snapshot
+        assert_eq!(model.domain_of(z), (3, 4));
+        assert!(model.entails(ILit::geq(x, 2)));
+        assert!(!model.entails(ILit::geq(x, 3)));
+
+        // events made since the snapshot are no longer in the trail
+        assert_eq!(model.num_events(), 2);
+        let ev = model.implying_event(ILit::geq(x, 2)).unwrap();
+        assert_eq!(model.get_event(ev).new_value, crate::bounds::BoundValue::lb(2));
+    }
+
+    #[test]
+    fn invalid_var_creation() {
+        let mut model = DiscreteModel::new();
+        let err = model.try_new_discrete_var(5, 3, "my_var").unwrap_err();
+        assert_eq!(err.lb, 5);
+        assert_eq!(err.ub, 3);
+        assert!(err.to_string().contains("my_var"));
+        assert_eq!(model.variables().count(), 0);
+
+        let x = model.try_new_discrete_var(3, 3, "x").unwrap();
+        assert_eq!(model.domain_of(x), (3, 3));
+    }
+
+    #[test]
+    fn aliasing() {
+        let mut model = Model::new();
+        let x = model.new_ivar(0, 10, "x");
+        let y = model.new_ivar(0, 10, "y");
+
+        model.save_state();
+        model.discrete.alias(x, y, Cause::Decision).unwrap();
+        model.discrete.set_lb(x, 3, Cause::Decision).unwrap();
+        model.discrete.set_ub(y, 6, Cause::Decision).unwrap();
+        assert_eq!(model.domain_of(x), (3, 6));
+        assert_eq!(model.domain_of(y), (3, 6));
+
+        model.restore_last();
+        assert_eq!(model.domain_of(x), (0, 10));
+        assert_eq!(model.domain_of(y), (0, 10));
+        model.discrete.set_lb(x, 3, Cause::Decision).unwrap();
+        assert_eq!(model.domain_of(y), (0, 10));
+    }
+
+    #[test]
+    fn bulk_var_creation() {
+        let mut model = DiscreteModel::new();
+        let first = model.new_discrete_var(0, 1, "first");
+        let vars = model.new_discrete_vars(1000, -5, 5, "x");
+        assert_eq!(vars.len(), 1000);
+        for (i, &v) in vars.iter().enumerate() {
+            assert_eq!(usize::from(v), usize::from(first) + 1 + i);
+            assert_eq!(model.domain_of(v), (-5, 5));
+            assert_eq!(model.label(v), Some(format!("x{}", i).as_str()));
+        }
+        assert_eq!(model.variables().count(), 1001);
+    }
+
+    #[test]
+    fn bound_causes() {
+        let mut model = Model::new();
+        let a = model.new_ivar(0, 10, "a");
+        let writer = WriterId::new(7);
+        let cause = Cause::inference(writer, 42u32);
+
+        assert_eq!(model.discrete.cause_of_lb(a), None);
+        assert_eq!(model.discrete.cause_of_ub(a), None);
+        model.save_state();
+        assert_eq!(model.discrete.set_lb(a, 5, cause), Ok(true));
+        assert_eq!(model.discrete.set_ub(a, 8, Cause::Decision), Ok(true));
+        // a weaker update leaves the bound and its cause unchanged
+        assert_eq!(model.discrete.set_lb(a, 3, Cause::Decision), Ok(false));
+        assert_eq!(model.discrete.cause_of_lb(a), Some(cause));
+        assert_eq!(model.discrete.cause_of_ub(a), Some(Cause::Decision));
+        match model.discrete.cause_of_lb(a) {
+            Some(Cause::Inference(c)) => {
+                assert_eq!(c.writer, writer);
+                assert_eq!(c.payload, 42);
+            }
+            _ => panic!("Expected an inference"),
+        }
+
+        model.restore_last();
+        assert_eq!(model.discrete.cause_of_lb(a), None);
+        assert_eq!(model.discrete.cause_of_ub(a), None);
+    }
+
     #[test]
     fn test_explanation() {
         let mut model = Model::new();
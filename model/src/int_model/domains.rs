@@ -1,8 +1,9 @@
 use crate::bounds::{Bound, BoundValue, VarBound};
 use crate::int_model::{Cause, EmptyDomain};
 use crate::lang::{IntCst, VarRef};
-use aries_backtrack::{Backtrack, BacktrackWith, DecLvl, EventIndex, ObsTrail};
-use aries_collections::ref_store::RefVec;
+use aries_backtrack::{Backtrack, BacktrackWith, DecLvl, EventIndex, ObsTrail, Trail};
+use aries_collections::ref_store::{RefMap, RefVec};
+use aries_collections::set::RefSet;
 use std::fmt::{Debug, Formatter};
 
 type ChangeIndex = Option<EventIndex>;
@@ -43,9 +44,10 @@ impl Debug for Event {
 /// Represents a the value of an upper/lower bound of a particular variable.
 /// It is packed with the index of the event that caused this change.
 ///
-/// We enforce an alignment on 8 bytes to make sure it can be read and written in a single instruction.
+/// We enforce an alignment on 16 bytes (the size of the struct with a 64 bits value) to make sure it can be
+/// read and written in a single instruction.
 #[derive(Copy, Clone, Debug)]
-#[repr(align(8))]
+#[repr(align(16))]
 pub struct ValueCause {
     pub value: BoundValue,
     pub cause: ChangeIndex,
@@ -56,10 +58,76 @@ impl ValueCause {
     }
 }
 
+/// Values removed from the interior of the domain of a variable.
+/// They are stored as a bitset of offsets from `base`, the lower bound of the variable when the
+/// first of them was removed.
+#[derive(Clone)]
+struct Holes {
+    base: IntCst,
+    removed: RefSet<usize>,
+}
+impl Holes {
+    fn new(base: IntCst) -> Self {
+        Holes {
+            base,
+            removed: RefSet::new(),
+        }
+    }
+
+    fn contains(&self, value: IntCst) -> bool {
+        value >= self.base && self.removed.contains((value - self.base) as usize)
+    }
+
+    fn insert(&mut self, value: IntCst) {
+        debug_assert!(value >= self.base);
+        self.removed.insert((value - self.base) as usize)
+    }
+
+    fn remove(&mut self, value: IntCst) {
+        debug_assert!(value >= self.base);
+        self.removed.remove((value - self.base) as usize)
+    }
+
+    /// Iterates over the removed values, in increasing order.
+    fn values(&self) -> impl Iterator<Item = IntCst> + '_ {
+        self.removed.iter().map(move |offset| self.base + offset as IntCst)
+    }
+}
+
+/// Removal of a value from the interior of the domain of a variable (see `Domains::remove_value`).
+#[derive(Copy, Clone, Debug)]
+struct HoleEvent {
+    var: VarRef,
+    value: IntCst,
+    cause: Cause,
+}
+
+/// Bounds of all variables of a `Domains`, as captured by `Domains::snapshot_bounds`.
+#[derive(Clone)]
+pub struct BoundsSnapshot {
+    bounds: RefVec<VarBound, ValueCause>,
+    /// Number of events in the trail when the snapshot was taken.
+    num_events: usize,
+    /// Decision level of the trail when the snapshot was taken.
+    decision_level: DecLvl,
+}
+
 #[derive(Default, Clone)]
 pub struct Domains {
     bounds: RefVec<VarBound, ValueCause>,
     events: ObsTrail<Event>,
+    /// Values removed from the interior of domains, for variables that have any.
+    /// Holes are never placed on a bound of the domain: removing the lower (resp. upper) bound of a variable
+    /// results in a regular bound update.
+    holes: RefMap<VarRef, Holes>,
+    /// History of the values removed from the interior of domains, used to restore them on backtrack.
+    hole_events: Trail<HoleEvent>,
+    /// Associates each aliased variable to the variable whose domain it shares (see `alias`).
+    aliases: RefMap<VarRef, VarRef>,
+    /// Number of entries in `aliases`, allowing to bypass their resolution when there are none.
+    num_aliases: usize,
+    /// History of the aliases made, used to undo them on backtrack.
+    alias_events: Trail<VarRef>,
 }
 
 impl Domains {
@@ -73,6 +141,67 @@ impl Domains {
         var_lb.variable()
     }
 
+    /// Reserves capacity for at least `additional` more variables.
+    pub fn reserve(&mut self, additional: usize) {
+        self.bounds.reserve(2 * additional)
+    }
+
+    // ============== Aliases =====================
+
+    /// Makes `b` an alias of `a`: from now on, both variables share the same domain, which is the
+    /// intersection of their current domains. Any update to one of them is reflected on the other.
+    /// The alias is undone when backtracking before this call.
+    ///
+    /// Updates made through an alias are recorded as events on the bounds of the variable it refers to.
+    /// In particular, modules watching the bounds of `b` will not be notified of changes made after the alias.
+    ///
+    /// Returns `Err(EmptyDomain(v))` if the two domains have an empty intersection.
+    pub fn alias(&mut self, a: VarRef, b: VarRef, cause: Cause) -> Result<(), EmptyDomain> {
+        let a = self.representative(a);
+        let b = self.representative(b);
+        if a == b {
+            return Ok(());
+        }
+        // restrict the domain of `a` to the one of `b`
+        let (lb, ub) = self.bounds(b);
+        self.set_lb(a, lb, cause)?;
+        self.set_ub(a, ub, cause)?;
+        if let Some(holes) = self.holes.get(b) {
+            let removed: Vec<IntCst> = holes.values().filter(|&value| lb < value && value < ub).collect();
+            for value in removed {
+                self.remove_value(a, value, cause)?;
+            }
+        }
+        self.aliases.insert(b, a);
+        self.num_aliases += 1;
+        self.alias_events.push(b);
+        Ok(())
+    }
+
+    /// Returns the variable holding the domain of `var`: either `var` itself or the variable it is an alias of.
+    pub fn representative(&self, var: VarRef) -> VarRef {
+        let mut var = var;
+        if self.num_aliases > 0 {
+            while let Some(&target) = self.aliases.get(var) {
+                var = target;
+            }
+        }
+        var
+    }
+
+    #[inline]
+    fn resolve(&self, var_bound: VarBound) -> VarBound {
+        if self.num_aliases == 0 {
+            return var_bound;
+        }
+        let var = self.representative(var_bound.variable());
+        if var_bound.is_lb() {
+            VarBound::lb(var)
+        } else {
+            VarBound::ub(var)
+        }
+    }
+
     // ============== Accessors =====================
 
     pub fn bounds(&self, v: VarRef) -> (IntCst, IntCst) {
@@ -80,26 +209,66 @@ impl Domains {
     }
 
     pub fn ub(&self, var: VarRef) -> IntCst {
-        self.bounds[VarBound::ub(var)].value.as_ub()
+        self.bounds[self.resolve(VarBound::ub(var))].value.as_ub()
     }
 
     pub fn lb(&self, var: VarRef) -> IntCst {
-        self.bounds[VarBound::lb(var)].value.as_lb()
+        self.bounds[self.resolve(VarBound::lb(var))].value.as_lb()
+    }
+
+    /// Returns true if the value is in the domain of the variable, i.e., it is between its bounds
+    /// and was not removed with `remove_value`.
+    pub fn contains(&self, var: VarRef, value: IntCst) -> bool {
+        let (lb, ub) = self.bounds(var);
+        lb <= value && value <= ub && !self.is_hole(var, value)
+    }
+
+    fn is_hole(&self, var: VarRef, value: IntCst) -> bool {
+        match self.holes.get(self.representative(var)) {
+            Some(holes) => holes.contains(value),
+            None => false,
+        }
+    }
+
+    /// Returns true if some values were removed from the interior of the domain of the variable.
+    pub fn has_holes(&self, var: VarRef) -> bool {
+        self.holes.get(self.representative(var)).is_some()
     }
 
     pub fn is_bound(&self, var: VarRef) -> bool {
-        let lb = self.bounds[VarBound::lb(var)].value;
-        let ub = self.bounds[VarBound::ub(var)].value;
+        let lb = self.bounds[self.resolve(VarBound::lb(var))].value;
+        let ub = self.bounds[self.resolve(VarBound::ub(var))].value;
         lb.equal_to_symmetric(ub)
     }
 
     pub fn entails(&self, lit: Bound) -> bool {
-        self.bounds[lit.affected_bound()].value.stronger(lit.bound_value())
+        self.bounds[self.resolve(lit.affected_bound())]
+            .value
+            .stronger(lit.bound_value())
     }
 
     #[inline]
     pub fn get_bound(&self, var_bound: VarBound) -> BoundValue {
-        self.bounds[var_bound].value
+        self.bounds[self.resolve(var_bound)].value
+    }
+
+    /// Returns the cause of the latest change of the given bound, or `None` if it still has its initial value.
+    pub fn cause_of(&self, var_bound: VarBound) -> Option<Cause> {
+        self.bounds[self.resolve(var_bound)]
+            .cause
+            .map(|loc| self.events.get_event(loc).cause)
+    }
+
+    /// Returns the cause of the removal of `value` from the interior of the domain of `var`,
+    /// or `None` if it is not a hole or if the hole was introduced at the root decision level.
+    pub fn hole_cause(&self, var: VarRef, value: IntCst) -> Option<Cause> {
+        let var = self.representative(var);
+        self.hole_events
+            .trail
+            .iter()
+            .rev()
+            .find(|ev| ev.var == var && ev.value == value)
+            .map(|ev| ev.cause)
     }
 
     // ============== Updates ==============
@@ -119,8 +288,65 @@ impl Domains {
         self.set_bound(literal.affected_bound(), literal.bound_value(), cause)
     }
 
+    /// Removes a single value from the domain of the variable.
+    ///
+    /// If the value is one of the bounds of the domain, the bound is updated to the closest value that was not
+    /// removed. Otherwise, the value is recorded as a hole in the domain, that will be skipped by any subsequent
+    /// bound update. In both cases, the change will be undone when backtracking.
+    ///
+    /// Holes are recorded with their cause in a dedicated trail rather than in the event trail of the bounds:
+    /// if a bound update is made stronger to skip a hole, the explanation of the update must account for it
+    /// through `hole_cause`.
+    ///
+    /// The function returns:
+    ///  - `Ok(true)` if the value was removed from the domain, leaving it non-empty.
+    ///  - `Ok(false)` if the value was not part of the domain.
+    ///  - `Err(EmptyDomain(var))` if removing the value left the variable with an empty domain.
+    pub fn remove_value(&mut self, var: VarRef, value: IntCst, cause: Cause) -> Result<bool, EmptyDomain> {
+        let var = self.representative(var);
+        if !self.contains(var, value) {
+            return Ok(false);
+        }
+        let (lb, ub) = self.bounds(var);
+        if value == lb {
+            self.set_lb(var, value + 1, cause)
+        } else if value == ub {
+            self.set_ub(var, value - 1, cause)
+        } else {
+            if self.holes.get(var).is_none() {
+                self.holes.insert(var, Holes::new(lb));
+            }
+            self.holes[var].insert(value);
+            self.hole_events.push(HoleEvent { var, value, cause });
+            Ok(true)
+        }
+    }
+
+    /// Returns the bound value closest to `new` that does not fall into a hole of the domain.
+    fn skip_holes(&self, affected: VarBound, new: BoundValue) -> BoundValue {
+        let holes = match self.holes.get(affected.variable()) {
+            Some(holes) => holes,
+            None => return new,
+        };
+        if affected.is_lb() {
+            let mut lb = new.as_lb();
+            while holes.contains(lb) {
+                lb += 1;
+            }
+            BoundValue::lb(lb)
+        } else {
+            let mut ub = new.as_ub();
+            while holes.contains(ub) {
+                ub -= 1;
+            }
+            BoundValue::ub(ub)
+        }
+    }
+
     pub fn set_bound(&mut self, affected: VarBound, new: BoundValue, cause: Cause) -> Result<bool, EmptyDomain> {
+        let affected = self.resolve(affected);
         let current = self.bounds[affected];
+        let new = self.skip_holes(affected, new);
 
         if current.value.stronger(new) {
             Ok(false)
@@ -149,6 +375,8 @@ impl Domains {
     }
 
     pub fn set_bound_unchecked(&mut self, affected: VarBound, new: BoundValue, cause: Cause) {
+        let affected = self.resolve(affected);
+        let new = self.skip_holes(affected, new);
         debug_assert!(new.strictly_stronger(self.bounds[affected].value));
         debug_assert!(new.compatible_with_symmetric(self.bounds[affected.symmetric_bound()].value));
         let previous = self.bounds[affected];
@@ -184,7 +412,7 @@ impl Domains {
     // history
 
     pub fn implying_event(&self, lit: Bound) -> Option<EventIndex> {
-        let mut cur = self.bounds[lit.affected_bound()].cause;
+        let mut cur = self.bounds[self.resolve(lit.affected_bound())].cause;
         while let Some(loc) = cur {
             let ev = self.events.get_event(loc);
             if ev.makes_true(lit) {
@@ -210,6 +438,48 @@ impl Domains {
 
     // State management
 
+    /// Captures the current bounds of all variables, that can be later reinstated with `restore_bounds`.
+    /// This is much cheaper than cloning the whole domains as only the bounds are copied.
+    pub fn snapshot_bounds(&self) -> BoundsSnapshot {
+        BoundsSnapshot {
+            bounds: self.bounds.clone(),
+            num_events: self.events.len(),
+            decision_level: self.events.current_decision_level(),
+        }
+    }
+
+    /// Resets the bounds of all variables that existed when the snapshot was taken to their value in it.
+    /// Variables created after the snapshot keep their current bounds.
+    /// The events recorded since the snapshot are dropped from the trail, so that the causes of the restored
+    /// bounds remain consistent with it.
+    ///
+    /// Only bounds and the event trail are restored: holes and aliases are left untouched, watchers of the domains
+    /// are not notified and any structure derived from the bound changes made since the snapshot (e.g. learnt
+    /// clauses or inferences of a propagator) is not undone. It is thus intended for resetting domains at the
+    /// decision level the snapshot was taken at, typically the root, before the domains are used again from scratch.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the decision level differs from the one of the snapshot or if the trail was backtracked
+    /// past the snapshot.
+    pub fn restore_bounds(&mut self, snapshot: &BoundsSnapshot) {
+        assert_eq!(
+            self.events.current_decision_level(),
+            snapshot.decision_level,
+            "Restoring bounds at a different decision level than the one of the snapshot"
+        );
+        assert!(
+            self.events.len() >= snapshot.num_events,
+            "Restoring bounds after backtracking past the snapshot"
+        );
+        while self.events.len() > snapshot.num_events {
+            self.events.pop();
+        }
+        for (var_bound, value) in snapshot.bounds.entries() {
+            self.bounds[var_bound] = *value;
+        }
+    }
+
     fn undo_event(bounds: &mut RefVec<VarBound, ValueCause>, ev: &Event) {
         bounds[ev.affected_bound] = ev.previous;
     }
@@ -224,6 +494,8 @@ impl Domains {
 
 impl Backtrack for Domains {
     fn save_state(&mut self) -> DecLvl {
+        self.hole_events.save_state();
+        self.alias_events.save_state();
         self.events.save_state()
     }
 
@@ -235,6 +507,20 @@ impl Backtrack for Domains {
         let bounds = &mut self.bounds;
         self.events.restore_last_with(|ev| {
             Self::undo_event(bounds, &ev);
+        });
+        let holes = &mut self.holes;
+        self.hole_events.restore_last_with(|HoleEvent { var, value, .. }| {
+            holes[var].remove(value);
+            if holes[var].removed.is_empty() {
+                // forget about the base of the holes, that may be above the lower bound once backtracked
+                holes.remove(var);
+            }
+        });
+        let aliases = &mut self.aliases;
+        let num_aliases = &mut self.num_aliases;
+        self.alias_events.restore_last_with(|var| {
+            aliases.remove(var);
+            *num_aliases -= 1;
         })
     }
 }
@@ -242,6 +528,16 @@ impl Backtrack for Domains {
 #[cfg(test)]
 mod tests {
     use crate::int_model::domains::Domains;
+    use crate::int_model::{Cause, EmptyDomain};
+    use crate::lang::INT_CST_MAX;
+    use aries_backtrack::Backtrack;
+
+    #[test]
+    fn test_value_cause_layout() {
+        use crate::int_model::domains::ValueCause;
+        assert_eq!(std::mem::size_of::<ValueCause>(), 16);
+        assert_eq!(std::mem::align_of::<ValueCause>(), 16);
+    }
 
     #[test]
     fn test_entails() {
@@ -263,4 +559,84 @@ mod tests {
         assert!(!m.entails(a.leq(8)));
         assert!(!m.entails(a.leq(0)));
     }
+
+    #[test]
+    fn test_holes() {
+        let mut m = Domains::default();
+        let a = m.new_var(0, 4);
+
+        m.save_state();
+        assert_eq!(m.remove_value(a, 2, Cause::Decision), Ok(true));
+        assert!(!m.contains(a, 2));
+        assert_eq!(m.bounds(a), (0, 4));
+        assert_eq!(m.remove_value(a, 2, Cause::Decision), Ok(false));
+        assert_eq!(m.hole_cause(a, 2), Some(Cause::Decision));
+        assert_eq!(m.hole_cause(a, 3), None);
+
+        // bound updates skip the hole
+        m.save_state();
+        assert_eq!(m.set_lb(a, 2, Cause::Decision), Ok(true));
+        assert_eq!(m.bounds(a), (3, 4));
+        m.restore_last();
+        assert_eq!(m.bounds(a), (0, 4));
+        assert_eq!(m.set_ub(a, 2, Cause::Decision), Ok(true));
+        assert_eq!(m.bounds(a), (0, 1));
+
+        // removing a bound narrows the domain
+        assert_eq!(m.remove_value(a, 1, Cause::Decision), Ok(true));
+        assert_eq!(m.bounds(a), (0, 0));
+        assert_eq!(m.remove_value(a, 0, Cause::Decision), Err(EmptyDomain(a)));
+
+        // backtracking restores the removed value
+        m.restore_last();
+        assert_eq!(m.bounds(a), (0, 4));
+        assert!(m.contains(a, 2));
+        assert_eq!(m.hole_cause(a, 2), None);
+        assert_eq!(m.set_lb(a, 2, Cause::Decision), Ok(true));
+        assert_eq!(m.bounds(a), (2, 4));
+    }
+
+    #[test]
+    fn test_aliases() {
+        let mut m = Domains::default();
+        let a = m.new_var(0, 10);
+        let b = m.new_var(2, 12);
+
+        m.save_state();
+        assert_eq!(m.alias(a, b, Cause::Decision), Ok(()));
+        assert_eq!(m.representative(b), a);
+        assert_eq!(m.bounds(a), (2, 10));
+        assert_eq!(m.bounds(b), (2, 10));
+
+        // updates on either variable are visible on both
+        assert_eq!(m.set_ub(b, 7, Cause::Decision), Ok(true));
+        assert_eq!(m.bounds(a), (2, 7));
+        assert_eq!(m.set_lb(a, 4, Cause::Decision), Ok(true));
+        assert_eq!(m.bounds(b), (4, 7));
+        assert!(m.entails(b.geq(4)));
+        assert!(m.entails(a.leq(7)));
+
+        // backtracking undoes the alias
+        m.restore_last();
+        assert_eq!(m.representative(b), b);
+        assert_eq!(m.bounds(a), (0, 10));
+        assert_eq!(m.bounds(b), (2, 12));
+        assert_eq!(m.set_ub(b, 11, Cause::Decision), Ok(true));
+        assert_eq!(m.bounds(a), (0, 10));
+
+        // incompatible domains cannot be merged
+        let c = m.new_var(20, 30);
+        assert_eq!(m.alias(a, c, Cause::Decision), Err(EmptyDomain(a)));
+
+        // holes are copied to the representative, regardless of the width of the domains
+        let d = m.new_var(0, INT_CST_MAX);
+        let e = m.new_var(0, INT_CST_MAX);
+        assert_eq!(m.remove_value(e, 5, Cause::Decision), Ok(true));
+        m.save_state();
+        assert_eq!(m.alias(d, e, Cause::Decision), Ok(()));
+        assert!(!m.contains(d, 5));
+        assert!(m.contains(d, 4));
+        m.restore_last();
+        assert!(m.contains(d, 5));
+    }
 }
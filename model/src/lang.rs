@@ -11,7 +11,10 @@ use std::hash::Hash;
 use aries_collections::create_ref_type;
 
 /// Type representing an integer constant.
-pub type IntCst = i32;
+///
+/// This is also the type of the weights of temporal networks (`aries_tnet::stn::W`).
+/// It is 64 bits wide to leave room for the sums of long durations on large horizons.
+pub type IntCst = i64;
 
 /// Overflow tolerant min value for integer constants.
 /// It is used a default for the lower bound of integer variable domains
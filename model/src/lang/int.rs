@@ -58,7 +58,7 @@ impl From<IVar> for IAtom {
     }
 }
 impl From<IntCst> for IAtom {
-    fn from(i: i32) -> Self {
+    fn from(i: IntCst) -> Self {
         IAtom::new(None, i)
     }
 }
@@ -6,6 +6,7 @@ mod label;
 pub mod lang;
 mod model;
 pub mod symbols;
+pub mod table;
 pub mod types;
 
 // reexport the Label type
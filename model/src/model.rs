@@ -5,6 +5,7 @@ use crate::lang::*;
 use aries_backtrack::{Backtrack, DecLvl, ObsTrail};
 
 use crate::symbols::SymbolTable;
+use crate::table::Table;
 use crate::types::TypeId;
 use crate::Label;
 use aries_collections::ref_store::RefMap;
@@ -13,8 +14,22 @@ use std::cmp::Ordering;
 use crate::bounds::{Bound, Relation};
 use crate::int_model::domains::Event;
 use aries_utils::Fmt;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
+/// Maximal number of literals for which `Model::at_most_one` uses a pairwise encoding.
+/// Larger sets of literals are encoded with a sequential counter.
+pub const AT_MOST_ONE_PAIRWISE_LIMIT: usize = 6;
+
+/// Result of adding a `<=` constraint with `Model::add_leq_simplified`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum LeqStatus {
+    /// The constraint is not implied by the previously added ones. The atom representing it should be enforced.
+    Novel(BAtom),
+    /// The constraint is implied by the previously added ones and can be dropped.
+    Subsumed,
+}
+
 pub struct Model {
     pub symbols: Arc<SymbolTable>,
     pub discrete: DiscreteModel,
@@ -24,6 +39,12 @@ pub struct Model {
     pub tautology: Bound,
     assignments: Vec<SavedAssignment>,
     num_writers: u8,
+    /// Transitive closure of the relations added with `add_leq_simplified`, indexed by their first variable:
+    /// an entry `a -> b -> w` states that `a - b <= w`
+    leq_closure: HashMap<VarRef, HashMap<VarRef, IntCst>>,
+    /// Same relations as in `leq_closure`, indexed by their second variable:
+    /// an entry `b -> a -> w` states that `a - b <= w`
+    leq_closure_rev: HashMap<VarRef, HashMap<VarRef, IntCst>>,
 }
 
 impl Model {
@@ -43,6 +64,8 @@ impl Model {
             tautology: Bound::geq(true_var, 1),
             assignments: vec![],
             num_writers: 0,
+            leq_closure: HashMap::new(),
+            leq_closure_rev: HashMap::new(),
         }
     }
 
@@ -59,6 +82,10 @@ impl Model {
         self.create_ivar(lb, ub, None, label)
     }
 
+    /// Creates a new integer variable that is only meaningful if `presence` holds.
+    ///
+    /// Constraints built by the model (e.g. with `leq` or `eq`) are not automatically guarded by the presence of
+    /// their variables: callers must either guard them explicitly or use `guarded` to do so.
     pub fn new_optional_ivar(
         &mut self,
         lb: IntCst,
@@ -82,6 +109,8 @@ impl Model {
         self.create_sym_var(tpe, None, label)
     }
 
+    /// Creates a new symbolic variable that is only meaningful if `presence` holds.
+    /// As for `new_optional_ivar`, constraints on it must be guarded by the caller (see `guarded`).
     pub fn new_optional_sym_var(&mut self, tpe: TypeId, presence: impl Into<BAtom>, label: impl Into<Label>) -> SVar {
         self.create_sym_var(tpe, Some(presence.into()), label)
     }
@@ -106,6 +135,48 @@ impl Model {
         SVar::new(dvar, tpe)
     }
 
+    /// Returns the presence of a variable: the literal it was created with if it is optional, `true` otherwise.
+    pub fn presence_of(&self, var: impl Into<VarRef>) -> BAtom {
+        self.var_presence.get(var.into()).copied().unwrap_or(BAtom::Cst(true))
+    }
+
+    /// Guards a constraint by the presence of all optional variables appearing in it.
+    /// The result holds if the constraint holds or if any of these variables is absent.
+    pub fn guarded(&mut self, constraint: BAtom) -> BAtom {
+        let mut presences = Vec::new();
+        self.collect_presences(constraint.into(), &mut presences);
+        presences.sort_by(BAtom::lexical_cmp);
+        presences.dedup();
+        match presences.as_slice() {
+            [] => constraint,
+            [presence] => self.implies(*presence, constraint),
+            _ => {
+                let all_present = self.and(&presences);
+                self.implies(all_present, constraint)
+            }
+        }
+    }
+
+    /// Appends to `presences` the presence literals of all optional variables appearing in the atom.
+    fn collect_presences(&self, atom: Atom, presences: &mut Vec<BAtom>) {
+        let var: Option<VarRef> = match atom {
+            Atom::Bool(BAtom::Cst(_)) => None,
+            Atom::Bool(BAtom::Bound(b)) => Some(b.variable()),
+            Atom::Bool(BAtom::Expr(e)) => {
+                for &arg in self.expressions.get(e.expr).args.iter() {
+                    self.collect_presences(arg, presences);
+                }
+                None
+            }
+            Atom::Int(i) => i.var.map(VarRef::from),
+            Atom::Sym(SAtom::Var(v)) => Some(v.var),
+            Atom::Sym(SAtom::Cst(_)) => None,
+        };
+        if let Some(&presence) = var.and_then(|v| self.var_presence.get(v)) {
+            presences.push(presence);
+        }
+    }
+
     pub fn unifiable(&self, a: impl Into<Atom>, b: impl Into<Atom>) -> bool {
         let a = a.into();
         let b = b.into();
@@ -119,6 +190,16 @@ impl Model {
         }
     }
 
+    /// Computes once whether each pair of the given atoms is `unifiable`, for repeated O(1) lookups.
+    /// The table reflects the current domains of the variables.
+    pub fn precompute_unifiable(&self, vars: &[Atom]) -> UnifiabilityTable {
+        let mut table = UnifiabilityTable::default();
+        for &atom in vars {
+            table.add(self, atom);
+        }
+        table
+    }
+
     pub fn unifiable_seq<A: Into<Atom> + Copy, B: Into<Atom> + Copy>(&self, a: &[A], b: &[B]) -> bool {
         if a.len() != b.len() {
             false
@@ -138,6 +219,22 @@ impl Model {
         self.discrete.domain_of(ivar)
     }
 
+    /// Restricts the domain of `var` to the single `value`, by setting both its bounds.
+    /// Returns true if the domain was modified, and an error if `value` is not in the domain of `var`.
+    pub fn fix(&mut self, var: IVar, value: IntCst, cause: Cause) -> Result<bool, EmptyDomain> {
+        let lb_changed = self.discrete.set_lb(var, value, cause)?;
+        let ub_changed = self.discrete.set_ub(var, value, cause)?;
+        Ok(lb_changed || ub_changed)
+    }
+
+    /// Returns the value of `var` if its domain is reduced to a single value.
+    pub fn is_fixed(&self, var: IVar) -> Option<IntCst> {
+        match self.bounds(var) {
+            (lb, ub) if lb == ub => Some(lb),
+            _ => None,
+        }
+    }
+
     pub fn intern_bool(&mut self, e: Expr) -> BExpr {
         let handle = self.expressions.intern(e);
         BExpr {
@@ -255,6 +352,67 @@ impl Model {
         }
     }
 
+    /// Records the constraint `a <= b` and returns whether it is implied by the constraints previously
+    /// recorded with this method (through chains of `<=` relations), in which case it can be dropped.
+    /// Otherwise, the returned atom is the one of `leq(a, b)` and it is the responsibility of the caller to enforce it.
+    ///
+    /// Recorded constraints are assumed to hold for the entire lifetime of the model: they are not undone on backtrack.
+    pub fn add_leq_simplified<A: Into<IAtom>, B: Into<IAtom>>(&mut self, a: A, b: B) -> LeqStatus {
+        let a = a.into();
+        let b = b.into();
+        // va + a.shift <= vb + b.shift   <=>   va - vb <= b.shift - a.shift
+        let w = match b.shift.checked_sub(a.shift) {
+            Some(w) => w,
+            None => {
+                // beyond the difference of any two values in [INT_CST_MIN, INT_CST_MAX]: the constraint is trivially
+                // true (resp. false) for a positive (resp. negative) weight, and cannot be recorded in the closure
+                return LeqStatus::Novel(BAtom::Cst(b.shift > a.shift));
+            }
+        };
+        let leq = self.leq(a, b);
+        let (va, vb) = match (a.var, b.var) {
+            (Some(va), Some(vb)) if va != vb => (VarRef::from(va), VarRef::from(vb)),
+            _ => {
+                // not a difference constraint, only a trivially true one can be dropped
+                return match leq {
+                    BAtom::Cst(true) => LeqStatus::Subsumed,
+                    _ => LeqStatus::Novel(leq),
+                };
+            }
+        };
+        if matches!(self.leq_closure.get(&va).and_then(|rels| rels.get(&vb)), Some(&prev) if prev <= w) {
+            return LeqStatus::Subsumed;
+        }
+
+        // all `x` such that `x - va <= wx` and all `y` such that `vb - y <= wy`
+        let mut before = vec![(va, 0)];
+        if let Some(rels) = self.leq_closure_rev.get(&va) {
+            before.extend(rels.iter().map(|(&x, &d)| (x, d)));
+        }
+        let mut after = vec![(vb, 0)];
+        if let Some(rels) = self.leq_closure.get(&vb) {
+            after.extend(rels.iter().map(|(&y, &d)| (y, d)));
+        }
+        for &(x, wx) in &before {
+            for &(y, wy) in &after {
+                if x == y {
+                    continue;
+                }
+                // a relation whose weight overflows is not recorded: the closure may miss implied relations,
+                // but never contains relations that are not implied
+                let d = match wx.checked_add(w).and_then(|d| d.checked_add(wy)) {
+                    Some(d) => d,
+                    None => continue,
+                };
+                let rels = self.leq_closure.entry(x).or_default();
+                let d = rels.get(&y).map_or(d, |&prev| prev.min(d));
+                rels.insert(y, d);
+                self.leq_closure_rev.entry(y).or_default().insert(x, d);
+            }
+        }
+        LeqStatus::Novel(leq)
+    }
+
     pub fn geq<A: Into<IAtom>, B: Into<IAtom>>(&mut self, a: A, b: B) -> BAtom {
         self.leq(b, a)
     }
@@ -326,6 +484,112 @@ impl Model {
         self.intern_bool(implication).into()
     }
 
+    /// Returns a boolean atom that holds iff `a` and `b` have the same value, encoded as `(a => b) & (b => a)`.
+    pub fn iff<A: Into<BAtom>, B: Into<BAtom>>(&mut self, a: A, b: B) -> BAtom {
+        let a = a.into();
+        let b = b.into();
+        if a == b {
+            return true.into();
+        } else if a == !b {
+            return false.into();
+        }
+        let a_implies_b = self.implies(a, b);
+        let b_implies_a = self.implies(b, a);
+        self.and2(a_implies_b, b_implies_a)
+    }
+
+    /// Returns a boolean atom that holds iff at most one of the literals is true.
+    ///
+    /// Sets of at most `AT_MOST_ONE_PAIRWISE_LIMIT` literals are encoded by forbidding each pair of literals to be
+    /// true together. Larger sets are encoded with a sequential counter, whose size is linear in the number of literals.
+    pub fn at_most_one(&mut self, lits: &[BAtom]) -> BAtom {
+        if lits.len() <= AT_MOST_ONE_PAIRWISE_LIMIT {
+            self.at_most_one_pairwise(lits)
+        } else {
+            self.at_most_one_sequential(lits)
+        }
+    }
+
+    /// Returns a boolean atom that holds iff exactly one of the literals is true.
+    pub fn exactly_one(&mut self, lits: &[BAtom]) -> BAtom {
+        let at_most_one = self.at_most_one(lits);
+        let at_least_one = self.or(lits);
+        self.and2(at_most_one, at_least_one)
+    }
+
+    fn at_most_one_pairwise(&mut self, lits: &[BAtom]) -> BAtom {
+        let mut conjuncts = Vec::with_capacity(lits.len() * lits.len() / 2);
+        for (i, &a) in lits.iter().enumerate() {
+            for &b in &lits[i + 1..] {
+                conjuncts.push(self.or2(!a, !b));
+            }
+        }
+        self.and(&conjuncts)
+    }
+
+    fn at_most_one_sequential(&mut self, lits: &[BAtom]) -> BAtom {
+        let mut conjuncts = Vec::with_capacity(lits.len());
+        // holds iff any of the literals seen so far is true
+        let mut any_before = match lits.first() {
+            Some(&first) => first,
+            None => return true.into(),
+        };
+        for &lit in &lits[1..] {
+            conjuncts.push(self.implies(any_before, !lit));
+            any_before = self.or2(any_before, lit);
+        }
+        self.and(&conjuncts)
+    }
+
+    /// Returns a boolean atom that holds iff the values of `vars` match one of the lines of the table.
+    pub fn in_table(&mut self, vars: &[Atom], table: &Table<IntCst>) -> BAtom {
+        let mut supported_by_a_line = Vec::with_capacity(256);
+        for values in table.lines() {
+            assert_eq!(vars.len(), values.len());
+            let mut supported_by_this_line = Vec::with_capacity(16);
+            for (&var, &val) in vars.iter().zip(values.iter()) {
+                supported_by_this_line.push(self.eq(var, val));
+            }
+            supported_by_a_line.push(self.and(&supported_by_this_line));
+        }
+        self.or(&supported_by_a_line)
+    }
+
+    /// Returns all variables on which the boolean atom depends, i.e., the variables appearing at the leaves of its
+    /// expression DAG. The result is sorted and free of duplicates.
+    pub fn support(&self, b: BAtom) -> Vec<Variable> {
+        let mut support = Vec::new();
+        let mut visited = HashSet::new();
+        let mut queue = vec![Atom::from(b)];
+        while let Some(atom) = queue.pop() {
+            match atom {
+                Atom::Bool(BAtom::Cst(_)) => {}
+                Atom::Bool(BAtom::Bound(b)) => support.push(self.variable_of(b.variable())),
+                Atom::Bool(BAtom::Expr(BExpr { expr, .. })) => {
+                    if visited.insert(expr) {
+                        queue.extend(self.expressions.get(expr).args.iter().copied());
+                    }
+                }
+                Atom::Int(i) => support.extend(i.var.map(Variable::Int)),
+                Atom::Sym(SAtom::Var(v)) => support.push(Variable::Sym(v)),
+                Atom::Sym(SAtom::Cst(_)) => {}
+            }
+        }
+        support.sort();
+        support.dedup();
+        support
+    }
+
+    /// Returns the typed variable corresponding to the given variable reference.
+    /// Variables not declared with a type are assumed to be booleans.
+    fn variable_of(&self, var: VarRef) -> Variable {
+        match self.types.get(var) {
+            Some(Type::Int) => Variable::Int(IVar::new(var)),
+            Some(&Type::Sym(tpe)) => Variable::Sym(SVar::new(var, tpe)),
+            Some(Type::Bool) | None => Variable::Bool(BVar::new(var)),
+        }
+    }
+
     // =========== Formatting ==============
 
     /// Wraps an atom into a custom object that can be formatted with the standard library `Display`
@@ -437,6 +701,8 @@ impl Clone for Model {
             tautology: self.tautology,
             assignments: self.assignments.clone(),
             num_writers: self.num_writers,
+            leq_closure: self.leq_closure.clone(),
+            leq_closure_rev: self.leq_closure_rev.clone(),
         }
     }
 }
@@ -512,6 +778,53 @@ impl Assignment for Model {
     }
 }
 
+/// Result of `Model::unifiable` for all pairs of a set of atoms, as computed by `Model::precompute_unifiable`.
+#[derive(Clone, Default)]
+pub struct UnifiabilityTable {
+    /// Index of each atom in the table
+    index: HashMap<Atom, usize>,
+    /// Lower triangular matrix: `unifiable[i][j]` (with `j <= i`) is true if the i-th and j-th atoms are unifiable.
+    unifiable: Vec<Vec<bool>>,
+}
+
+impl UnifiabilityTable {
+    /// Adds an atom to the table, computing its unifiability with all atoms already in it.
+    /// Does nothing if the atom is already present.
+    pub fn add(&mut self, model: &Model, atom: impl Into<Atom>) {
+        let atom = atom.into();
+        if self.index.contains_key(&atom) {
+            return;
+        }
+        let mut row = vec![false; self.unifiable.len() + 1];
+        for (&other, &j) in &self.index {
+            row[j] = model.unifiable(atom, other);
+        }
+        row[self.unifiable.len()] = model.unifiable(atom, atom);
+        self.index.insert(atom, self.unifiable.len());
+        self.unifiable.push(row);
+    }
+
+    /// Returns true if the two atoms are unifiable.
+    ///
+    /// # Panics
+    ///
+    /// Panics if one of the atoms is not in the table.
+    pub fn unifiable(&self, a: impl Into<Atom>, b: impl Into<Atom>) -> bool {
+        let i = self.index[&a.into()];
+        let j = self.index[&b.into()];
+        if i >= j {
+            self.unifiable[i][j]
+        } else {
+            self.unifiable[j][i]
+        }
+    }
+
+    /// Returns true if the two sequences have the same length and all their elements are pairwise unifiable.
+    pub fn unifiable_seq<A: Into<Atom> + Copy, B: Into<Atom> + Copy>(&self, a: &[A], b: &[B]) -> bool {
+        a.len() == b.len() && a.iter().zip(b.iter()).all(|(&a, &b)| self.unifiable(a, b))
+    }
+}
+
 /// Provides write access to a model for a particular module.
 pub struct WModel<'a> {
     model: &'a mut Model,
@@ -585,3 +898,142 @@ impl Assignment for WModel<'_> {
         self.model.to_owned_assignment()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::symbols::tests::table;
+
+    #[test]
+    fn support() {
+        let mut model = Model::new();
+        let a = model.new_ivar(0, 10, "a");
+        let b = model.new_ivar(0, 10, "b");
+        let c = model.new_ivar(0, 10, "c");
+        let x = model.new_bvar("x");
+        let ab = model.leq(a, b);
+        let bc = model.leq(b, c);
+        let conj = model.and2(ab, bc);
+        let expected: Vec<Variable> = vec![a.into(), b.into(), c.into()];
+        assert_eq!(model.support(conj), expected);
+        assert_eq!(model.support(!conj), expected);
+
+        let disj = model.or2(conj, x.true_lit().into());
+        assert_eq!(model.support(disj), vec![x.into(), a.into(), b.into(), c.into()]);
+        assert_eq!(model.support(true.into()), vec![]);
+    }
+
+    #[test]
+    fn leq_chains() {
+        let mut model = Model::new();
+        let a = model.new_ivar(0, 10, "a");
+        let b = model.new_ivar(0, 10, "b");
+        let c = model.new_ivar(0, 10, "c");
+        let d = model.new_ivar(0, 10, "d");
+        let ac = model.leq(a, c);
+
+        assert!(matches!(model.add_leq_simplified(a, b), LeqStatus::Novel(_)));
+        assert!(matches!(model.add_leq_simplified(b, c), LeqStatus::Novel(_)));
+        assert_eq!(model.add_leq_simplified(a, c), LeqStatus::Subsumed);
+        assert_eq!(model.leq(a, c), ac);
+
+        // weaker constraints are subsumed, stronger ones are not
+        assert_eq!(model.add_leq_simplified(a, c + 2), LeqStatus::Subsumed);
+        assert!(matches!(model.add_leq_simplified(a + 1, c), LeqStatus::Novel(_)));
+
+        // closure is maintained when a chain is extended on both ends
+        assert!(matches!(model.add_leq_simplified(d, a), LeqStatus::Novel(_)));
+        assert_eq!(model.add_leq_simplified(d + 1, c), LeqStatus::Subsumed);
+        assert!(matches!(model.add_leq_simplified(c, d + 3), LeqStatus::Novel(_)));
+        assert_eq!(model.add_leq_simplified(b, d + 3), LeqStatus::Subsumed);
+        assert_eq!(model.add_leq_simplified(a, d + 2), LeqStatus::Subsumed);
+        assert!(matches!(model.add_leq_simplified(a, d + 1), LeqStatus::Novel(_)));
+
+        // relations whose weight would overflow are not derived
+        let e = model.new_ivar(0, 10, "e");
+        let f = model.new_ivar(0, 10, "f");
+        let g = model.new_ivar(0, 10, "g");
+        let big = IntCst::MAX;
+        assert!(matches!(model.add_leq_simplified(e, f + big), LeqStatus::Novel(_)));
+        assert!(matches!(model.add_leq_simplified(f, g + big), LeqStatus::Novel(_)));
+        assert!(matches!(model.add_leq_simplified(e, g), LeqStatus::Novel(_)));
+        // constraints whose weight overflows are not recorded
+        assert_eq!(
+            model.add_leq_simplified(e + IntCst::MIN, f + 1),
+            LeqStatus::Novel(BAtom::Cst(true))
+        );
+        assert_eq!(
+            model.add_leq_simplified(e + 1, f + IntCst::MIN),
+            LeqStatus::Novel(BAtom::Cst(false))
+        );
+        assert!(matches!(model.add_leq_simplified(f, e), LeqStatus::Novel(_)));
+    }
+
+    #[test]
+    fn fix_variable() {
+        let mut model = Model::new();
+        let a = model.new_ivar(0, 10, "a");
+        let b = model.new_ivar(0, 10, "b");
+        assert_eq!(model.is_fixed(a), None);
+
+        assert_eq!(model.fix(a, 4, Cause::Decision), Ok(true));
+        assert_eq!(model.discrete.domain_of(a), (4, 4));
+        assert_eq!(model.is_fixed(a), Some(4));
+        assert_eq!(model.fix(a, 4, Cause::Decision), Ok(false));
+        assert!(model.fix(a, 5, Cause::Decision).is_err());
+
+        // fixing to a bound only changes the other one
+        assert_eq!(model.fix(b, 10, Cause::Decision), Ok(true));
+        assert_eq!(model.is_fixed(b), Some(10));
+    }
+
+    #[test]
+    fn unifiability_table() {
+        let symbols = Arc::new(table());
+        let mut model = Model::new_with_symbols(symbols.clone());
+        let rover = symbols.types.id_of("rover").unwrap();
+        let location = symbols.types.id_of("location").unwrap();
+        let object = symbols.types.id_of("object").unwrap();
+        let cst = |name: &str| -> Atom {
+            SAtom::new_constant(symbols.id(name).unwrap(), symbols.type_of(symbols.id(name).unwrap())).into()
+        };
+
+        let atoms: Vec<Atom> = vec![
+            cst("rover1"),
+            cst("l1"),
+            cst("l2"),
+            model.new_sym_var(rover, "r").into(),
+            model.new_sym_var(location, "l").into(),
+            model.new_sym_var(object, "o").into(),
+            model.new_ivar(0, 10, "a").into(),
+            model.new_ivar(5, 15, "b").into(),
+            model.new_ivar(11, 20, "c").into(),
+            IAtom::from(3).into(),
+            IAtom::from(12).into(),
+            BAtom::from(model.new_bvar("x")).into(),
+            BAtom::from(true).into(),
+        ];
+        let table = model.precompute_unifiable(&atoms);
+        for &a in &atoms {
+            for &b in &atoms {
+                assert_eq!(table.unifiable(a, b), model.unifiable(a, b), "{:?} {:?}", a, b);
+            }
+        }
+        // sanity check that the comparison is not trivial
+        assert!(table.unifiable(atoms[4], atoms[1]));
+        assert!(!table.unifiable(atoms[3], atoms[1]));
+        assert!(!table.unifiable(atoms[6], atoms[8]));
+        assert!(table.unifiable_seq(&atoms[0..2], &[atoms[5], atoms[4]]));
+
+        // extending the table does not change the result of previous atoms
+        let mut extended = table.clone();
+        let d: Atom = model.new_ivar(0, 2, "d").into();
+        extended.add(&model, d);
+        assert!(extended.unifiable(d, atoms[6]));
+        assert!(!extended.unifiable(d, atoms[7]));
+        assert_eq!(
+            extended.unifiable(atoms[6], atoms[7]),
+            table.unifiable(atoms[6], atoms[7])
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use crate::lang::IntCst;
 use crate::types::{TypeHierarchy, TypeId};
 use anyhow::*;
 use aries_collections::create_ref_type;
@@ -177,6 +178,17 @@ impl SymbolTable {
         instance
     }
 
+    /// Returns an iterator on all instances of the given type.
+    /// If `include_subtypes` is true, this includes the instances of all direct or indirect subtypes,
+    /// otherwise only the symbols whose type is exactly `tpe` are returned.
+    pub fn symbols_of_type(&self, tpe: TypeId, include_subtypes: bool) -> impl Iterator<Item = SymId> {
+        if include_subtypes {
+            self.instances_of_type(tpe)
+        } else {
+            self.instances_by_exact_type[tpe]
+        }
+    }
+
     /// Returns a formated view of an S-Expression
     pub fn format<'a>(&self, sexpr: impl IntoIterator<Item = &'a SymId>) -> String
     where
@@ -197,8 +209,8 @@ impl SymbolTable {
 create_ref_type!(SymId);
 
 impl SymId {
-    pub fn int_value(self) -> i32 {
-        usize::from(self) as i32
+    pub fn int_value(self) -> IntCst {
+        usize::from(self) as IntCst
     }
 }
 
@@ -240,6 +252,37 @@ pub mod tests {
         assert_eq!(symbols.instances_of_type(location).count(), 2);
     }
 
+    #[test]
+    fn symbols_of_type() {
+        let types = vec![
+            ("vehicle".into(), None),
+            ("car".into(), Some("vehicle".into())),
+            ("truck".into(), Some("vehicle".into())),
+            ("location".into(), None),
+        ];
+        let types = TypeHierarchy::new(types).unwrap();
+        let instances = vec![
+            ("v".into(), "vehicle".into()),
+            ("c1".into(), "car".into()),
+            ("c2".into(), "car".into()),
+            ("t1".into(), "truck".into()),
+            ("l1".into(), "location".into()),
+        ];
+        let symbols = SymbolTable::new(types, instances).unwrap();
+        let of_type = |tpe: &str, include_subtypes: bool| {
+            let tpe = symbols.types.id_of(tpe).unwrap();
+            symbols
+                .symbols_of_type(tpe, include_subtypes)
+                .map(|s| symbols.symbol(s).to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(of_type("vehicle", true), vec!["v", "c1", "c2", "t1"]);
+        assert_eq!(of_type("vehicle", false), vec!["v"]);
+        assert_eq!(of_type("car", true), vec!["c1", "c2"]);
+        assert_eq!(of_type("truck", false), vec!["t1"]);
+        assert_eq!(of_type("location", true), vec!["l1"]);
+    }
+
     #[test]
     fn enumeration() {
         let symbols = table();
@@ -0,0 +1,36 @@
+use crate::lang::Type;
+
+/// A set of tuples, representing the allowed values in a table constraint.
+#[derive(Clone)]
+pub struct Table<E> {
+    /// Number of elements in the tuple
+    line_size: usize,
+    /// Type of the values in the tuples (length = line_size)
+    types: Vec<Type>,
+    /// linear representation of a matrix (each line occurs right after the previous one)
+    inner: Vec<E>,
+}
+
+impl<E: Clone> Table<E> {
+    pub fn new(types: Vec<Type>) -> Table<E> {
+        Table {
+            line_size: types.len(),
+            types,
+            inner: Vec::new(),
+        }
+    }
+
+    /// Types of the values in each line of the table.
+    pub fn types(&self) -> &[Type] {
+        &self.types
+    }
+
+    pub fn push(&mut self, line: &[E]) {
+        assert!(line.len() == self.line_size);
+        self.inner.extend_from_slice(line);
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = &[E]> {
+        self.inner.chunks(self.line_size)
+    }
+}
@@ -83,7 +83,7 @@ impl TypeHierarchy {
         self.types.get_ref(tpe)
     }
     pub fn from_id(&self, tid: TypeId) -> &Sym {
-        self.types.get(tid)
+        &self.types[tid]
     }
 
     pub fn is_subtype(&self, tpe: TypeId, possible_subtype: TypeId) -> bool {
@@ -97,7 +97,7 @@ impl TypeHierarchy {
     }
 
     /// Iterator on all Types by increasing usize value
-    pub fn types(&self) -> impl Iterator<Item = TypeId> {
+    pub fn types(&self) -> impl Iterator<Item = TypeId> + '_ {
         self.types.keys()
     }
 }
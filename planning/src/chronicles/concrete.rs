@@ -1,4 +1,5 @@
 use crate::chronicles::constraints::Constraint;
+use crate::chronicles::Ctx;
 use aries_model::bounds::Bound;
 use aries_model::lang::*;
 
@@ -117,7 +118,7 @@ impl Substitute for Vec<Atom> {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
 pub struct Effect {
     pub transition_start: Time,
     pub persistence_start: Time,
@@ -150,7 +151,7 @@ impl Substitute for Effect {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
 pub struct Condition {
     pub start: Time,
     pub end: Time,
@@ -231,6 +232,80 @@ pub struct Chronicle {
     pub effects: Vec<Effect>,
     pub constraints: Vec<Constraint>,
     pub subtasks: Vec<SubTask>,
+    /// Cost incurred by the plan if the chronicle is present, e.g., from an `(increase (total-cost) n)` effect.
+    pub cost: Option<IntCst>,
+}
+
+impl Chronicle {
+    /// Returns a human readable representation of the chronicle, where all atoms are formatted
+    /// with the labels and symbols of the context.
+    /// Conditions are displayed as `[start, end] sv == value` and effects as `[trans, pers] sv := value`.
+    pub fn format(&self, ctx: &Ctx) -> String {
+        let model = &ctx.model;
+        let fmt_sv = |sv: &[SAtom]| {
+            let parts: Vec<String> = sv.iter().map(|&x| model.fmt(x).to_string()).collect();
+            format!("({})", parts.join(" "))
+        };
+        let mut s = String::new();
+        s.push_str(&format!("{:?} {}", self.kind, fmt_sv(&self.name)));
+        s.push_str(&format!(" [{}, {}]\n", model.fmt(self.start), model.fmt(self.end)));
+        s.push_str(&format!("  presence: {}\n", model.fmt(self.presence)));
+        if let Some(cost) = self.cost {
+            s.push_str(&format!("  cost: {}\n", cost));
+        }
+        s.push_str("  conditions:\n");
+        for c in &self.conditions {
+            s.push_str(&format!(
+                "    [{}, {}] {} == {}\n",
+                model.fmt(c.start),
+                model.fmt(c.end),
+                fmt_sv(&c.state_var),
+                model.fmt(c.value)
+            ));
+        }
+        s.push_str("  effects:\n");
+        for e in &self.effects {
+            s.push_str(&format!(
+                "    [{}, {}] {} := {}\n",
+                model.fmt(e.transition_start),
+                model.fmt(e.persistence_start),
+                fmt_sv(&e.state_var),
+                model.fmt(e.value)
+            ));
+        }
+        s
+    }
+
+    /// Checks that the time points of the chronicle are ordered as assumed by its encoding: the chronicle and each
+    /// of its conditions must not end before they start, and no effect may persist before its transition starts.
+    ///
+    /// Only the relationships that are statically known are checked, i.e., between two constants or two time points
+    /// on the same variable. Returns a description of the first offending element otherwise.
+    pub fn validate_timing(&self) -> Result<(), String> {
+        if self.start > self.end {
+            return Err(format!(
+                "the chronicle ends ({:?}) before it starts ({:?})",
+                self.end, self.start
+            ));
+        }
+        for (i, c) in self.conditions.iter().enumerate() {
+            if c.start > c.end {
+                return Err(format!(
+                    "condition {} ends ({:?}) before it starts ({:?})",
+                    i, c.end, c.start
+                ));
+            }
+        }
+        for (i, e) in self.effects.iter().enumerate() {
+            if e.transition_start > e.persistence_start {
+                return Err(format!(
+                    "effect {} persists ({:?}) before its transition starts ({:?})",
+                    i, e.persistence_start, e.transition_start
+                ));
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Substitute for Chronicle {
@@ -246,6 +321,92 @@ impl Substitute for Chronicle {
             effects: self.effects.iter().map(|e| e.substitute(s)).collect(),
             constraints: self.constraints.iter().map(|c| c.substitute(s)).collect(),
             subtasks: self.subtasks.iter().map(|c| c.substitute(s)).collect(),
+            cost: self.cost,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::chronicles::constraints::Constraint;
+    use crate::chronicles::Problem;
+    use crate::parsing::pddl::{parse_pddl_domain, parse_pddl_problem};
+    use crate::parsing::pddl_to_chronicles;
+    use aries_model::lang::IAtom;
+    use aries_utils::input::Input;
+    use std::collections::HashSet;
+
+    fn rooms() -> Problem {
+        let domain = "(define (domain rooms)
+          (:requirements :strips :typing)
+          (:types room)
+          (:predicates (at ?r - room) (connected ?from ?to - room))
+          (:action move
+            :parameters (?from ?to - room)
+            :precondition (and (at ?from) (connected ?from ?to))
+            :effect (and (not (at ?from)) (at ?to))))";
+        let problem = "(define (problem rooms-1)
+          (:domain rooms)
+          (:objects kitchen bedroom - room)
+          (:init (at kitchen) (connected kitchen bedroom))
+          (:goal (at bedroom)))";
+        let dom = parse_pddl_domain(Input::from_string(domain)).unwrap();
+        let prob = parse_pddl_problem(Input::from_string(problem)).unwrap();
+        pddl_to_chronicles(&dom, &prob).unwrap()
+    }
+
+    #[test]
+    fn inverted_effect_timing() {
+        let spec = rooms();
+        let mut chronicle = spec.templates[0].chronicle.clone();
+        assert_eq!(chronicle.validate_timing(), Ok(()));
+
+        chronicle.effects[1].transition_start = IAtom::from(5);
+        chronicle.effects[1].persistence_start = IAtom::from(3);
+        let err = chronicle.validate_timing().unwrap_err();
+        assert!(err.starts_with("effect 1 persists"), "{}", err);
+    }
+
+    #[test]
+    fn format_chronicle() {
+        let spec = rooms();
+        let template = &spec.templates[0];
+        let formatted = template.chronicle.format(&spec.context);
+        assert!(formatted.contains("(move "), "{}", formatted);
+        assert!(formatted.contains("(connected "), "{}", formatted);
+        assert!(formatted.contains("== true"), "{}", formatted);
+        assert!(formatted.contains("(at "), "{}", formatted);
+        assert!(formatted.contains(":= false"), "{}", formatted);
+
+        let init = &spec.chronicles[0].chronicle;
+        let formatted = init.format(&spec.context);
+        assert!(formatted.contains("(at kitchen) := true"), "{}", formatted);
+    }
+
+    #[test]
+    fn deduplication() {
+        let spec = rooms();
+        let template = &spec.templates[0].chronicle;
+        let mut effects = HashSet::new();
+        effects.insert(template.effects[0].clone());
+        effects.insert(template.effects[0].clone());
+        assert_eq!(effects.len(), 1);
+
+        let conditions: HashSet<_> = template
+            .conditions
+            .iter()
+            .chain(&template.conditions)
+            .cloned()
+            .collect();
+        assert_eq!(conditions.len(), template.conditions.len());
+
+        let constraints: HashSet<_> = vec![
+            Constraint::lt(template.start, template.end),
+            Constraint::lt(template.start, template.end),
+            Constraint::lt(template.end, template.start),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(constraints.len(), 2);
+    }
+}
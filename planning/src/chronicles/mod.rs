@@ -5,16 +5,20 @@ mod templates;
 
 use aries_model::symbols::{SymId, SymbolTable, TypedSym};
 
-use self::constraints::Table;
-use aries_model::lang::{Atom, IAtom, Type, Variable};
+use self::constraints::{ConstraintType, Table};
+use aries_model::bounds::Bound;
+use aries_model::int_model::Cause;
+use aries_model::lang::{Atom, BAtom, IAtom, IntCst, SAtom, Type, VarRef, Variable};
 use aries_model::Model;
 
+use std::cell::Cell;
+use std::collections::HashMap;
 use std::sync::Arc;
 
 pub use concrete::*;
 
 /// Represents a discrete value (symbol, integer or boolean)
-pub type DiscreteValue = i32;
+pub type DiscreteValue = IntCst;
 
 /// A state function is a symbol and a set of parameter and return types.
 ///
@@ -25,7 +29,7 @@ pub type DiscreteValue = i32;
 /// *state function* `at` to these parameters:
 /// `(at bob kitchen)` is a *state variable* of boolean type.
 // TODO: make internals private
-#[derive(Clone)]
+#[derive(Clone, Eq, PartialEq)]
 pub struct StateFun {
     /// Symbol of this state function
     pub sym: SymId,
@@ -82,6 +86,37 @@ impl Ctx {
             tpe: self.model.symbols.type_of(sym),
         }
     }
+
+    /// Returns all ground state variables of the context, i.e., the application of each state function
+    /// to all combinations of symbols of its argument types.
+    /// The first element of each state variable is the symbol of its state function.
+    ///
+    /// State functions with non-symbolic arguments are ignored as their groundings cannot be enumerated.
+    pub fn ground_state_variables(&self) -> Vec<SV> {
+        let symbols = &self.model.symbols;
+        let mut result = Vec::new();
+        'functions: for sf in &self.state_functions {
+            let mut groundings: Vec<SV> = vec![vec![self.typed_sym(sf.sym).into()]];
+            for &arg_type in sf.argument_types() {
+                let tpe = match arg_type {
+                    Type::Sym(tpe) => tpe,
+                    _ => continue 'functions,
+                };
+                groundings = groundings
+                    .iter()
+                    .flat_map(|prefix| {
+                        symbols.symbols_of_type(tpe, true).map(move |sym| {
+                            let mut sv = prefix.clone();
+                            sv.push(SAtom::from(self.typed_sym(sym)));
+                            sv
+                        })
+                    })
+                    .collect();
+            }
+            result.extend(groundings);
+        }
+        result
+    }
 }
 
 #[derive(Clone)]
@@ -161,6 +196,189 @@ pub struct Problem {
     pub chronicles: Vec<ChronicleInstance>,
 }
 
+impl Problem {
+    /// Returns all ground state variables of the problem, i.e., the application of each state function
+    /// to all combinations of symbols of its argument types.
+    /// The first element of each state variable is the symbol of its state function.
+    ///
+    /// State functions with non-symbolic arguments are ignored as their groundings cannot be enumerated.
+    pub fn ground_state_variables(&self) -> Vec<SV> {
+        self.context.ground_state_variables()
+    }
+
+    /// Returns the goals of the problem (conditions of its `Problem` chronicles) that cannot be supported
+    /// by any effect, neither from a chronicle instance (including the initial one) nor from a template.
+    ///
+    /// This is a cheap static check: an effect is a possible support if its state variable and value are
+    /// unifiable with the ones of the goal. If any goal is returned, the problem is unsatisfiable.
+    pub fn unreachable_goals(&self) -> Vec<Condition> {
+        let model = &self.context.model;
+        let possible_support = |e: &Effect, c: &Condition| -> bool {
+            model.unifiable_seq(&e.state_var, &c.state_var) && model.unifiable(e.value, c.value)
+        };
+        let effects = || {
+            self.chronicles
+                .iter()
+                .flat_map(|ch| ch.chronicle.effects.iter())
+                .chain(self.templates.iter().flat_map(|t| t.chronicle.effects.iter()))
+        };
+        self.chronicles
+            .iter()
+            .filter(|ch| ch.chronicle.kind == ChronicleKind::Problem)
+            .flat_map(|ch| ch.chronicle.conditions.iter())
+            .filter(|&goal| !effects().any(|e| possible_support(e, goal)))
+            .cloned()
+            .collect()
+    }
+
+    /// Appends the templates and chronicles of `other` to this problem, e.g., to extend a domain
+    /// with the templates of a separately written module.
+    ///
+    /// Both problems must share the same symbol table and declare the same state functions.
+    /// All variables of `other` are recreated in the model of this problem, except for its horizon that is
+    /// identified with the one of this problem. The tables of `other` are appended to the ones of this problem
+    /// and references to its tables, templates and chronicles are renumbered accordingly.
+    ///
+    /// Returns an error, leaving this problem unchanged, if the chronicles of `other` contain expressions
+    /// as they cannot be substituted.
+    pub fn merge(&mut self, other: Problem) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            Arc::ptr_eq(&self.context.model.symbols, &other.context.model.symbols),
+            "Cannot merge problems with distinct symbol tables"
+        );
+        anyhow::ensure!(
+            self.context.state_functions == other.context.state_functions,
+            "Cannot merge problems with distinct state functions"
+        );
+
+        // expressions cannot be substituted, check that none appear before modifying this problem
+        let check = MergeSub::default();
+        for chronicle in other.templates.iter().map(|t| &t.chronicle) {
+            chronicle.substitute(&check);
+        }
+        for instance in &other.chronicles {
+            instance.chronicle.substitute(&check);
+        }
+        for var in other.context.model.var_presence.keys() {
+            check.bsub(other.context.model.presence_of(var));
+        }
+        anyhow::ensure!(
+            !check.found_expr.get(),
+            "Cannot merge a problem whose chronicles contain expressions"
+        );
+
+        // map each variable of `other` to a variable of this problem
+        let from = &other.context.model;
+        let into = &mut self.context.model;
+        let mut sub = MergeSub::default();
+        sub.vars.insert(from.tautology.variable(), into.tautology.variable());
+        sub.vars.insert(
+            VarRef::from(other.context.horizon.var.unwrap()),
+            VarRef::from(self.context.horizon.var.unwrap()),
+        );
+        for var in from.discrete.variables() {
+            if sub.vars.contains_key(&var) {
+                continue;
+            }
+            let (lb, ub) = from.discrete.domain_of(var);
+            let fresh = into
+                .discrete
+                .new_discrete_var(lb, ub, from.discrete.label(var).unwrap_or(""));
+            if let Some(&tpe) = from.types.get(var) {
+                into.types.insert(fresh, tpe);
+            }
+            sub.vars.insert(var, fresh);
+        }
+        for var in from.var_presence.keys() {
+            let presence = sub.bsub(from.presence_of(var));
+            into.var_presence.insert(sub.sub_var(var), presence);
+        }
+
+        let num_templates = self.templates.len();
+        let num_chronicles = self.chronicles.len();
+        let num_tables = self.context.tables.len() as u32;
+        let import = |chronicle: &Chronicle| {
+            let mut chronicle = chronicle.substitute(&sub);
+            for constraint in &mut chronicle.constraints {
+                match &mut constraint.tpe {
+                    ConstraintType::InTable { table_id } | ConstraintType::NotInTable { table_id } => {
+                        *table_id += num_tables
+                    }
+                    _ => {}
+                }
+            }
+            chronicle
+        };
+        for template in &other.templates {
+            let parameters = template
+                .parameters
+                .iter()
+                .map(|&param| match param {
+                    Variable::Bool(b) => sub.sub_bvar(b).into(),
+                    Variable::Int(i) => sub.sub_ivar(i).into(),
+                    Variable::Sym(s) => sub.sub_svar(s).into(),
+                })
+                .collect();
+            self.templates.push(ChronicleTemplate {
+                label: template.label.clone(),
+                parameters,
+                chronicle: import(&template.chronicle),
+            });
+        }
+        for instance in &other.chronicles {
+            let origin = match instance.origin {
+                ChronicleOrigin::Original => ChronicleOrigin::Original,
+                ChronicleOrigin::FreeAction {
+                    template_id,
+                    generation_id,
+                } => ChronicleOrigin::FreeAction {
+                    template_id: template_id + num_templates,
+                    generation_id,
+                },
+                ChronicleOrigin::Refinement { instance_id, task_id } => ChronicleOrigin::Refinement {
+                    instance_id: instance_id + num_chronicles,
+                    task_id,
+                },
+            };
+            self.chronicles.push(ChronicleInstance {
+                parameters: instance.parameters.substitute(&sub),
+                origin,
+                chronicle: import(&instance.chronicle),
+            });
+        }
+        self.context.tables.extend(other.context.tables);
+        Ok(())
+    }
+}
+
+/// Substitution of the variables of a problem merged into another one (see `Problem::merge`).
+/// Expressions are left unchanged and recorded in `found_expr`, as they cannot be substituted.
+#[derive(Default)]
+struct MergeSub {
+    vars: HashMap<VarRef, VarRef>,
+    found_expr: Cell<bool>,
+}
+
+impl Substitution for MergeSub {
+    fn sub_var(&self, var: VarRef) -> VarRef {
+        self.vars.get(&var).copied().unwrap_or(var)
+    }
+
+    fn bsub(&self, b: BAtom) -> BAtom {
+        match b {
+            BAtom::Cst(_) => b,
+            BAtom::Bound(b) => {
+                let (var, rel, val) = b.unpack();
+                BAtom::Bound(Bound::new(self.sub_var(var), rel, val))
+            }
+            BAtom::Expr(_) => {
+                self.found_expr.set(true);
+                b
+            }
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct FiniteProblem {
     pub model: Model,
@@ -169,3 +387,227 @@ pub struct FiniteProblem {
     pub chronicles: Vec<ChronicleInstance>,
     pub tables: Vec<Table<DiscreteValue>>,
 }
+
+impl FiniteProblem {
+    /// Forces the `instance_index`-th chronicle to be present in any solution of the problem.
+    ///
+    /// The presence of the chronicle is fixed in the domains of the model,
+    /// and is thus enforced in any encoding of the problem.
+    pub fn force_present(&mut self, instance_index: usize) -> anyhow::Result<()> {
+        let presence = self.chronicles[instance_index].chronicle.presence;
+        match presence {
+            BAtom::Cst(true) => {}
+            BAtom::Cst(false) => anyhow::bail!("Chronicle instance {} can never be present", instance_index),
+            BAtom::Bound(lit) => {
+                if self.model.discrete.decide(lit).is_err() {
+                    anyhow::bail!("Chronicle instance {} can never be present", instance_index);
+                }
+            }
+            BAtom::Expr(_) => anyhow::bail!("Presence of chronicle instance {} is not a literal", instance_index),
+        }
+        Ok(())
+    }
+
+    /// Fixes the `param_index`-th parameter of the `instance_index`-th chronicle to the given `value`,
+    /// by restricting its domain in the model.
+    ///
+    /// Symbolic parameters are fixed through their integer view, i.e., `value` is expected to be the
+    /// integer value of a `SymId`.
+    pub fn fix_parameter(
+        &mut self,
+        instance_index: usize,
+        param_index: usize,
+        value: DiscreteValue,
+    ) -> anyhow::Result<()> {
+        let param = self.chronicles[instance_index].parameters[param_index];
+        let param: IAtom = match param {
+            Atom::Int(i) => i,
+            Atom::Sym(s) => s.int_view(),
+            Atom::Bool(_) => anyhow::bail!(
+                "Cannot fix the boolean parameter {} of chronicle instance {}",
+                param_index,
+                instance_index
+            ),
+        };
+        let fixed = match param.var {
+            Some(var) => {
+                let var_value = value - param.shift;
+                self.model.discrete.set_lb(var, var_value, Cause::Decision).is_ok()
+                    && self.model.discrete.set_ub(var, var_value, Cause::Decision).is_ok()
+            }
+            None => param.shift == value,
+        };
+        if !fixed {
+            anyhow::bail!(
+                "Value {} is not in the domain of parameter {} of chronicle instance {}",
+                value,
+                param_index,
+                instance_index
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chronicles::preprocessing::statics_as_tables;
+    use crate::parsing::pddl::{parse_pddl_domain, parse_pddl_problem};
+    use crate::parsing::pddl_to_chronicles;
+    use aries_model::symbols::SymId;
+    use aries_utils::input::Input;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn ground_state_variables() {
+        let domain = "(define (domain rooms)
+          (:requirements :strips :typing)
+          (:types room)
+          (:predicates (connected ?from ?to - room)))";
+        let problem = "(define (problem rooms-1)
+          (:domain rooms)
+          (:objects kitchen bedroom - room)
+          (:init (connected kitchen bedroom))
+          (:goal (connected kitchen bedroom)))";
+        let dom = parse_pddl_domain(Input::from_string(domain)).unwrap();
+        let prob = parse_pddl_problem(Input::from_string(problem)).unwrap();
+        let spec = pddl_to_chronicles(&dom, &prob).unwrap();
+
+        let symbols = &spec.context.model.symbols;
+        let mut svs: Vec<String> = spec
+            .ground_state_variables()
+            .iter()
+            .map(|sv| {
+                let syms: Vec<SymId> = sv.iter().map(|&x| SymId::try_from(x).unwrap()).collect();
+                symbols.format(&syms)
+            })
+            .collect();
+        svs.sort();
+        assert_eq!(
+            svs,
+            vec![
+                "(connected bedroom bedroom)",
+                "(connected bedroom kitchen)",
+                "(connected kitchen bedroom)",
+                "(connected kitchen kitchen)",
+            ]
+        );
+    }
+
+    #[test]
+    fn unreachable_goals() {
+        let domain = "(define (domain rooms)
+          (:requirements :strips :typing)
+          (:types room robot)
+          (:predicates (at ?r - robot ?l - room) (clean ?l - room) (painted ?l - room))
+          (:action move
+            :parameters (?r - robot ?from ?to - room)
+            :precondition (at ?r ?from)
+            :effect (and (at ?r ?to) (not (at ?r ?from))))
+          (:action sweep
+            :parameters (?r - robot ?l - room)
+            :precondition (at ?r ?l)
+            :effect (clean ?l)))";
+        let problem = "(define (problem rooms-1)
+          (:domain rooms)
+          (:objects kitchen bedroom - room bob - robot)
+          (:init (at bob kitchen))
+          (:goal (and (at bob bedroom) (clean bedroom) (painted bedroom))))";
+        let dom = parse_pddl_domain(Input::from_string(domain)).unwrap();
+        let prob = parse_pddl_problem(Input::from_string(problem)).unwrap();
+        let spec = pddl_to_chronicles(&dom, &prob).unwrap();
+
+        // no action paints a room
+        let symbols = &spec.context.model.symbols;
+        let unreachable: Vec<String> = spec
+            .unreachable_goals()
+            .iter()
+            .map(|goal| {
+                let syms: Vec<SymId> = goal.state_var.iter().map(|&x| SymId::try_from(x).unwrap()).collect();
+                symbols.format(&syms)
+            })
+            .collect();
+        assert_eq!(unreachable, vec!["(painted bedroom)"]);
+    }
+
+    #[test]
+    fn merge() {
+        let domain = "(define (domain rooms)
+          (:requirements :strips :typing)
+          (:types room)
+          (:predicates (at ?r - room) (connected ?from ?to - room))
+          (:action move
+            :parameters (?from ?to - room)
+            :precondition (and (at ?from) (connected ?from ?to))
+            :effect (and (not (at ?from)) (at ?to))))";
+        let problem = "(define (problem rooms-1)
+          (:domain rooms)
+          (:objects kitchen bedroom - room)
+          (:init (at kitchen) (connected kitchen bedroom))
+          (:goal (at bedroom)))";
+        let dom = parse_pddl_domain(Input::from_string(domain)).unwrap();
+        let prob = parse_pddl_problem(Input::from_string(problem)).unwrap();
+        let mut spec = pddl_to_chronicles(&dom, &prob).unwrap();
+        statics_as_tables(&mut spec);
+        let num_templates = spec.templates.len();
+        let num_chronicles = spec.chronicles.len();
+        let num_tables = spec.context.tables.len();
+        assert!(num_tables > 0);
+
+        // a copy of the problem, with an instance of its first template
+        let mut other = spec.clone();
+        let mut instance = other.chronicles[0].clone();
+        instance.origin = ChronicleOrigin::FreeAction {
+            template_id: 0,
+            generation_id: 0,
+        };
+        other.chronicles.push(instance);
+
+        spec.merge(other.clone()).unwrap();
+        assert_eq!(spec.templates.len(), 2 * num_templates);
+        assert_eq!(spec.chronicles.len(), 2 * num_chronicles + 1);
+        assert_eq!(spec.context.tables.len(), 2 * num_tables);
+        assert!(matches!(
+            spec.chronicles.last().unwrap().origin,
+            ChronicleOrigin::FreeAction { template_id, .. } if template_id == num_templates
+        ));
+
+        let table_ids = |t: &ChronicleTemplate| -> Vec<u32> {
+            t.chronicle
+                .constraints
+                .iter()
+                .filter_map(|c| match c.tpe {
+                    ConstraintType::InTable { table_id } => Some(table_id),
+                    _ => None,
+                })
+                .collect()
+        };
+        for (merged, original) in spec.templates[num_templates..].iter().zip(&other.templates) {
+            // table references point to the copies of the original tables
+            let expected: Vec<u32> = table_ids(original).iter().map(|id| id + num_tables as u32).collect();
+            assert!(!expected.is_empty());
+            assert_eq!(table_ids(merged), expected);
+            // parameters are new variables of the model
+            for (p, q) in merged.parameters.iter().zip(&original.parameters) {
+                assert!(p != q);
+            }
+        }
+
+        // an independently parsed problem has its own symbol table
+        let unrelated = pddl_to_chronicles(&dom, &prob).unwrap();
+        assert!(spec.merge(unrelated).is_err());
+
+        // expressions cannot be substituted, the problem is left unchanged
+        let mut with_expr = other.clone();
+        let a = with_expr.context.model.new_ivar(0, 10, "a");
+        let b = with_expr.context.model.new_ivar(0, 10, "b");
+        let expr = with_expr.context.model.leq(a, b);
+        assert!(matches!(expr, BAtom::Expr(_)));
+        with_expr.chronicles[0].chronicle.presence = expr;
+        let num_variables = spec.context.model.discrete.variables().count();
+        assert!(spec.merge(with_expr).is_err());
+        assert_eq!(spec.templates.len(), 2 * num_templates);
+        assert_eq!(spec.context.model.discrete.variables().count(), num_variables);
+    }
+}
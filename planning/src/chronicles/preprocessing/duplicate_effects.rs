@@ -0,0 +1,74 @@
+use crate::chronicles::{Chronicle, Problem};
+
+/// Removes the effects that appear more than once in the same chronicle (instance or template).
+/// Two effects are considered duplicates if they have the same state variable, value and time points.
+pub fn merge_duplicate_effects(pb: &mut Problem) {
+    let instances = pb.chronicles.iter_mut().map(|ch| &mut ch.chronicle);
+    let templates = pb.templates.iter_mut().map(|t| &mut t.chronicle);
+    let num_removed: usize = instances.chain(templates).map(dedup_effects).sum();
+
+    if num_removed > 0 {
+        println!("Removed {} duplicated effects", num_removed);
+    }
+}
+
+/// Removes duplicated effects of the chronicle, keeping the first occurrence of each.
+/// Returns the number of effects removed.
+fn dedup_effects(chronicle: &mut Chronicle) -> usize {
+    let initial_len = chronicle.effects.len();
+    let mut i = 0;
+    while i < chronicle.effects.len() {
+        if chronicle.effects[..i].contains(&chronicle.effects[i]) {
+            chronicle.effects.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+    initial_len - chronicle.effects.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chronicles::{ChronicleKind, Effect};
+    use aries_model::lang::{Atom, BAtom, IAtom};
+    use aries_model::Model;
+
+    #[test]
+    fn duplicated_effect() {
+        let mut model = Model::new();
+        let start = IAtom::from(model.new_ivar(0, 10, "start"));
+        let end = IAtom::from(model.new_ivar(0, 10, "end"));
+        let effect = |at: IAtom, value: bool| Effect {
+            transition_start: at,
+            persistence_start: at + 1,
+            state_var: Vec::new(),
+            value: Atom::from(value),
+        };
+        let mut chronicle = Chronicle {
+            kind: ChronicleKind::Action,
+            presence: BAtom::Cst(true),
+            start,
+            end,
+            name: Vec::new(),
+            task: None,
+            conditions: Vec::new(),
+            effects: vec![
+                effect(start, true),
+                effect(end, true),
+                effect(start, true),
+                effect(start, false),
+            ],
+            constraints: Vec::new(),
+            subtasks: Vec::new(),
+            cost: None,
+        };
+
+        assert_eq!(dedup_effects(&mut chronicle), 1);
+        assert_eq!(
+            chronicle.effects,
+            vec![effect(start, true), effect(end, true), effect(start, false)]
+        );
+        assert_eq!(dedup_effects(&mut chronicle), 0);
+    }
+}
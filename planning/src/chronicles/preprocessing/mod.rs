@@ -1,5 +1,7 @@
+mod duplicate_effects;
 mod state_variables;
 mod statics;
+mod unusable_templates;
 mod unused_effects;
 
 use env_param::EnvParam;
@@ -7,13 +9,25 @@ use env_param::EnvParam;
 static PREPRO_STATIC: EnvParam<bool> = EnvParam::new("ARIES_PLANNING_PREPRO_STATIC", "true");
 static PREPRO_STATE_VARS: EnvParam<bool> = EnvParam::new("ARIES_PLANNING_PREPRO_STATE_VARS", "true");
 static PREPRO_UNUSABLE_EFFECTS: EnvParam<bool> = EnvParam::new("ARIES_PLANNING_PREPRO_UNUSABLE_EFFECTS", "true");
+static PREPRO_DUPLICATE_EFFECTS: EnvParam<bool> = EnvParam::new("ARIES_PLANNING_PREPRO_DUPLICATE_EFFECTS", "true");
+static PREPRO_UNUSABLE_TEMPLATES: EnvParam<bool> = EnvParam::new("ARIES_PLANNING_PREPRO_UNUSABLE_TEMPLATES", "true");
 
 use crate::chronicles::Problem;
+use anyhow::{bail, Result};
+pub use duplicate_effects::merge_duplicate_effects;
 pub use state_variables::predicates_as_state_variables;
 pub use statics::statics_as_tables;
+pub use unusable_templates::remove_unusable_templates;
 pub use unused_effects::remove_unusable_effects;
 
-pub fn preprocess(problem: &mut Problem) {
+pub fn preprocess(problem: &mut Problem) -> Result<()> {
+    validate_timing(problem)?;
+    if *PREPRO_DUPLICATE_EFFECTS.get() {
+        merge_duplicate_effects(problem);
+    }
+    if *PREPRO_UNUSABLE_TEMPLATES.get() {
+        remove_unusable_templates(problem);
+    }
     if *PREPRO_UNUSABLE_EFFECTS.get() {
         remove_unusable_effects(problem);
     }
@@ -23,4 +37,22 @@ pub fn preprocess(problem: &mut Problem) {
     if *PREPRO_STATIC.get() {
         statics_as_tables(problem);
     }
+    Ok(())
+}
+
+/// Ensures that the time points of all chronicles and templates are consistently ordered,
+/// to avoid a problem that is unsatisfiable for no apparent reason.
+fn validate_timing(problem: &Problem) -> Result<()> {
+    for (i, ch) in problem.chronicles.iter().enumerate() {
+        if let Err(msg) = ch.chronicle.validate_timing() {
+            bail!("Invalid timing in chronicle instance {}: {}", i, msg);
+        }
+    }
+    for (i, t) in problem.templates.iter().enumerate() {
+        if let Err(msg) = t.chronicle.validate_timing() {
+            let label = t.label.clone().unwrap_or_else(|| i.to_string());
+            bail!("Invalid timing in chronicle template {}: {}", label, msg);
+        }
+    }
+    Ok(())
 }
@@ -0,0 +1,80 @@
+use crate::chronicles::{Condition, Effect, Problem};
+
+/// Removes all chronicle templates that have a condition that cannot be supported by any effect,
+/// in either a chronicle instance (including the initial one) or a template.
+///
+/// An effect is considered to be a possible support for a condition if their state variables and values
+/// are unifiable. Since removing a template also removes its effects, this is repeated until a fixpoint is reached.
+pub fn remove_unusable_templates(pb: &mut Problem) {
+    let mut num_removed = 0;
+    loop {
+        let model = &pb.context.model;
+        let possible_support = |e: &Effect, c: &Condition| -> bool {
+            model.unifiable_seq(&e.state_var, &c.state_var) && model.unifiable(e.value, c.value)
+        };
+        let effects = || {
+            pb.chronicles
+                .iter()
+                .flat_map(|ch| ch.chronicle.effects.iter())
+                .chain(pb.templates.iter().flat_map(|t| t.chronicle.effects.iter()))
+        };
+        let is_supportable = |c: &Condition| effects().any(|e| possible_support(e, c));
+
+        let unusable = pb
+            .templates
+            .iter()
+            .position(|t| !t.chronicle.conditions.iter().all(is_supportable));
+        match unusable {
+            Some(i) => {
+                pb.templates.remove(i);
+                num_removed += 1;
+            }
+            None => break,
+        }
+    }
+
+    if num_removed > 0 {
+        println!("Removed {} unusable chronicle templates", num_removed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsing::pddl::{parse_pddl_domain, parse_pddl_problem};
+    use crate::parsing::pddl_to_chronicles;
+    use aries_utils::input::Input;
+
+    #[test]
+    fn unreachable_precondition() {
+        let domain = "(define (domain birds)
+          (:requirements :strips)
+          (:predicates (has-wings) (on-ground) (flying))
+          (:action fly
+            :parameters ()
+            :precondition (has-wings)
+            :effect (flying))
+          (:action land
+            :parameters ()
+            :precondition (flying)
+            :effect (on-ground))
+          (:action jump
+            :parameters ()
+            :precondition (on-ground)
+            :effect (on-ground)))";
+        let problem = "(define (problem birds-1)
+          (:domain birds)
+          (:objects)
+          (:init (on-ground))
+          (:goal (on-ground)))";
+        let dom = parse_pddl_domain(Input::from_string(domain)).unwrap();
+        let prob = parse_pddl_problem(Input::from_string(problem)).unwrap();
+        let mut spec = pddl_to_chronicles(&dom, &prob).unwrap();
+        assert_eq!(spec.templates.len(), 3);
+
+        remove_unusable_templates(&mut spec);
+        // `fly` requires wings that nobody can give, `land` requires flying that only `fly` gives
+        let remaining: Vec<_> = spec.templates.iter().map(|t| t.label.clone().unwrap()).collect();
+        assert_eq!(remaining, vec!["jump".to_string()]);
+    }
+}
@@ -34,6 +34,11 @@ pub struct ParameterizedPred {
 }
 
 impl ParameterizedPred {
+    /// A predicate over the given symbols or action parameters, e.g. `(at Param(0) kitchen)`.
+    pub fn new(positive: bool, sexpr: Vec<Holed<SymId>>) -> Self {
+        ParameterizedPred { positive, sexpr }
+    }
+
     pub fn bind(&self, sd: &World, params: &[SymId], working: &mut Vec<SymId>) -> Option<Lit> {
         working.clear();
         for &x in &self.sexpr {
@@ -60,6 +65,67 @@ pub struct ActionSchema {
     pub eff: Vec<ParameterizedPred>,
 }
 
+impl ActionSchema {
+    /// Starts the construction of an action schema named `name`, for building classical
+    /// problems without going through PDDL.
+    ///
+    /// Parameters are numbered in their order of declaration and are referred to
+    /// in preconditions and effects as `Holed::Param(i)`.
+    pub fn builder(name: SymId) -> ActionSchemaBuilder {
+        ActionSchemaBuilder {
+            name,
+            params: Vec::new(),
+            pre: Vec::new(),
+            eff: Vec::new(),
+        }
+    }
+}
+
+/// Incremental construction of an [`ActionSchema`], obtained with [`ActionSchema::builder`].
+pub struct ActionSchemaBuilder {
+    name: SymId,
+    params: Vec<(TypeId, Option<String>)>,
+    pre: Vec<ParameterizedPred>,
+    eff: Vec<ParameterizedPred>,
+}
+
+impl ActionSchemaBuilder {
+    /// Declares a new parameter of type `tpe`, referred to as `Holed::Param(i)` where `i` is its position.
+    pub fn param(mut self, name: impl Into<String>, tpe: TypeId) -> Self {
+        self.params.push((tpe, Some(name.into())));
+        self
+    }
+
+    /// Adds a precondition that must hold for the action to be applicable.
+    pub fn precondition(mut self, pred: ParameterizedPred) -> Self {
+        self.pre.push(pred);
+        self
+    }
+
+    /// Adds an effect applied to the state when the action is executed.
+    pub fn effect(mut self, pred: ParameterizedPred) -> Self {
+        self.eff.push(pred);
+        self
+    }
+
+    /// Builds the action schema, failing if a predicate refers to an undeclared parameter.
+    pub fn build(self) -> Result<ActionSchema> {
+        for pred in self.pre.iter().chain(self.eff.iter()) {
+            for x in &pred.sexpr {
+                if let Holed::Param(i) = x {
+                    ensure!(*i < self.params.len(), "Reference to undeclared parameter {}", i);
+                }
+            }
+        }
+        Ok(ActionSchema {
+            name: self.name,
+            params: self.params,
+            pre: self.pre,
+            eff: self.eff,
+        })
+    }
+}
+
 pub struct LiftedProblem {
     pub world: World,
     pub initial_state: State,
@@ -269,3 +335,74 @@ fn ground_action_schema(schema: &ActionSchema, desc: &World) -> Vec<Operator> {
 
     res
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsing::pddl::{parse_pddl_domain, parse_pddl_problem};
+    use crate::parsing::pddl_to_chronicles;
+    use aries_utils::input::Input;
+
+    const DOMAIN: &str = "(define (domain rooms)
+      (:requirements :strips :typing)
+      (:types room)
+      (:predicates (at ?l - room) (connected ?a ?b - room))
+      (:action move
+        :parameters (?from ?to - room)
+        :precondition (and (at ?from) (connected ?from ?to))
+        :effect (and (not (at ?from)) (at ?to))))";
+
+    const PROBLEM: &str = "(define (problem rooms-1)
+      (:domain rooms)
+      (:objects kitchen bedroom office - room)
+      (:init (at kitchen) (connected kitchen bedroom) (connected bedroom office))
+      (:goal (at office)))";
+
+    #[test]
+    fn action_schema_builder() {
+        let dom = parse_pddl_domain(Input::from_string(DOMAIN)).unwrap();
+        let prob = parse_pddl_problem(Input::from_string(PROBLEM)).unwrap();
+        let spec = pddl_to_chronicles(&dom, &prob).unwrap();
+        let mut lifted = from_chronicles(&spec).unwrap();
+        let parsed = grounded_problem(&lifted).unwrap();
+
+        let table = &lifted.world.table;
+        let room = table.types.id_of("room").unwrap();
+        let sym = |name: &str| Holed::Full(table.id(name).unwrap());
+        let from = Holed::Param(0);
+        let to = Holed::Param(1);
+        let schema = ActionSchema::builder(table.id("move").unwrap())
+            .param("from", room)
+            .param("to", room)
+            .precondition(ParameterizedPred::new(true, vec![sym("at"), from]))
+            .precondition(ParameterizedPred::new(true, vec![sym("connected"), from, to]))
+            .effect(ParameterizedPred::new(false, vec![sym("at"), from]))
+            .effect(ParameterizedPred::new(true, vec![sym("at"), to]))
+            .build()
+            .unwrap();
+        assert_eq!(schema.params.len(), 2);
+        lifted.actions = vec![schema];
+        let built = grounded_problem(&lifted).unwrap();
+
+        let ops = |pb: &GroundProblem| {
+            pb.operators
+                .iter()
+                .map(|op| {
+                    (
+                        pb.operators.name(op).to_vec(),
+                        pb.operators.preconditions(op).to_vec(),
+                        pb.operators.effects(op).to_vec(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ops(&built).len(), 9);
+        assert_eq!(ops(&built), ops(&parsed));
+
+        let invalid = ActionSchema::builder(table.id("move").unwrap())
+            .param("from", room)
+            .effect(ParameterizedPred::new(true, vec![sym("at"), to]))
+            .build();
+        assert!(invalid.is_err());
+    }
+}
@@ -167,7 +167,7 @@ impl World {
 
     /// Returns the state variable associated with the given ID
     pub fn sv_of(&self, sv: SVId) -> &[SymId] {
-        self.expressions.get(sv)
+        &self.expressions[sv]
     }
 
     pub fn make_new_state(&self) -> State {
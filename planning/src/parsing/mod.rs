@@ -2,20 +2,18 @@ pub mod pddl;
 pub mod sexpr;
 
 use crate::chronicles::*;
-use crate::classical::state::{SVId, World};
-use crate::parsing::pddl::{PddlFeature, TypedSymbol};
+use crate::parsing::pddl::{PddlFeature, TypeExpr, TypedSymbol};
 
 use crate::chronicles::constraints::Constraint;
 use crate::parsing::sexpr::SExpr;
 use anyhow::*;
 use aries_model::lang::*;
-use aries_model::symbols::SymbolTable;
+use aries_model::symbols::{SymId, SymbolTable};
 use aries_model::types::TypeHierarchy;
-use aries_utils::input::{ErrLoc, Loc, Sym};
+use aries_utils::input::{Loc, Sym};
 use itertools::Itertools;
 use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
-use std::ops::Deref;
 use std::sync::Arc;
 
 /// Names for built in types. They contain UTF-8 symbols for sexiness (and to avoid collision with user defined symbols)
@@ -28,7 +26,39 @@ static OBJECT_TYPE: &str = "★object★";
 
 type Pb = Problem;
 
+/// Splits a problem whose goal is a top-level disjunction `(or g1 g2 ...)` into one problem per disjunct,
+/// in the order in which they appear.
+/// Since chronicles only support conjunctive goals, each alternative must be converted and solved separately.
+/// A problem with any other goal is returned unchanged as the single alternative.
+pub fn goal_alternatives(prob: &pddl::Problem) -> Vec<pddl::Problem> {
+    if let [goal] = prob.goal.as_slice() {
+        if let Some(disjuncts) = goal.as_application("or") {
+            return disjuncts
+                .iter()
+                .map(|disjunct| pddl::Problem {
+                    goal: vec![disjunct.clone()],
+                    ..prob.clone()
+                })
+                .collect();
+        }
+    }
+    vec![prob.clone()]
+}
+
 pub fn pddl_to_chronicles(dom: &pddl::Domain, prob: &pddl::Problem) -> Result<Pb> {
+    // reject the requirements whose semantics would not be captured by the encoding
+    let unsupported: Vec<&str> = dom
+        .features
+        .iter()
+        .filter(|f| !f.is_supported())
+        .map(|f| f.flag())
+        .collect();
+    ensure!(
+        unsupported.is_empty(),
+        "Unsupported PDDL requirements: {}",
+        unsupported.join(" ")
+    );
+
     // top types in pddl
     let mut types: Vec<(Sym, Option<Sym>)> = vec![
         (TASK_TYPE.into(), None),
@@ -38,16 +68,30 @@ pub fn pddl_to_chronicles(dom: &pddl::Domain, prob: &pddl::Problem) -> Result<Pb
         (PREDICATE_TYPE.into(), None),
         (OBJECT_TYPE.into(), None),
     ];
-    let top_type = OBJECT_TYPE.into();
+    let top_type: Sym = OBJECT_TYPE.into();
+
+    // unions of types are only supported for parameters, each symbol having a single type
+    for ts in dom.types.iter().chain(&dom.constants).chain(&prob.objects) {
+        if let Some(TypeExpr::Either(_)) = ts.tpe {
+            return Err(ts
+                .symbol
+                .invalid("`either` types are only supported for parameters")
+                .into());
+        }
+    }
+    let user_types: Vec<(Sym, Option<Sym>)> = dom
+        .types
+        .iter()
+        .map(|t| (t.symbol.clone(), t.tpe.as_ref().map(TypeExpr::name)))
+        .collect();
 
     // determine the top types in the user-defined hierarchy.
     // this is typically "object" by convention but might something else (e.g. "obj" in some hddl problems).
     {
-        let all_types: HashSet<&Sym> = dom.types.iter().map(|tpe| &tpe.symbol).collect();
-        let top_types = dom
-            .types
+        let all_types: HashSet<&Sym> = user_types.iter().map(|(tpe, _)| tpe).collect();
+        let top_types = user_types
             .iter()
-            .filter_map(|tpe| tpe.tpe.as_ref())
+            .filter_map(|(_, parent)| parent.as_ref())
             .filter(|tpe| !all_types.contains(tpe))
             .unique();
         for t in top_types {
@@ -55,8 +99,51 @@ pub fn pddl_to_chronicles(dom: &pddl::Domain, prob: &pddl::Problem) -> Result<Pb
         }
     }
 
-    for t in &dom.types {
-        types.push((t.symbol.clone(), t.tpe.clone()));
+    types.extend(user_types.iter().cloned());
+
+    // Each union of types `(either a b)` appearing in a parameter is inserted in the hierarchy as a synthetic type,
+    // that becomes the parent of its members. This requires all members to share the same parent and
+    // to not be part of any other union.
+    let unions = dom
+        .predicates
+        .iter()
+        .flat_map(|p| &p.args)
+        .chain(dom.tasks.iter().flat_map(|t| &t.args))
+        .chain(dom.methods.iter().flat_map(|m| &m.parameters))
+        .chain(dom.actions.iter().flat_map(|a| &a.args))
+        .chain(dom.functions.iter().flat_map(|f| &f.args))
+        .filter_map(|ts| match &ts.tpe {
+            Some(union @ TypeExpr::Either(members)) => Some((union.name(), members)),
+            _ => None,
+        })
+        .unique_by(|(name, _)| name.clone());
+    for (union, members) in unions {
+        let mut parent = None;
+        for member in members {
+            let member_parent = &types
+                .iter()
+                .find(|(tpe, _)| tpe == member)
+                .ok_or_else(|| member.invalid("Unknown type"))?
+                .1;
+            match &parent {
+                None => parent = Some(member_parent.clone()),
+                Some(p) if p == member_parent => {}
+                Some(_) => {
+                    return Err(member
+                        .invalid(format!(
+                            "Unsupported `{}`: its members must share a parent and appear in no other union",
+                            union
+                        ))
+                        .into())
+                }
+            }
+        }
+        for (tpe, tpe_parent) in types.iter_mut() {
+            if members.contains(tpe) {
+                *tpe_parent = Some(union.clone());
+            }
+        }
+        types.push((union, parent.unwrap()));
     }
 
     let ts = TypeHierarchy::new(types)?;
@@ -68,6 +155,11 @@ pub fn pddl_to_chronicles(dom: &pddl::Domain, prob: &pddl::Problem) -> Result<Pb
     for p in &dom.predicates {
         symbols.push(TypedSymbol::new(&p.name, PREDICATE_TYPE));
     }
+    // object fluents are state functions as well, numeric functions are not part of the state
+    let object_fluents: Vec<&pddl::Function> = dom.functions.iter().filter(|f| !f.is_numeric()).collect();
+    for f in &object_fluents {
+        symbols.push(TypedSymbol::new(&f.name, PREDICATE_TYPE));
+    }
     for a in &dom.actions {
         symbols.push(TypedSymbol::new(&a.name, ACTION_TYPE));
     }
@@ -79,30 +171,52 @@ pub fn pddl_to_chronicles(dom: &pddl::Domain, prob: &pddl::Problem) -> Result<Pb
     }
     let symbols = symbols
         .drain(..)
-        .map(|ts| (ts.symbol, ts.tpe.unwrap_or_else(|| OBJECT_TYPE.into())))
+        .map(|ts| {
+            (
+                ts.symbol,
+                ts.tpe.map(|tpe| tpe.name()).unwrap_or_else(|| OBJECT_TYPE.into()),
+            )
+        })
         .collect();
     let symbol_table = SymbolTable::new(ts, symbols)?;
 
-    let mut state_variables = Vec::with_capacity(dom.predicates.len());
-    for pred in &dom.predicates {
-        let sym = symbol_table
-            .id(&pred.name)
-            .ok_or_else(|| pred.name.invalid("Unknown symbol"))?;
-        let mut args = Vec::with_capacity(pred.args.len() + 1);
-        for a in &pred.args {
-            let tpe = a.tpe.as_ref().unwrap_or(&top_type);
-            let tpe = symbol_table
-                .types
-                .id_of(tpe)
-                .ok_or_else(|| tpe.invalid("Unknown type"))?;
-            args.push(Type::Sym(tpe));
+    let type_id = |tpe: &Sym| -> Result<Type> {
+        let tpe = symbol_table
+            .types
+            .id_of(tpe)
+            .ok_or_else(|| tpe.invalid("Unknown type"))?;
+        Ok(Type::Sym(tpe))
+    };
+    let mut state_variables = Vec::with_capacity(dom.predicates.len() + object_fluents.len());
+    let state_functions = dom
+        .predicates
+        .iter()
+        .map(|pred| (&pred.name, &pred.args, None))
+        .chain(object_fluents.iter().map(|f| (&f.name, &f.args, Some(&f.tpe))));
+    for (name, params, value_type) in state_functions {
+        let sym = symbol_table.id(name).ok_or_else(|| name.invalid("Unknown symbol"))?;
+        let mut args = Vec::with_capacity(params.len() + 1);
+        for a in params {
+            let tpe = a.tpe.as_ref().map(TypeExpr::name).unwrap_or_else(|| top_type.clone());
+            args.push(type_id(&tpe)?);
+        }
+        // return type (last one) is a boolean for predicates, and the type of the value for object fluents
+        match value_type {
+            Some(tpe) => args.push(type_id(tpe)?),
+            None => args.push(Type::Bool),
         }
-        args.push(Type::Bool); // return type (last one) is a boolean
         state_variables.push(StateFun { sym, tpe: args })
     }
 
     let mut context = Ctx::new(Arc::new(symbol_table), state_variables);
 
+    // name of the function whose increases are the costs of actions, e.g., `total-cost`
+    let cost_function = match &prob.metric {
+        None => None,
+        Some(pddl::Metric::Minimize(e)) => Some(read_cost_function(e, dom)?),
+        Some(pddl::Metric::Maximize(e)) => return Err(e.invalid("Unsupported metric, expected a minimization").into()),
+    };
+
     // Initial chronicle construction
     let mut init_ch = Chronicle {
         kind: ChronicleKind::Problem,
@@ -115,18 +229,13 @@ pub fn pddl_to_chronicles(dom: &pddl::Domain, prob: &pddl::Problem) -> Result<Pb
         effects: vec![],
         constraints: vec![],
         subtasks: vec![],
+        cost: None,
     };
 
     // Transforms atoms of an s-expression into the corresponding representation for chronicles
-    let as_model_atom_no_borrow = |atom: &sexpr::SAtom, context: &Ctx| -> Result<SAtom> {
-        let atom = context
-            .model
-            .symbols
-            .id(atom.as_str())
-            .ok_or_else(|| atom.invalid("Unknown atom"))?;
-        let atom = context.typed_sym(atom);
-        Ok(atom.into())
-    };
+    // there are no parameters in the scope of the problem, all atoms refer to objects and constants
+    let as_model_atom_no_borrow =
+        |atom: &sexpr::SAtom, context: &Ctx| -> Result<SAtom> { resolve_atom(atom, &[], &[], context) };
     let as_model_atom = |atom: &sexpr::SAtom| as_model_atom_no_borrow(atom, &context);
     for goal in &prob.goal {
         let goals = read_conjunction(goal, as_model_atom)?;
@@ -145,7 +254,14 @@ pub fn pddl_to_chronicles(dom: &pddl::Domain, prob: &pddl::Problem) -> Result<Pb
     // if we have negative preconditions, we need to assume a closed world assumption.
     // indeed, some preconditions might rely on initial facts being false
     let closed_world = dom.features.contains(&PddlFeature::NegativePreconditions);
-    for (sv, val) in read_init(&prob.init, closed_world, as_model_atom, &context)? {
+    // initial values of numeric functions (e.g. `(= (total-cost) 0)`) are not part of the state
+    let init: Vec<SExpr> = prob
+        .init
+        .iter()
+        .filter(|fact| !is_numeric_assignment(fact, dom))
+        .cloned()
+        .collect();
+    for (sv, val) in read_init(&init, closed_world, as_model_atom, &context)? {
         init_ch.effects.push(Effect {
             transition_start: init_ch.start,
             persistence_start: init_ch.start,
@@ -172,11 +288,11 @@ pub fn pddl_to_chronicles(dom: &pddl::Domain, prob: &pddl::Problem) -> Result<Pb
 
     let mut templates = Vec::new();
     for a in &dom.actions {
-        let template = read_chronicle_template(a, &mut context)?;
+        let template = read_chronicle_template(a, cost_function.as_deref(), &mut context)?;
         templates.push(template);
     }
     for m in &dom.methods {
-        let template = read_chronicle_template(m, &mut context)?;
+        let template = read_chronicle_template(m, cost_function.as_deref(), &mut context)?;
         templates.push(template);
     }
 
@@ -189,8 +305,37 @@ pub fn pddl_to_chronicles(dom: &pddl::Domain, prob: &pddl::Problem) -> Result<Pb
     Ok(problem)
 }
 
+/// Reads the expression minimized by a metric, which must be a function without parameters, e.g., `(total-cost)`.
+/// Returns the name of the function.
+fn read_cost_function(e: &SExpr, dom: &pddl::Domain) -> Result<String> {
+    let mut l = e
+        .as_list_iter()
+        .ok_or_else(|| e.invalid("Expected a function application"))?;
+    let name = l.pop_atom()?.clone();
+    if !l.is_empty() || !dom.functions.iter().any(|f| f.name == name && f.args.is_empty()) {
+        return Err(e
+            .invalid("Unsupported metric, expected a function without parameters")
+            .into());
+    }
+    Ok(name.to_string())
+}
+
+/// Returns true if the expression sets the value of a numeric function, e.g., `(= (total-cost) 0)`.
+fn is_numeric_assignment(e: &SExpr, dom: &pddl::Domain) -> bool {
+    match e.as_application("=") {
+        Some([SExpr::List(fun), _]) => match fun.iter().next().and_then(|name| name.as_atom()) {
+            Some(name) => !dom
+                .functions
+                .iter()
+                .any(|f| f.name.as_str() == name.as_str() && !f.is_numeric()),
+            None => true,
+        },
+        _ => false,
+    }
+}
+
 /// Transforms PDDL initial facts into binding of state variables to their values
-/// If `closed_world` is true, then all predicates that are not given a true value will be set to false.
+/// If `closed_world` is true, then all ground predicates that are not given a value will be set to false.
 fn read_init(
     initial_facts: &[SExpr],
     closed_world: bool,
@@ -198,48 +343,43 @@ fn read_init(
     context: &Ctx,
 ) -> Result<Vec<(SV, Atom)>> {
     let mut facts = Vec::new();
-    if closed_world {
-        // closed world, every predicate that is not given a true value should be given a false value
-        // to do this, we rely on the classical classical planning state
-        let state_desc = World::new(context.model.symbols.deref().clone(), &context.state_functions)?;
-        let mut s = state_desc.make_new_state();
-        for init in initial_facts {
-            let pred = read_sv(init, &state_desc)?;
-            s.add(pred);
-        }
-
-        let sv_to_sv = |sv| -> Vec<SAtom> {
-            state_desc
-                .sv_of(sv)
-                .iter()
-                .map(|&sym| context.typed_sym(sym).into())
-                .collect()
-        };
-
-        for literal in s.literals() {
-            let sv = sv_to_sv(literal.var());
-            let val: Atom = literal.val().into();
-            facts.push((sv, val));
+    for e in initial_facts {
+        match read_term(e, &as_model_atom)? {
+            TermLoc(Term::Binding(sv, val), loc) => {
+                check_state_variable(&sv, context).map_err(|e| loc.invalid(e))?;
+                facts.push((sv, val))
+            }
+            TermLoc(_, loc) => return Err(loc.invalid("Unsupported in initial facts").into()),
         }
-    } else {
-        // open world, we only add to the initial facts the one explicitly given in the problem definition
-        for e in initial_facts {
-            match read_term(e, &as_model_atom)? {
-                TermLoc(Term::Binding(sv, val), _) => facts.push((sv, val)),
-                TermLoc(_, loc) => return Err(loc.invalid("Unsupported in initial facts").into()),
+    }
+    if closed_world {
+        // closed world, every ground predicate that is not given a value should be given a false value
+        let predicates: HashSet<SymId> = context
+            .state_functions
+            .iter()
+            .filter(|f| f.return_type() == Type::Bool)
+            .map(|f| f.sym)
+            .collect();
+        let given: HashSet<SV> = facts.iter().map(|(sv, _)| sv.clone()).collect();
+        for sv in context.ground_state_variables() {
+            let is_predicate = matches!(sv[0], SAtom::Cst(head) if predicates.contains(&head.sym));
+            if is_predicate && !given.contains(&sv) {
+                facts.push((sv, false.into()));
             }
         }
     }
     Ok(facts)
 }
 
-/// Transforms a PDDL action into a Chronicle template
+/// Transforms a PDDL action into a Chronicle template.
+/// Increases of the `cost_function` in the effects of the action are summed into the cost of the chronicle.
 fn read_chronicle_template(
     // pddl_action: &pddl::Action,
     pddl: impl ChronicleTemplateView,
+    cost_function: Option<&str>,
     context: &mut Ctx,
 ) -> Result<ChronicleTemplate> {
-    let top_type = OBJECT_TYPE.into();
+    let top_type: Sym = OBJECT_TYPE.into();
     let mut params: Vec<Variable> = Vec::new();
     let prez = context.model.new_bvar("present");
     params.push(prez.into());
@@ -272,36 +412,25 @@ fn read_chronicle_template(
 
     // Process, the arguments of the action, adding them to the parameters of the chronicle and to the name of the action
     for arg in pddl.parameters() {
-        let tpe = arg.tpe.as_ref().unwrap_or(&top_type);
-        let tpe = context
-            .model
-            .symbols
-            .types
-            .id_of(tpe)
-            .ok_or_else(|| tpe.invalid("Unknown atom"))?;
+        let tpe = arg.tpe.as_ref().map(TypeExpr::name).unwrap_or_else(|| top_type.clone());
+        let tpe = context.model.symbols.types.id_of(&tpe).ok_or_else(|| {
+            tpe.invalid(format!(
+                "Unknown type `{}` for parameter `{}` of `{}`",
+                tpe,
+                arg.symbol,
+                pddl.base_name()
+            ))
+        })?;
         let arg = context.model.new_optional_sym_var(tpe, prez, &arg.symbol);
         params.push(arg.into());
         name.push(arg.into());
     }
 
     // Transforms atoms of an s-expression into the corresponding representation for chronicles
+    // Parameters of the action or method shadow the constants with the same name in all its components
+    // (task, preconditions, effects and subtasks).
     let as_chronicle_atom_no_borrow = |atom: &sexpr::SAtom, context: &Ctx| -> Result<SAtom> {
-        match pddl
-            .parameters()
-            .iter()
-            .position(|arg| arg.symbol.as_str() == atom.as_str())
-        {
-            Some(i) => Ok(name[i as usize + 1]),
-            None => {
-                let atom = context
-                    .model
-                    .symbols
-                    .id(atom.as_str())
-                    .ok_or_else(|| atom.invalid("Unknown atom"))?;
-                let atom = context.typed_sym(atom);
-                Ok(atom.into())
-            }
-        }
+        resolve_atom(atom, pddl.parameters(), &name[1..], context)
     };
     let as_chronicle_atom = |atom: &sexpr::SAtom| -> Result<SAtom> { as_chronicle_atom_no_borrow(atom, context) };
 
@@ -328,6 +457,7 @@ fn read_chronicle_template(
         effects: vec![],
         constraints: vec![],
         subtasks: vec![],
+        cost: None,
     };
 
     for eff in pddl.effects() {
@@ -336,6 +466,9 @@ fn read_chronicle_template(
         }
         let effects = read_conjunction(eff, &as_chronicle_atom)?;
         for TermLoc(term, loc) in effects {
+            if let Term::Binding(sv, _) = &term {
+                check_state_variable(sv, context).map_err(|e| loc.clone().invalid(e))?;
+            }
             match term {
                 Term::Binding(sv, val) => ch.effects.push(Effect {
                     transition_start: ch.start,
@@ -343,6 +476,9 @@ fn read_chronicle_template(
                     state_var: sv,
                     value: val,
                 }),
+                Term::Increase(fun, amount) if cost_function == Some(fun.as_str()) => {
+                    ch.cost = Some(ch.cost.unwrap_or(0) + amount)
+                }
                 _ => return Err(loc.invalid("Unsupported in action effects").into()),
             }
         }
@@ -364,9 +500,10 @@ fn read_chronicle_template(
 
     for cond in pddl.preconditions() {
         let effects = read_conjunction(cond, &as_chronicle_atom)?;
-        for TermLoc(term, _) in effects {
+        for TermLoc(term, loc) in effects {
             match term {
                 Term::Binding(sv, val) => {
+                    check_state_variable(&sv, context).map_err(|e| loc.clone().invalid(e))?;
                     let as_effect_on_same_state_variable = ch
                         .effects
                         .iter()
@@ -390,6 +527,7 @@ fn read_chronicle_template(
                 }
                 Term::Eq(a, b) => ch.constraints.push(Constraint::eq(a, b)),
                 Term::Neq(a, b) => ch.constraints.push(Constraint::neq(a, b)),
+                Term::Increase(..) => return Err(loc.invalid("Unsupported in preconditions").into()),
             }
         }
     }
@@ -406,6 +544,66 @@ fn read_chronicle_template(
     Ok(template)
 }
 
+/// Returns the representation of a symbol appearing in the scope of the given `parameters`, whose values in the
+/// chronicle are given by `arguments`.
+///
+/// A parameter shadows any object or constant with the same name: within the scope of an action or method, the symbol
+/// always refers to the parameter. Other symbols are resolved to the object or constant they name.
+fn resolve_atom(atom: &sexpr::SAtom, parameters: &[TypedSymbol], arguments: &[SAtom], context: &Ctx) -> Result<SAtom> {
+    debug_assert_eq!(parameters.len(), arguments.len());
+    match parameters.iter().position(|arg| arg.symbol.as_str() == atom.as_str()) {
+        Some(i) => Ok(arguments[i]),
+        None => {
+            let atom = context
+                .model
+                .symbols
+                .id(atom.as_str())
+                .ok_or_else(|| atom.invalid("Unknown atom"))?;
+            Ok(context.typed_sym(atom).into())
+        }
+    }
+}
+
+/// Checks that the state variable is the application of a known predicate to arguments of compatible types.
+/// An argument is compatible with a parameter of the predicate if one of their types is a subtype of the other.
+fn check_state_variable(sv: &[SAtom], context: &Ctx) -> std::result::Result<(), String> {
+    let (head, args) = match sv.split_first() {
+        Some((SAtom::Cst(head), args)) => (head.sym, args),
+        _ => return Ok(()), // not a constant predicate, nothing to check
+    };
+    let symbols = &context.model.symbols;
+    let name = symbols.symbol(head);
+    let fun = context
+        .state_functions
+        .iter()
+        .find(|f| f.sym == head)
+        .ok_or_else(|| format!("Unknown predicate `{}`", name))?;
+    let params = fun.argument_types();
+    if params.len() != args.len() {
+        return Err(format!(
+            "Predicate `{}` expects {} arguments but got {}",
+            name,
+            params.len(),
+            args.len()
+        ));
+    }
+    for (arg, param) in args.iter().zip(params) {
+        if let Type::Sym(expected) = *param {
+            let actual = arg.tpe();
+            if !symbols.types.is_subtype(expected, actual) && !symbols.types.is_subtype(actual, expected) {
+                return Err(format!(
+                    "Argument `{}` of predicate `{}` has type `{}`, incompatible with the expected type `{}`",
+                    context.model.fmt(*arg),
+                    name,
+                    symbols.types.from_id(actual),
+                    symbols.types.from_id(expected)
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
 /// An adapter to allow treating pddl actions and hddl methods identically
 trait ChronicleTemplateView {
     fn kind(&self) -> ChronicleKind;
@@ -538,6 +736,8 @@ enum Term {
     Binding(SV, Atom),
     Eq(Atom, Atom),
     Neq(Atom, Atom),
+    /// Increase of a numeric function without parameters by a constant amount
+    Increase(sexpr::SAtom, IntCst),
 }
 struct TermLoc(Term, Loc);
 
@@ -557,6 +757,10 @@ fn read_conjunction_impl(e: &SExpr, t: &impl Fn(&sexpr::SAtom) -> Result<SAtom>,
         for c in conjuncts.iter() {
             read_conjunction_impl(c, t, out)?;
         }
+    } else if e.as_application("or").is_some() {
+        return Err(e
+            .invalid("Unsupported disjunction (only a top-level disjunction of goals is supported)")
+            .into());
     } else if let Some([to_negate]) = e.as_application("not") {
         let TermLoc(t, _) = read_term(to_negate, &t)?;
         let negated = match t {
@@ -569,6 +773,7 @@ fn read_conjunction_impl(e: &SExpr, t: &impl Fn(&sexpr::SAtom) -> Result<SAtom>,
             }
             Term::Eq(a, b) => Term::Neq(a, b),
             Term::Neq(a, b) => Term::Eq(a, b),
+            Term::Increase(..) => return Err(to_negate.invalid("Could not apply 'not' to this expression").into()),
         };
         out.push(TermLoc(negated, e.loc()));
     } else {
@@ -585,21 +790,40 @@ fn read_term(expr: &SExpr, t: impl Fn(&sexpr::SAtom) -> Result<SAtom>) -> Result
         let term = match head.as_str() {
             "=" => {
                 l.pop_known_atom("=")?;
-                let a = l.pop_atom()?.clone();
+                let a = l.pop()?;
                 let b = l.pop_atom()?.clone();
                 if let Some(unexpected) = l.next() {
                     return Err(unexpected.invalid("Unexpected expr").into());
                 }
-                Term::Eq(t(&a)?.into(), t(&b)?.into())
+                match a.as_list() {
+                    // value of an object fluent, e.g., `(= (loc ?truck) ?city)`
+                    Some(sv) => Term::Binding(read_state_variable(sv, &t)?, t(&b)?.into()),
+                    None => Term::Eq(t(a.as_atom().unwrap())?.into(), t(&b)?.into()),
+                }
             }
-            _ => {
-                let mut sv = Vec::with_capacity(l.len());
-                for e in l {
-                    let atom = e.as_atom().ok_or_else(|| e.invalid("Expected an atom"))?;
-                    let atom = t(atom)?;
-                    sv.push(atom);
+            "increase" => {
+                l.pop_known_atom("increase")?;
+                let mut fun = l.pop_list()?.iter();
+                let name = fun.pop_atom()?.clone();
+                if let Some(arg) = fun.next() {
+                    return Err(arg.invalid("Only functions without parameters are supported").into());
                 }
-                Term::Binding(sv, true.into())
+                let amount = l.pop_atom()?;
+                let amount: IntCst = amount
+                    .as_str()
+                    .parse()
+                    .map_err(|_| amount.invalid("Expected an integer"))?;
+                if amount < 0 {
+                    return Err(expr.invalid("Negative increases are not supported").into());
+                }
+                if let Some(unexpected) = l.next() {
+                    return Err(unexpected.invalid("Unexpected expr").into());
+                }
+                Term::Increase(name, amount)
+            }
+            _ => {
+                let sv = expr.as_list().unwrap();
+                Term::Binding(read_state_variable(sv, &t)?, true.into())
             }
         };
         Ok(TermLoc(term, expr.loc()))
@@ -608,21 +832,352 @@ fn read_term(expr: &SExpr, t: impl Fn(&sexpr::SAtom) -> Result<SAtom>) -> Result
     }
 }
 
-fn read_sv(e: &SExpr, desc: &World) -> Result<SVId> {
-    let p = e.as_list().context("Expected s-expression")?;
-    let atoms: Result<Vec<_>, ErrLoc> = p
-        .iter()
-        .map(|e| e.as_atom().ok_or_else(|| e.invalid("Expected atom")))
-        .collect();
-    let atom_ids: Result<Vec<_>, ErrLoc> = atoms?
-        .iter()
-        .map(|atom| desc.table.id(atom.as_str()).ok_or_else(|| atom.invalid("Unknown atom")))
-        .collect();
-    let atom_ids = atom_ids?;
-    desc.sv_id(atom_ids.as_slice()).with_context(|| {
-        format!(
-            "Unknown predicate {} (wrong number of arguments or badly typed args ?)",
-            desc.table.format(&atom_ids)
-        )
-    })
+/// Reads the application of a predicate or function to its arguments, e.g., `(at ?r ?l)`.
+/// The first element of the resulting state variable is the predicate itself.
+fn read_state_variable(sv: &sexpr::SList, t: &impl Fn(&sexpr::SAtom) -> Result<SAtom>) -> Result<SV> {
+    let mut l = sv.iter();
+    let predicate = l.pop_atom()?.clone();
+    let mut result = Vec::with_capacity(1 + l.len());
+    result.push(t(&predicate)?);
+    for e in l {
+        let atom = e
+            .as_atom()
+            .ok_or_else(|| e.invalid(format!("Expected an atom as argument of `{}`", predicate)))?;
+        result.push(t(atom)?);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsing::pddl::{parse_pddl_domain, parse_pddl_problem};
+    use aries_utils::input::Input;
+
+    const PROBLEM: &str = "(define (problem rooms-1)
+      (:domain rooms)
+      (:objects kitchen - room bob - robot)
+      (:init (at bob kitchen))
+      (:goal (at bob kitchen)))";
+
+    /// Returns the error message of the conversion to chronicles of a domain with a `move` action with the given
+    /// parameters and effect.
+    fn conversion_error(parameters: &str, effect: &str) -> String {
+        let domain = format!(
+            "(define (domain rooms)
+              (:requirements :strips :typing)
+              (:types room robot)
+              (:predicates (at ?r - robot ?l - room))
+              (:action move
+                :parameters {}
+                :precondition ()
+                :effect {}))",
+            parameters, effect
+        );
+        let dom = parse_pddl_domain(Input::from_string(domain)).unwrap();
+        let prob = parse_pddl_problem(Input::from_string(PROBLEM)).unwrap();
+        match pddl_to_chronicles(&dom, &prob) {
+            Ok(_) => panic!("Expected a conversion error"),
+            Err(e) => format!("{:?}", e),
+        }
+    }
+
+    #[test]
+    fn unsupported_requirement() {
+        let domain = "(define (domain rooms)
+          (:requirements :strips :typing :durative-actions)
+          (:types room robot)
+          (:predicates (at ?r - robot ?l - room)))";
+        let dom = parse_pddl_domain(Input::from_string(domain)).unwrap();
+        let prob = parse_pddl_problem(Input::from_string(PROBLEM)).unwrap();
+        match pddl_to_chronicles(&dom, &prob) {
+            Ok(_) => panic!("Expected a conversion error"),
+            Err(e) => assert_eq!(e.to_string(), "Unsupported PDDL requirements: :durative-actions"),
+        }
+    }
+
+    #[test]
+    fn nested_argument() {
+        let err = conversion_error("(?r - robot ?to - room)", "(at (foo) ?to)");
+        assert!(err.contains("Expected an atom as argument of `at`"), "{}", err);
+
+        // same in the initial state, read with a closed world assumption
+        let domain = "(define (domain rooms)
+          (:requirements :strips :typing :negative-preconditions)
+          (:types room robot)
+          (:predicates (at ?r - robot ?l - room)))";
+        let problem = "(define (problem rooms-1)
+          (:domain rooms)
+          (:objects kitchen - room bob - robot)
+          (:init (at (bob) kitchen))
+          (:goal (at bob kitchen)))";
+        let dom = parse_pddl_domain(Input::from_string(domain)).unwrap();
+        let prob = parse_pddl_problem(Input::from_string(problem)).unwrap();
+        match pddl_to_chronicles(&dom, &prob) {
+            Ok(_) => panic!("Expected a conversion error"),
+            Err(e) => {
+                let err = format!("{:?}", e);
+                assert!(err.contains("Expected an atom as argument of `at`"), "{}", err);
+            }
+        }
+    }
+
+    #[test]
+    fn disjunctive_goal() {
+        let domain = "(define (domain rooms)
+          (:requirements :strips :typing :disjunctive-preconditions)
+          (:types room robot)
+          (:predicates (at ?r - robot ?l - room)))";
+        let problem = "(define (problem rooms-1)
+          (:domain rooms)
+          (:objects kitchen bedroom - room bob - robot)
+          (:init (at bob kitchen))
+          (:goal (or (at bob bedroom) (and (at bob kitchen) (not (at bob bedroom))))))";
+        let dom = parse_pddl_domain(Input::from_string(domain)).unwrap();
+        let prob = parse_pddl_problem(Input::from_string(problem)).unwrap();
+
+        // the disjunction cannot be represented in a single chronicle
+        let err = format!("{:?}", pddl_to_chronicles(&dom, &prob).err().unwrap());
+        assert!(err.contains("Unsupported disjunction"), "{}", err);
+
+        let alternatives = goal_alternatives(&prob);
+        assert_eq!(alternatives.len(), 2);
+        let goals: Vec<usize> = alternatives
+            .iter()
+            .map(|prob| {
+                let pb = pddl_to_chronicles(&dom, prob).unwrap();
+                pb.chronicles[0].chronicle.conditions.len()
+            })
+            .collect();
+        assert_eq!(goals, vec![1, 2]);
+
+        // disjunctions are not supported in the preconditions of actions
+        let domain = "(define (domain rooms)
+          (:requirements :strips :typing :disjunctive-preconditions)
+          (:types room robot)
+          (:predicates (at ?r - robot ?l - room))
+          (:action move
+            :parameters (?r - robot ?from ?to - room)
+            :precondition (or (at ?r ?from) (at ?r ?to))
+            :effect (at ?r ?to)))";
+        let dom = parse_pddl_domain(Input::from_string(domain)).unwrap();
+        let err = format!("{:?}", pddl_to_chronicles(&dom, &alternatives[0]).err().unwrap());
+        assert!(err.contains("Unsupported disjunction"), "{}", err);
+    }
+
+    #[test]
+    fn either_parameter() {
+        let domain = "(define (domain transport)
+          (:requirements :strips :typing)
+          (:types car truck bike - vehicle place)
+          (:predicates (at ?v - vehicle ?p - place))
+          (:action drive
+            :parameters (?v - (either truck car) ?from ?to - place)
+            :precondition (at ?v ?from)
+            :effect (and (at ?v ?to) (not (at ?v ?from)))))";
+        let problem = "(define (problem transport-1)
+          (:domain transport)
+          (:objects c1 c2 - car t1 - truck b1 - bike home work - place)
+          (:init (at c1 home) (at t1 home) (at b1 home))
+          (:goal (at c1 work)))";
+        let dom = parse_pddl_domain(Input::from_string(domain)).unwrap();
+        let prob = parse_pddl_problem(Input::from_string(problem)).unwrap();
+        let pb = pddl_to_chronicles(&dom, &prob).unwrap();
+
+        let symbols = &pb.context.model.symbols;
+        let drive = &pb.templates[0].chronicle;
+        let tpe = drive.name[1].tpe();
+        assert_eq!(symbols.types.from_id(tpe).as_str(), "(either car truck)");
+        let mut domain: Vec<&str> = symbols
+            .instances_of_type(tpe)
+            .map(|sym| symbols.symbol(sym).as_str())
+            .collect();
+        domain.sort_unstable();
+        assert_eq!(domain, vec!["c1", "c2", "t1"]);
+
+        // the union remains a subtype of the common parent of its members
+        let vehicle = symbols.types.id_of("vehicle").unwrap();
+        assert!(symbols.types.is_subtype(vehicle, tpe));
+    }
+
+    #[test]
+    fn object_fluent() {
+        let domain = "(define (domain trucks)
+          (:requirements :strips :typing :object-fluents)
+          (:types truck city)
+          (:functions (loc ?t - truck) - city (total-cost) - number)
+          (:action drive
+            :parameters (?t - truck ?from ?to - city)
+            :precondition (= (loc ?t) ?from)
+            :effect (and (= (loc ?t) ?to) (increase (total-cost) 1))))";
+        let problem = "(define (problem trucks-1)
+          (:domain trucks)
+          (:objects t1 - truck home work - city)
+          (:init (= (loc t1) home) (= (total-cost) 0))
+          (:goal (= (loc t1) work))
+          (:metric minimize (total-cost)))";
+        let dom = parse_pddl_domain(Input::from_string(domain)).unwrap();
+        let prob = parse_pddl_problem(Input::from_string(problem)).unwrap();
+        assert_eq!(dom.functions.len(), 2);
+        assert!(!dom.functions[0].is_numeric());
+        assert!(dom.functions[1].is_numeric());
+        let pb = pddl_to_chronicles(&dom, &prob).unwrap();
+
+        let symbols = &pb.context.model.symbols;
+        let city = symbols.types.id_of("city").unwrap();
+        // the value of `loc` is a city
+        let loc = &pb.context.state_functions[0];
+        assert_eq!(symbols.symbol(loc.sym).as_str(), "loc");
+        assert!(loc.return_type() == Type::Sym(city));
+
+        let drive = &pb.templates[0].chronicle;
+        assert_eq!(drive.effects.len(), 1);
+        let to = drive.name[3];
+        assert_eq!(drive.effects[0].value, Atom::from(to));
+        assert!(matches!(drive.effects[0].value, Atom::Sym(SAtom::Var(_))));
+        assert_eq!(drive.conditions[0].value, Atom::from(drive.name[2]));
+        assert_eq!(drive.cost, Some(1));
+
+        let init = &pb.chronicles[0].chronicle;
+        let home = pb.context.typed_sym(symbols.id("home").unwrap());
+        let work = pb.context.typed_sym(symbols.id("work").unwrap());
+        assert_eq!(init.effects.len(), 1);
+        assert_eq!(init.effects[0].value, Atom::from(SAtom::from(home)));
+        assert_eq!(init.conditions[0].value, Atom::from(SAtom::from(work)));
+    }
+
+    #[test]
+    fn object_fluent_either_parameter() {
+        let domain = "(define (domain vehicles)
+          (:requirements :strips :typing :object-fluents)
+          (:types truck plane city)
+          (:functions (loc ?v - (either truck plane)) - city)
+          (:action drive
+            :parameters (?t - truck ?to - city)
+            :precondition ()
+            :effect (= (loc ?t) ?to)))";
+        let problem = "(define (problem vehicles-1)
+          (:domain vehicles)
+          (:objects t1 - truck home work - city)
+          (:init (= (loc t1) home))
+          (:goal (= (loc t1) work)))";
+        let dom = parse_pddl_domain(Input::from_string(domain)).unwrap();
+        let prob = parse_pddl_problem(Input::from_string(problem)).unwrap();
+        let pb = pddl_to_chronicles(&dom, &prob).unwrap();
+
+        let symbols = &pb.context.model.symbols;
+        let union = symbols.types.id_of("(either plane truck)").unwrap();
+        let loc = &pb.context.state_functions[0];
+        assert!(loc.tpe[0] == Type::Sym(union));
+    }
+
+    #[test]
+    fn closed_world_init() {
+        let domain = "(define (domain doors)
+          (:requirements :strips :typing :negative-preconditions)
+          (:types door)
+          (:predicates (open ?d - door) (inside))
+          (:action enter
+            :parameters (?d - door)
+            :precondition (and (open ?d) (not (inside)))
+            :effect (inside)))";
+        let problem = "(define (problem doors-1)
+          (:domain doors)
+          (:objects front back - door)
+          (:init (open front))
+          (:goal (and (inside) (not (open back)))))";
+        let dom = parse_pddl_domain(Input::from_string(domain)).unwrap();
+        let prob = parse_pddl_problem(Input::from_string(problem)).unwrap();
+        let pb = pddl_to_chronicles(&dom, &prob).unwrap();
+        let model = &pb.context.model;
+        let fmt_sv = |sv: &[SAtom]| sv.iter().map(|&x| model.fmt(x).to_string()).join(" ");
+
+        // all ground predicates have a value in the initial state
+        let init = &pb.chronicles[0].chronicle;
+        let mut init_values: Vec<String> = init
+            .effects
+            .iter()
+            .map(|e| format!("({}) {}", fmt_sv(&e.state_var), model.fmt(e.value)))
+            .collect();
+        init_values.sort();
+        assert_eq!(
+            init_values,
+            vec!["(inside) false", "(open back) false", "(open front) true"]
+        );
+
+        // negative conditions are supported by the initial state
+        let supported = |c: &Condition| {
+            init.effects
+                .iter()
+                .any(|e| model.unifiable_seq(&e.state_var, &c.state_var) && model.unifiable(e.value, c.value))
+        };
+        let enter = &pb.templates[0].chronicle;
+        let not_inside = enter
+            .conditions
+            .iter()
+            .find(|c| fmt_sv(&c.state_var) == "inside")
+            .unwrap();
+        assert!(supported(not_inside));
+        let not_open_back = init
+            .conditions
+            .iter()
+            .find(|c| fmt_sv(&c.state_var) == "open back")
+            .unwrap();
+        assert!(supported(not_open_back));
+    }
+
+    #[test]
+    fn parameter_shadowing_constant() {
+        let domain = "(define (domain visits)
+          (:requirements :strips)
+          (:constants home)
+          (:predicates (at ?x) (visited ?x))
+          (:action go
+            :parameters (home)
+            :precondition (at home)
+            :effect (visited home)))";
+        let problem = "(define (problem visits-1)
+          (:domain visits)
+          (:objects office)
+          (:init (at office))
+          (:goal (visited home)))";
+        let dom = parse_pddl_domain(Input::from_string(domain)).unwrap();
+        let prob = parse_pddl_problem(Input::from_string(problem)).unwrap();
+        let pb = pddl_to_chronicles(&dom, &prob).unwrap();
+        let home = pb.context.model.symbols.id("home").unwrap();
+        let home = SAtom::from(pb.context.typed_sym(home));
+
+        // within the action, `home` is the parameter, in both the preconditions and the effects
+        let go = &pb.templates[0].chronicle;
+        let param = go.name[1];
+        assert!(matches!(param, SAtom::Var(_)));
+        assert_eq!(go.conditions[0].state_var[1], param);
+        assert_eq!(go.effects[0].state_var[1], param);
+
+        // in the goal, `home` is the constant
+        let goal = &pb.chronicles[0].chronicle.conditions[0];
+        assert_eq!(goal.state_var[1], home);
+    }
+
+    #[test]
+    fn unknown_parameter_type() {
+        let err = conversion_error("(?r - robot ?to - rom)", "(at ?r ?to)");
+        assert!(
+            err.contains("Unknown type `rom` for parameter `?to` of `move`"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn incompatible_argument_type() {
+        let err = conversion_error("(?r - robot ?to - robot)", "(at ?r ?to)");
+        assert!(
+            err.contains(
+                "Argument `?to` of predicate `at` has type `robot`, incompatible with the expected type `room`"
+            ),
+            "{}",
+            err
+        );
+    }
 }
@@ -26,7 +26,73 @@ pub enum PddlFeature {
     NegativePreconditions,
     Hierarchy,
     MethodPreconditions,
+    ActionCosts,
+    DisjunctivePreconditions,
+    ExistentialPreconditions,
+    UniversalPreconditions,
+    QuantifiedPreconditions,
+    ConditionalEffects,
+    Adl,
+    DurativeActions,
+    DurationInequalities,
+    TimedInitialLiterals,
+    NumericFluents,
+    ObjectFluents,
+    Fluents,
+    DerivedPredicates,
+    Preferences,
+    Constraints,
+}
+
+impl PddlFeature {
+    /// Requirement flag of the feature, as it appears in the `:requirements` of a domain.
+    pub fn flag(self) -> &'static str {
+        match self {
+            PddlFeature::Strips => ":strips",
+            PddlFeature::Typing => ":typing",
+            PddlFeature::Equality => ":equality",
+            PddlFeature::NegativePreconditions => ":negative-preconditions",
+            PddlFeature::Hierarchy => ":hierarchy",
+            PddlFeature::MethodPreconditions => ":method-preconditions",
+            PddlFeature::ActionCosts => ":action-costs",
+            PddlFeature::DisjunctivePreconditions => ":disjunctive-preconditions",
+            PddlFeature::ExistentialPreconditions => ":existential-preconditions",
+            PddlFeature::UniversalPreconditions => ":universal-preconditions",
+            PddlFeature::QuantifiedPreconditions => ":quantified-preconditions",
+            PddlFeature::ConditionalEffects => ":conditional-effects",
+            PddlFeature::Adl => ":adl",
+            PddlFeature::DurativeActions => ":durative-actions",
+            PddlFeature::DurationInequalities => ":duration-inequalities",
+            PddlFeature::TimedInitialLiterals => ":timed-initial-literals",
+            PddlFeature::NumericFluents => ":numeric-fluents",
+            PddlFeature::ObjectFluents => ":object-fluents",
+            PddlFeature::Fluents => ":fluents",
+            PddlFeature::DerivedPredicates => ":derived-predicates",
+            PddlFeature::Preferences => ":preferences",
+            PddlFeature::Constraints => ":constraints",
+        }
+    }
+
+    /// Returns true if problems with this requirement can be converted into chronicles.
+    ///
+    /// For `:disjunctive-preconditions`, only a top-level disjunction of goals is supported (see `goal_alternatives`):
+    /// a disjunction anywhere else is rejected when converting the expression that contains it.
+    pub fn is_supported(self) -> bool {
+        matches!(
+            self,
+            PddlFeature::Strips
+                | PddlFeature::Typing
+                | PddlFeature::Equality
+                | PddlFeature::NegativePreconditions
+                | PddlFeature::Hierarchy
+                | PddlFeature::MethodPreconditions
+                | PddlFeature::ActionCosts
+                | PddlFeature::ObjectFluents
+                | PddlFeature::DisjunctivePreconditions
+        )
+    }
 }
+
 impl std::str::FromStr for PddlFeature {
     type Err = String;
 
@@ -38,6 +104,22 @@ impl std::str::FromStr for PddlFeature {
             ":negative-preconditions" => Ok(PddlFeature::NegativePreconditions),
             ":hierarchy" => Ok(PddlFeature::Hierarchy),
             ":method-preconditions" => Ok(PddlFeature::MethodPreconditions),
+            ":action-costs" => Ok(PddlFeature::ActionCosts),
+            ":disjunctive-preconditions" => Ok(PddlFeature::DisjunctivePreconditions),
+            ":existential-preconditions" => Ok(PddlFeature::ExistentialPreconditions),
+            ":universal-preconditions" => Ok(PddlFeature::UniversalPreconditions),
+            ":quantified-preconditions" => Ok(PddlFeature::QuantifiedPreconditions),
+            ":conditional-effects" => Ok(PddlFeature::ConditionalEffects),
+            ":adl" => Ok(PddlFeature::Adl),
+            ":durative-actions" => Ok(PddlFeature::DurativeActions),
+            ":duration-inequalities" => Ok(PddlFeature::DurationInequalities),
+            ":timed-initial-literals" => Ok(PddlFeature::TimedInitialLiterals),
+            ":numeric-fluents" => Ok(PddlFeature::NumericFluents),
+            ":object-fluents" => Ok(PddlFeature::ObjectFluents),
+            ":fluents" => Ok(PddlFeature::Fluents),
+            ":derived-predicates" => Ok(PddlFeature::DerivedPredicates),
+            ":preferences" => Ok(PddlFeature::Preferences),
+            ":constraints" => Ok(PddlFeature::Constraints),
             _ => Err(format!("Unknown feature `{}`", s)),
         }
     }
@@ -50,6 +132,9 @@ pub struct Domain {
     pub types: Vec<TypedSymbol>,
     pub constants: Vec<TypedSymbol>,
     pub predicates: Vec<Predicate>,
+    /// Functions of the domain, either numeric (e.g. `(total-cost) - number`) or
+    /// object fluents (e.g. `(loc ?t - truck) - city`).
+    pub functions: Vec<Function>,
     pub tasks: Vec<TaskDef>,
     pub methods: Vec<Method>,
     pub actions: Vec<Action>,
@@ -83,16 +168,45 @@ impl Display for Tpe {
     }
 }
 
+/// Type annotation of a symbol.
+#[derive(Debug, Clone)]
+pub enum TypeExpr {
+    /// A single type, e.g., `location`
+    Named(Sym),
+    /// A union of at least two distinct types, e.g., `(either car truck)`.
+    /// Member types are sorted by name.
+    Either(Vec<Sym>),
+}
+impl TypeExpr {
+    /// Name of the type in the type hierarchy.
+    /// A union is identified by its sorted members, e.g., `(either car truck)`, so that all occurrences
+    /// of the same union share a name. This name cannot clash with a user-defined type.
+    pub fn name(&self) -> Sym {
+        match self {
+            TypeExpr::Named(tpe) => tpe.clone(),
+            TypeExpr::Either(members) => {
+                let members: Vec<&str> = members.iter().map(Sym::as_str).collect();
+                format!("(either {})", members.join(" ")).into()
+            }
+        }
+    }
+}
+impl Display for TypeExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "{}", self.name())
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct TypedSymbol {
     pub symbol: Sym,
-    pub tpe: Option<Sym>,
+    pub tpe: Option<TypeExpr>,
 }
 impl TypedSymbol {
     pub fn new(symbol: impl Into<Sym>, tpe: impl Into<Sym>) -> TypedSymbol {
         TypedSymbol {
             symbol: symbol.into(),
-            tpe: Some(tpe.into()),
+            tpe: Some(TypeExpr::Named(tpe.into())),
         }
     }
 }
@@ -119,6 +233,26 @@ impl Display for Predicate {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct Function {
+    pub name: Sym,
+    pub args: Vec<TypedSymbol>,
+    /// Type of the value of the function, `number` for numeric functions.
+    pub tpe: Sym,
+}
+impl Function {
+    pub fn is_numeric(&self) -> bool {
+        self.tpe.as_str() == "number"
+    }
+}
+impl Display for Function {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "{}(", self.name)?;
+        disp_iter(f, self.args.as_slice(), ", ")?;
+        write!(f, ") - {}", self.tpe)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct TaskDef {
     pub name: Sym,
@@ -201,9 +335,32 @@ impl Display for Action {
         write!(f, ")")
     }
 }
+/// Consume a type, either a single name (`loc`) or a union of types (`(either car truck)`)
+fn consume_type(input: &mut ListIter) -> std::result::Result<TypeExpr, ErrLoc> {
+    let next = input.pop()?;
+    if let Some(tpe) = next.as_atom() {
+        return Result::Ok(TypeExpr::Named(tpe.into()));
+    }
+    let union = next.as_list().ok_or_else(|| next.invalid("Expected a type"))?;
+    let mut members_iter = union.iter();
+    members_iter.pop_known_atom("either")?;
+    let mut members: Vec<Sym> = Vec::with_capacity(members_iter.len());
+    while !members_iter.is_empty() {
+        members.push(members_iter.pop_atom()?.into());
+    }
+    members.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+    members.dedup_by(|a, b| a.as_str() == b.as_str());
+    match members.len() {
+        0 => Err(union.invalid("Expected at least one type in `either`")),
+        1 => Result::Ok(TypeExpr::Named(members.pop().unwrap())),
+        _ => Result::Ok(TypeExpr::Either(members)),
+    }
+}
+
 /// Consume a typed list of symbols
 ///  - (a - loc b - loc c - loc) : symbols a, b and c of type loc
 ///  - (a b c - loc)  : symbols a, b and c of type loc
+///  - (a b - (either car truck)) : symbols a and b whose type is either car or truck
 ///  - (a b c) : symbols a b and c of type object
 fn consume_typed_symbols(input: &mut ListIter) -> std::result::Result<Vec<TypedSymbol>, ErrLoc> {
     let mut args = Vec::with_capacity(input.len() / 3);
@@ -211,10 +368,13 @@ fn consume_typed_symbols(input: &mut ListIter) -> std::result::Result<Vec<TypedS
     while !input.is_empty() {
         let next = input.pop_atom()?;
         if next.as_str() == "-" {
-            let tpe = input.pop_atom()?;
+            let tpe = consume_type(input)?;
             untyped
                 .drain(..)
-                .map(|name| TypedSymbol::new(name, tpe))
+                .map(|name| TypedSymbol {
+                    symbol: name,
+                    tpe: Some(tpe.clone()),
+                })
                 .for_each(|a| args.push(a));
         } else {
             untyped.push(next.into());
@@ -247,6 +407,7 @@ fn read_domain(dom: SExpr) -> std::result::Result<Domain, ErrLoc> {
         types: vec![],
         constants: vec![],
         predicates: vec![],
+        functions: vec![],
         tasks: vec![],
         methods: vec![],
         actions: vec![],
@@ -277,6 +438,36 @@ fn read_domain(dom: SExpr) -> std::result::Result<Domain, ErrLoc> {
                     res.predicates.push(Predicate { name, args });
                 }
             }
+            ":functions" => {
+                // functions whose return type is not yet known
+                let mut untyped = Vec::new();
+                while let Some(fun) = property.next() {
+                    if fun.is_atom("-") {
+                        // return type of the previous functions
+                        let tpe: Sym = property.pop_atom()?.into();
+                        for (name, args) in untyped.drain(..) {
+                            res.functions.push(Function {
+                                name,
+                                args,
+                                tpe: tpe.clone(),
+                            });
+                        }
+                        continue;
+                    }
+                    let mut fun = fun.as_list_iter().ok_or_else(|| fun.invalid("Expected a list"))?;
+                    let name = fun.pop_atom()?.clone();
+                    let args = consume_typed_symbols(&mut fun)?;
+                    untyped.push((name, args));
+                }
+                // functions are numeric by default
+                for (name, args) in untyped {
+                    res.functions.push(Function {
+                        name,
+                        args,
+                        tpe: "number".into(),
+                    });
+                }
+            }
             ":types" => {
                 if !res.types.is_empty() {
                     return Err(current.invalid("More than one ':types' section definition"));
@@ -487,6 +678,13 @@ fn parse_conjunction<T>(e: &SExpr, item_parser: impl Fn(&SExpr) -> R<T>) -> R<Ve
     }
 }
 
+/// Objective of a planning problem, given by a `:metric` block, e.g., `(:metric minimize (total-cost))`.
+#[derive(Clone, Debug)]
+pub enum Metric {
+    Minimize(SExpr),
+    Maximize(SExpr),
+}
+
 #[derive(Clone, Debug)]
 pub struct Problem {
     pub problem_name: Sym,
@@ -495,6 +693,7 @@ pub struct Problem {
     pub init: Vec<SExpr>,
     pub task_network: Option<TaskNetwork>,
     pub goal: Vec<SExpr>,
+    pub metric: Option<Metric>,
 }
 
 impl Display for Problem {
@@ -541,6 +740,7 @@ fn read_problem(problem: SExpr) -> std::result::Result<Problem, ErrLoc> {
         init: vec![],
         task_network: None,
         goal: vec![],
+        metric: None,
     };
 
     for current in problem {
@@ -565,6 +765,18 @@ fn read_problem(problem: SExpr) -> std::result::Result<Problem, ErrLoc> {
                     res.goal.push(goal.clone());
                 }
             }
+            ":metric" => {
+                if res.metric.is_some() {
+                    return Err(current.invalid("More than one metric specified"));
+                }
+                let direction = property.pop_atom()?.clone();
+                let expr = property.pop()?.clone();
+                res.metric = match direction.as_str() {
+                    "minimize" => Some(Metric::Minimize(expr)),
+                    "maximize" => Some(Metric::Maximize(expr)),
+                    _ => return Err(direction.invalid("Expected `minimize` or `maximize`")),
+                };
+            }
             ":htn" => {
                 if res.task_network.is_some() {
                     return Err(current.invalid("More than one task network specified"));
@@ -594,6 +806,34 @@ mod tests {
         Result::Ok(())
     }
 
+    #[test]
+    fn parsing_metric() -> Result<()> {
+        let domain = "(define (domain costs)
+          (:requirements :strips :action-costs)
+          (:predicates (done))
+          (:functions (total-cost) - number)
+          (:action finish
+            :parameters ()
+            :precondition ()
+            :effect (and (done) (increase (total-cost) 3))))";
+        let problem = "(define (problem costs-1)
+          (:domain costs)
+          (:init (= (total-cost) 0))
+          (:goal (done))
+          (:metric minimize (total-cost)))";
+        let dom = parse_pddl_domain(Input::from_string(domain))?;
+        assert!(dom.features.contains(&PddlFeature::ActionCosts));
+        assert_eq!(dom.functions.len(), 1);
+        assert_eq!(dom.functions[0].name.as_str(), "total-cost");
+
+        let pb = parse_pddl_problem(Input::from_string(problem))?;
+        match pb.metric {
+            Some(Metric::Minimize(e)) => assert_eq!(e.to_string(), "(total-cost)"),
+            x => panic!("Unexpected metric: {:?}", x),
+        }
+        Ok(())
+    }
+
     #[test]
     fn parsing_hddl() -> Result<()> {
         let source = "../problems/hddl/towers/domain.hddl";
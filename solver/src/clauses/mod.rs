@@ -311,6 +311,12 @@ impl ClauseDB {
         self.metadata.keys()
     }
 
+    pub fn learnt_clauses(&self) -> impl Iterator<Item = ClauseId> + '_ {
+        self.metadata
+            .entries()
+            .filter_map(|(id, meta)| if meta.learnt { Some(id) } else { None })
+    }
+
     pub fn bump_activity(&mut self, cl: ClauseId) {
         self.metadata[cl].activity += self.params.cla_inc;
         if self.metadata[cl].activity > 1e100_f64 {
@@ -329,7 +335,13 @@ impl ClauseDB {
         self.params.cla_inc *= 1e-100_f64;
     }
 
-    pub fn reduce_db<F: Fn(ClauseId) -> bool>(&mut self, locked: F, remove_watch: &mut impl FnMut(ClauseId, Bound)) {
+    /// Removes the least active fraction (`fraction_removed`, between 0 and 1) of the learnt clauses that are not locked.
+    pub fn reduce_db<F: Fn(ClauseId) -> bool>(
+        &mut self,
+        locked: F,
+        fraction_removed: f64,
+        remove_watch: &mut impl FnMut(ClauseId, Bound),
+    ) {
         let mut clauses: Vec<_> = self
             .metadata
             .entries()
@@ -343,8 +355,8 @@ impl ClauseDB {
             .collect();
 
         clauses.sort_by(|&a, &b| a.1.partial_cmp(&b.1).unwrap_or(Equal));
-        // remove half removable
-        clauses.iter().take(clauses.len() / 2).for_each(|&(id, _)| {
+        let num_removed = (clauses.len() as f64 * fraction_removed) as usize;
+        clauses.iter().take(num_removed).for_each(|&(id, _)| {
             let cl = &self.clauses[id];
             if !cl.is_empty() {
                 remove_watch(id, !cl.watch1);
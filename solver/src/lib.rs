@@ -24,6 +24,24 @@ pub trait Theory: Backtrack {
     fn explain(&mut self, literal: Bound, context: u32, model: &DiscreteModel, out_explanation: &mut Explanation);
 
     fn print_stats(&self);
+
+    /// Statistics on the activity of the theory, by default no activity is reported.
+    fn stats(&self) -> TheoryStats {
+        TheoryStats::default()
+    }
+}
+
+/// Counters summarizing the activity of a theory during the search.
+#[derive(Clone, Debug, Default)]
+pub struct TheoryStats {
+    /// Number of expressions that were submitted to the theory with `bind`.
+    pub num_bindings: u64,
+    /// Number of propagations run by the theory.
+    pub num_propagations: u64,
+    /// Number of propagations that resulted in a conflict.
+    pub num_conflicts: u64,
+    /// Largest number of elements simultaneously in the propagation queue of the theory, if it has one.
+    pub max_queue_len: usize,
 }
 
 #[derive(Debug)]
@@ -3,10 +3,10 @@ pub mod sat_solver;
 pub mod stats;
 pub mod theory_solver;
 
-use crate::{Contradiction, Theory};
+use crate::{Contradiction, Theory, TheoryStats};
 use aries_backtrack::ObsTrail;
 use aries_backtrack::{Backtrack, DecLvl};
-use aries_model::lang::{BAtom, BExpr, IAtom, IntCst};
+use aries_model::lang::{BAtom, BExpr, BVar, IAtom, IVar, IntCst, INT_CST_MAX};
 use aries_model::{Model, WriterId};
 
 use crate::solver::brancher::{Brancher, Decision};
@@ -14,16 +14,36 @@ use crate::solver::sat_solver::SatSolver;
 use crate::solver::stats::Stats;
 use crate::solver::theory_solver::TheorySolver;
 use aries_model::assignments::{Assignment, SavedAssignment};
-use aries_model::int_model::{DiscreteModel, Explainer, Explanation, InferenceCause};
+use aries_model::int_model::{Cause, DiscreteModel, Explainer, Explanation, InferenceCause};
 
 use crate::cpu_time::CycleCount;
 use crate::cpu_time::StartCycleCount;
 use aries_model::bounds::{Bound, Disjunction};
 use env_param::EnvParam;
+use std::collections::{BTreeSet, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::Instant;
 
 pub static OPTIMIZE_USES_LNS: EnvParam<bool> = EnvParam::new("ARIES_SMT_OPTIMIZE_USES_LNS", "true");
 
+/// Maximum number of values of a partial sum in the exact encoding of the penalty of `solve_maxsat`.
+pub const MAX_PENALTY_VALUES: usize = 1000;
+
+/// Returns true if the subsets of `weights` have at most `max` distinct totals.
+fn reachable_penalties_at_most(weights: &[IntCst], max: usize) -> bool {
+    let mut reachable: BTreeSet<IntCst> = BTreeSet::new();
+    reachable.insert(0);
+    for &weight in weights {
+        let shifted: Vec<IntCst> = reachable.iter().map(|&k| k + weight).collect();
+        reachable.extend(shifted);
+        if reachable.len() > max {
+            return false;
+        }
+    }
+    true
+}
+
 struct Reasoners {
     sat: SatSolver,
     theories: Vec<TheorySolver>,
@@ -61,9 +81,22 @@ impl Explainer for Reasoners {
 
 pub struct Solver {
     pub model: Model,
-    brancher: Brancher,
+    pub brancher: Brancher,
     reasoners: Reasoners,
     decision_level: DecLvl,
+    /// Constraints enforced with `enforce_all_tracked`, together with the literal that activates them.
+    tracked: Vec<(Bound, BAtom)>,
+    /// Tracked constraints responsible for the unsatisfiability detected by the last call to `solve`.
+    unsat_core: Vec<BAtom>,
+    /// For each assertion level opened by `push`, a literal that activates all constraints enforced in it,
+    /// together with the number of tracked constraints when it was opened.
+    assertion_levels: Vec<(Bound, usize)>,
+    /// If set, the search will be interrupted at the next restart once this flag is raised.
+    interrupt: Option<Arc<AtomicBool>>,
+    /// Soft constraints added with `add_soft`, together with the penalty incurred when they are violated.
+    soft_constraints: Vec<(BAtom, u64)>,
+    /// If set, receives a description of each step of the search (see `set_trace`).
+    trace: Option<Box<dyn FnMut(TraceEvent)>>,
     pub stats: Stats,
 }
 impl Solver {
@@ -75,6 +108,12 @@ impl Solver {
             brancher: Brancher::new(),
             reasoners: Reasoners::new(sat, sat_id),
             decision_level: DecLvl::ROOT,
+            tracked: Vec::new(),
+            unsat_core: Vec::new(),
+            assertion_levels: Vec::new(),
+            interrupt: None,
+            soft_constraints: Vec::new(),
+            trace: None,
             stats: Default::default(),
         }
     }
@@ -86,13 +125,124 @@ impl Solver {
         self.stats.per_module_propagation_loops.push(0);
     }
 
+    /// Provides a guess for the solution that the search should first try to reach: when branching on a variable
+    /// bound in `guess`, the solver will first try its value in `guess`.
+    ///
+    /// This is typically used to warm-start the search from the solution of a closely related problem.
+    /// Variables are matched by their identifier, which means the `guess` should come from a model sharing its
+    /// variables with the one of the solver (e.g. built from a common ancestor).
+    pub fn set_initial_guess(&mut self, guess: &SavedAssignment) {
+        self.brancher.set_default_values_from(guess);
+    }
+
+    /// Sets the seed used by the solver for breaking ties in its branching heuristic.
+    /// Solving the same problem with the same seed always results in the same sequence of decisions.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.brancher.set_seed(seed);
+    }
+
+    /// Sets a flag that allows cooperatively interrupting the search from another thread.
+    /// When the flag is raised, any ongoing search will give up at its next restart.
+    pub fn set_interrupt_flag(&mut self, flag: Arc<AtomicBool>) {
+        self.interrupt = Some(flag);
+    }
+
+    /// Installs a sink that will be notified of every decision, propagation, conflict, restart and backtrack
+    /// of the search, in the order in which they occur. This is intended for debugging purposes.
+    ///
+    /// When no sink is installed, no event is built.
+    pub fn set_trace(&mut self, sink: Box<dyn FnMut(TraceEvent)>) {
+        self.trace = Some(sink);
+    }
+
+    /// Reports to the trace sink, if any, all inferences made since the `first` event of the trail.
+    fn trace_propagations(&mut self, first: u32) {
+        if let Some(sink) = &mut self.trace {
+            for ev in &self.model.discrete.trail().events()[first as usize..] {
+                if let Cause::Inference(cause) = ev.cause {
+                    sink(TraceEvent::Propagation {
+                        lit: ev.new_literal(),
+                        by: cause,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Returns true if the flag set with `set_interrupt_flag` has been raised.
+    fn is_interrupted(&self) -> bool {
+        matches!(&self.interrupt, Some(flag) if flag.load(Ordering::Relaxed))
+    }
+
     /// Impose the constraint that the given boolean atom is true in the final model.
     pub fn enforce(&mut self, constraint: impl Into<BAtom>) {
         self.enforce_all(&[constraint.into()])
     }
 
+    /// Impose the constraint that at least one of the literals is true in the final model.
+    ///
+    /// This is typically used to inject clauses learned by a previous solver (see `learned_clauses`).
+    pub fn add_clause(&mut self, clause: &[Bound]) {
+        let disjunction = self.model.or_from_iter(clause.iter().map(|&l| BAtom::from(l)));
+        self.enforce(disjunction)
+    }
+
+    /// Bounds the number of clauses learned from conflicts: on each restart and as long as the database holds more
+    /// than `max_learnt` learned clauses, the `reduction_factor` (between 0 and 1) least active ones are deleted.
+    pub fn set_clause_db_policy(&mut self, max_learnt: usize, reduction_factor: f32) {
+        self.reasoners.sat.set_clause_db_policy(max_learnt, reduction_factor);
+    }
+
+    /// Returns the clauses learned from conflicts that are currently in the database of the solver.
+    pub fn learned_clauses(&self) -> impl Iterator<Item = Vec<Bound>> + '_ {
+        self.reasoners.sat.learnt_clauses()
+    }
+
+    /// Impose the constraint that the two boolean atoms have the same value in the final model.
+    pub fn enforce_iff(&mut self, a: impl Into<BAtom>, b: impl Into<BAtom>) {
+        let iff = self.model.iff(a, b);
+        self.enforce(iff)
+    }
+
     /// Impose the constraint that all given boolean atoms are true in the final model.
+    ///
+    /// If an assertion level was opened with `push`, the constraints will be removed by the matching `pop`.
     pub fn enforce_all(&mut self, constraints: &[BAtom]) {
+        match self.assertion_levels.last() {
+            Some(&(level, _)) => {
+                let scoped: Vec<BAtom> = constraints.iter().map(|&c| self.model.implies(level, c)).collect();
+                self.enforce_all_unscoped(&scoped)
+            }
+            None => self.enforce_all_unscoped(constraints),
+        }
+    }
+
+    /// Opens a new assertion level: all constraints enforced until the matching `pop` will be removed by it.
+    ///
+    /// Assertion levels are independent of the decision levels of the search. Constraints of an assertion level
+    /// are reified with a literal that is assumed true on every call to `solve`. Clauses learnt from them
+    /// will contain the negation of this literal and are thus discarded along with them.
+    ///
+    /// Note that the constraints on the objective imposed by `minimize_with` and `maximize_with` are scoped as
+    /// any other constraint: after a `pop`, the objective bounds found within the assertion level are lost.
+    pub fn push(&mut self) {
+        self.reset();
+        let level = self.model.new_bvar("assertion-level").true_lit();
+        self.assertion_levels.push((level, self.tracked.len()));
+    }
+
+    /// Closes the last assertion level opened by `push`, removing all constraints enforced since then.
+    pub fn pop(&mut self) {
+        self.reset();
+        let (level, num_tracked) = self.assertion_levels.pop().expect("No assertion level to pop");
+        // tracked constraints of this level are no longer assumed nor reported in unsat cores
+        self.tracked.truncate(num_tracked);
+        self.unsat_core.clear();
+        // permanently disable the constraints of this level
+        self.enforce_all_unscoped(&[(!level).into()]);
+    }
+
+    fn enforce_all_unscoped(&mut self, constraints: &[BAtom]) {
         let start_time = Instant::now();
         let start_cycles = StartCycleCount::now();
         let mut queue = ObsTrail::new();
@@ -148,15 +298,129 @@ impl Solver {
         self.stats.init_cycles += start_cycles.elapsed();
     }
 
+    /// Impose the constraint that all given boolean atoms are true in the final model, while keeping
+    /// track of them so that they can appear in an unsat core (see `unsat_core`).
+    ///
+    /// Each constraint is reified with a fresh literal that is assumed true on every call to `solve`.
+    /// This weakens propagation at the root level and should be reserved to problems for which an
+    /// explanation of infeasibility is needed.
+    pub fn enforce_all_tracked(&mut self, constraints: &[BAtom]) {
+        for &constraint in constraints {
+            let tag = self.model.new_bvar("tracked");
+            let reified = self.model.implies(tag, constraint);
+            self.enforce(reified);
+            self.tracked.push((tag.true_lit(), constraint));
+        }
+    }
+
+    /// Searches for a solution, returning true if one was found.
+    ///
+    /// Note that false is also returned if the search was interrupted (see `set_interrupt_flag`).
     pub fn solve(&mut self) -> bool {
+        match self.solve_with_limit(SolveLimit::default()) {
+            SolveOutcome::Sat => true,
+            SolveOutcome::Unsat => false,
+            SolveOutcome::Unknown => {
+                debug_assert!(self.is_interrupted(), "No limit on the search");
+                false
+            }
+        }
+    }
+
+    /// Searches for a solution, giving up with `SolveOutcome::Unknown` if the given limit is exceeded
+    /// or if the search was interrupted (see `set_interrupt_flag`).
+    ///
+    /// In the latter case, the solver is left in the state it was when interrupted and a subsequent
+    /// call will resume the search from there (keeping all learnt clauses).
+    pub fn solve_with_limit(&mut self, limit: SolveLimit) -> SolveOutcome {
+        if self.tracked.is_empty() && self.assertion_levels.is_empty() {
+            return match self.search(&[], &limit) {
+                Some(Ok(())) => SolveOutcome::Sat,
+                Some(Err(_)) => SolveOutcome::Unsat,
+                None => SolveOutcome::Unknown,
+            };
+        }
+        let assumptions: Vec<Bound> = self
+            .assertion_levels
+            .iter()
+            .map(|&(level, _)| level)
+            .chain(self.tracked.iter().map(|(tag, _)| *tag))
+            .collect();
+        self.reset();
+        match self.search(&assumptions, &limit) {
+            Some(Ok(())) => {
+                self.unsat_core.clear();
+                SolveOutcome::Sat
+            }
+            Some(Err(core)) => {
+                self.unsat_core = self
+                    .tracked
+                    .iter()
+                    .filter(|(tag, _)| core.contains(tag))
+                    .map(|(_, constraint)| *constraint)
+                    .collect();
+                SolveOutcome::Unsat
+            }
+            None => SolveOutcome::Unknown,
+        }
+    }
+
+    /// Returns a subset of the constraints enforced with `enforce_all_tracked` that are sufficient to make
+    /// the problem unsatisfiable, as identified by the last call to `solve`.
+    ///
+    /// The core is empty if the last call to `solve` succeeded, if an assertion level was closed by `pop` since,
+    /// or if the problem is unsatisfiable regardless of the tracked constraints.
+    pub fn unsat_core(&self) -> Vec<BAtom> {
+        self.unsat_core.clone()
+    }
+
+    /// Solves the problem under the assumption that all given literals are true.
+    ///
+    /// The solver is first reset to the root level and the assumptions are then made as the first decisions.
+    /// They are not permanently added to the problem: they will be undone on the next reset.
+    ///
+    /// Returns `Ok(())` if a solution was found, in which case it is available in the solver's model.
+    /// Otherwise, returns `Err(core)` where `core` is a subset of the assumptions that cannot be all true.
+    /// An empty core indicates that the problem is unsatisfiable, regardless of the assumptions.
+    ///
+    /// Panics if the search is interrupted (see `set_interrupt_flag`).
+    pub fn solve_under(&mut self, assumptions: &[Bound]) -> Result<(), Vec<Bound>> {
+        self.reset();
+        self.search(assumptions, &SolveLimit::default())
+            .expect("Search interrupted")
+    }
+
+    /// Main search loop. The assumptions are, in order, enforced as the first decisions of the search.
+    /// The i-th assumption is always made at decision level `i+1`.
+    ///
+    /// Returns `None` if the search was interrupted because the `limit` was exceeded.
+    fn search(&mut self, assumptions: &[Bound], limit: &SolveLimit) -> Option<Result<(), Vec<Bound>>> {
         let start_time = Instant::now();
         let start_cycles = StartCycleCount::now();
+        let max_conflicts = limit
+            .max_conflicts
+            .map(|max| self.stats.num_conflicts.saturating_add(max));
         loop {
             if !self.propagate_and_backtrack_to_consistent() {
                 // UNSAT
                 self.stats.solve_time += start_time.elapsed();
                 self.stats.solve_cycles += start_cycles.elapsed();
-                return false;
+                return Some(Err(Vec::new()));
+            }
+            if matches!(max_conflicts, Some(max) if self.stats.num_conflicts >= max) {
+                self.stats.solve_time += start_time.elapsed();
+                self.stats.solve_cycles += start_cycles.elapsed();
+                return None;
+            }
+            if let Some(res) = self.decide_next_assumption(assumptions) {
+                match res {
+                    Ok(()) => continue, // new assumption made, propagate it
+                    Err(core) => {
+                        self.stats.solve_time += start_time.elapsed();
+                        self.stats.solve_cycles += start_cycles.elapsed();
+                        return Some(Err(core));
+                    }
+                }
             }
             match self.brancher.next_decision(&self.stats, &self.model) {
                 Some(Decision::SetLiteral(lit)) => {
@@ -164,17 +428,92 @@ impl Solver {
                     self.decide(lit);
                 }
                 Some(Decision::Restart) => {
+                    if let Some(sink) = &mut self.trace {
+                        sink(TraceEvent::Restart);
+                    }
                     self.reset();
                     self.stats.num_restarts += 1;
+                    self.reasoners.sat.reduce_learnt_clauses();
+                    // only check the deadline on restarts, to avoid the cost of querying the clock on each decision
+                    if self.is_interrupted() || matches!(limit.deadline, Some(deadline) if Instant::now() >= deadline) {
+                        self.stats.solve_time += start_time.elapsed();
+                        self.stats.solve_cycles += start_cycles.elapsed();
+                        return None;
+                    }
                 }
                 None => {
                     // SAT: consistent + no choices left
                     self.stats.solve_time += start_time.elapsed();
                     self.stats.solve_cycles += start_cycles.elapsed();
-                    return true;
+                    return Some(Ok(()));
+                }
+            }
+        }
+    }
+
+    /// Makes the decision for the next assumption not yet enforced, if any.
+    ///
+    /// Returns:
+    ///  - `None` if all assumptions are already enforced
+    ///  - `Some(Ok(()))` if a new assumption was made
+    ///  - `Some(Err(core))` if an assumption is violated, where `core` is a subset of the assumptions that cannot be all true.
+    fn decide_next_assumption(&mut self, assumptions: &[Bound]) -> Option<Result<(), Vec<Bound>>> {
+        while (self.decision_level.to_int() as usize) < assumptions.len() {
+            let lit = assumptions[self.decision_level.to_int() as usize];
+            match self.model.discrete.value(lit) {
+                Some(true) => {
+                    // already entailed, open an empty decision level to preserve the level <-> assumption mapping
+                    self.save_state();
+                }
+                Some(false) => {
+                    // violated by the previous assumptions, find the ones responsible for it
+                    let mut explanation = Explanation::with_capacity(1);
+                    explanation.push(!lit);
+                    let mut core = self.model.discrete.implying_decisions(explanation, &mut self.reasoners);
+                    core.push(lit);
+                    // the model has been partially undone by the analysis, go back to a consistent state
+                    // (we cannot use `reset` that would check the consistency of the decision levels)
+                    self.restore(DecLvl::ROOT);
+                    return Some(Err(core));
+                }
+                None => {
+                    self.decide(lit);
+                    return Some(Ok(()));
                 }
             }
         }
+        None
+    }
+
+    /// Enumerates all solutions that differ in their assignment to the given boolean variables.
+    ///
+    /// Each time a solution is found, it is passed to the `on_solution` callback and a clause blocking the current
+    /// values of `vars` is added to the solver. Only the projection of the solutions on `vars` is blocked: two
+    /// solutions with the same values for `vars` will not both be enumerated.
+    /// The enumeration stops when no new solution exists or when the callback returns `false`.
+    ///
+    /// Note that the blocking clauses are permanently added to the solver.
+    pub fn enumerate(&mut self, vars: &[BVar], mut on_solution: impl FnMut(&SavedAssignment) -> bool) {
+        while self.solve() {
+            let sol = SavedAssignment::from_model(&self.model);
+            if !on_solution(&sol) {
+                break;
+            }
+            let blocking_clause: Vec<BAtom> = vars
+                .iter()
+                .map(|&v| match self.model.boolean_value_of(v) {
+                    Some(true) => BAtom::from(v.false_lit()),
+                    Some(false) => BAtom::from(v.true_lit()),
+                    None => panic!(
+                        "Variable {} is not bound in the solution",
+                        self.model.fmt(BAtom::from(v))
+                    ),
+                })
+                .collect();
+            self.reset();
+            let blocking_clause = self.model.or(&blocking_clause);
+            self.enforce(blocking_clause);
+        }
     }
 
     pub fn minimize(&mut self, objective: impl Into<IAtom>) -> Option<(IntCst, SavedAssignment)> {
@@ -184,12 +523,260 @@ impl Solver {
     pub fn minimize_with(
         &mut self,
         objective: impl Into<IAtom>,
-        mut on_new_solution: impl FnMut(IntCst, &SavedAssignment),
+        on_new_solution: impl FnMut(IntCst, &SavedAssignment),
+    ) -> Option<(IntCst, SavedAssignment)> {
+        self.optimize_with(objective.into(), true, on_new_solution)
+    }
+
+    /// Minimizes the objective, calling `on_improve` on each improved solution. The constraints returned by
+    /// `on_improve` are enforced before the search continues, which allows guiding the optimization,
+    /// e.g., by forbidding a feature of the solution that was just found.
+    ///
+    /// Constraints that were not returned before are permanently added to the solver. As they might be violated
+    /// by the solutions found so far, the optimization then restarts from scratch.
+    /// The returned solution is thus the optimum under all constraints requested by `on_improve`.
+    pub fn minimize_with_constraints(
+        &mut self,
+        objective: impl Into<IAtom>,
+        mut on_improve: impl FnMut(IntCst, &SavedAssignment) -> Vec<BAtom>,
     ) -> Option<(IntCst, SavedAssignment)> {
         let objective = objective.into();
+        let mut requested: HashSet<BAtom> = HashSet::new();
+        loop {
+            // improvements are only enforced in this assertion level, to be undone if new constraints are requested
+            self.push();
+            let mut best = None;
+            let mut new_constraints = Vec::new();
+            while self.solve() {
+                let value = self.model.domain_of(objective).0;
+                let sol = SavedAssignment::from_model(&self.model);
+                new_constraints = on_improve(value, &sol)
+                    .into_iter()
+                    .filter(|&c| requested.insert(c))
+                    .collect();
+                if !new_constraints.is_empty() {
+                    break;
+                }
+                best = Some((value, sol));
+                let improved = self.model.lt(objective, value);
+                self.enforce(improved);
+            }
+            self.pop();
+            if new_constraints.is_empty() {
+                return best;
+            }
+            self.enforce_all(&new_constraints);
+        }
+    }
+
+    /// Minimizes the objective by tightening an upper bound on it, instead of improving on successive solutions.
+    ///
+    /// Starting from the lower bound of the objective, feasibility is probed with increasingly large upper
+    /// bounds (the distance to the lower bound being doubled after each failure) until a solution is found.
+    /// The optimum is then found by a binary search between the last infeasible bound and the best solution.
+    ///
+    /// Each probe is made by solving under the assumption that the objective does not exceed the bound,
+    /// so that no constraint is permanently added to the solver.
+    /// Panics if the search is interrupted (see `set_interrupt_flag`).
+    pub fn minimize_doubling(&mut self, objective: impl Into<IAtom>) -> Option<(IntCst, SavedAssignment)> {
+        let objective = objective.into();
+        let var = match objective.var {
+            Some(var) => var,
+            None => {
+                return if self.solve() {
+                    Some((objective.shift, SavedAssignment::from_model(&self.model)))
+                } else {
+                    None
+                };
+            }
+        };
+        self.reset();
+        let (lb, ub) = self.model.domain_of(objective);
+
+        // solves the problem with the additional assumption that the objective is at most `bound`,
+        // returning `Some(Ok(solution))` if feasible, `Some(Err(()))` if infeasible with this bound
+        // and `None` if the problem is infeasible regardless of the objective
+        let probe = |solver: &mut Solver, bound: IntCst| {
+            let objective_bound = Bound::leq(var, bound - objective.shift);
+            let assumptions: Vec<Bound> = solver
+                .assertion_levels
+                .iter()
+                .map(|&(level, _)| level)
+                .chain(solver.tracked.iter().map(|(tag, _)| *tag))
+                .chain(std::iter::once(objective_bound))
+                .collect();
+            match solver.solve_under(&assumptions) {
+                Ok(()) => {
+                    let value = solver.model.domain_of(objective).0;
+                    Some(Ok((value, SavedAssignment::from_model(&solver.model))))
+                }
+                Err(core) if core.contains(&objective_bound) => Some(Err(())),
+                Err(_) => None,
+            }
+        };
+
+        // largest bound known to be infeasible and best solution found so far
+        let mut infeasible = lb - 1;
+        let mut step = 1;
+        let mut best = loop {
+            let bound = ub.min(infeasible.saturating_add(step));
+            match probe(self, bound)? {
+                Ok(solution) => break solution,
+                Err(()) if bound >= ub => return None,
+                Err(()) => {
+                    infeasible = bound;
+                    step = step.saturating_mul(2);
+                }
+            }
+        };
+
+        while infeasible + 1 < best.0 {
+            let bound = infeasible + (best.0 - infeasible) / 2;
+            match probe(self, bound)? {
+                Ok(solution) => best = solution,
+                Err(()) => infeasible = bound,
+            }
+        }
+        Some(best)
+    }
+
+    pub fn maximize(&mut self, objective: impl Into<IAtom>) -> Option<(IntCst, SavedAssignment)> {
+        self.maximize_with(objective, |_, _| ())
+    }
+
+    pub fn maximize_with(
+        &mut self,
+        objective: impl Into<IAtom>,
+        on_new_solution: impl FnMut(IntCst, &SavedAssignment),
+    ) -> Option<(IntCst, SavedAssignment)> {
+        self.optimize_with(objective.into(), false, on_new_solution)
+    }
+
+    /// Adds a soft constraint: solutions are not required to satisfy it, but `solve_maxsat` will minimize the
+    /// total `weight` of the violated ones.
+    ///
+    /// Panics if the weight is greater than `INT_CST_MAX`.
+    pub fn add_soft(&mut self, constraint: impl Into<BAtom>, weight: u64) {
+        assert!(
+            weight <= INT_CST_MAX as u64,
+            "Weight of soft constraint exceeds INT_CST_MAX: {}",
+            weight
+        );
+        self.soft_constraints.push((constraint.into(), weight));
+    }
+
+    /// Searches for a solution of the hard constraints that minimizes the total weight of the violated soft
+    /// constraints (see `add_soft`), returning this total weight together with the solution.
+    /// Returns `None` if the hard constraints are unsatisfiable.
+    ///
+    /// The penalty is encoded as a sequence of partial sums `p_i` where `p_i = p_(i-1) + w_i` if the i-th soft
+    /// constraint is violated and `p_i = p_(i-1)` otherwise, with one clause per value that `p_(i-1)` can reach
+    /// as the total weight of a subset of the previous soft constraints.
+    /// The last partial sum is then minimized by branch-and-bound. The encoding and the bounds on the penalty
+    /// are enforced in a dedicated assertion level (see `push`) that is removed before returning.
+    ///
+    /// With `n` soft constraints of total weight `W`, a partial sum can reach up to `min(2^n, W + 1)` values.
+    /// If any of them would exceed `MAX_PENALTY_VALUES`, the soft constraints are instead stratified by weight:
+    /// the number of violated soft constraints of each weight is minimized in turn, from the heaviest weight
+    /// to the lightest, each stratum taking at most `n + 1` values. The solution is then optimal
+    /// for the lexicographic order on the strata, which is only guaranteed to minimize the total weight
+    /// if each weight is greater than the total weight of all soft constraints with a lighter weight.
+    ///
+    /// Panics if the total weight of the soft constraints is greater than `INT_CST_MAX`.
+    pub fn solve_maxsat(&mut self) -> Option<(IntCst, SavedAssignment)> {
+        let total_weight: u64 = self.soft_constraints.iter().map(|&(_, weight)| weight).sum();
+        assert!(
+            total_weight <= INT_CST_MAX as u64,
+            "Total weight of soft constraints exceeds INT_CST_MAX: {}",
+            total_weight
+        );
+        self.push();
+        let mut softs = Vec::with_capacity(self.soft_constraints.len());
+        for (i, (constraint, weight)) in self.soft_constraints.clone().into_iter().enumerate() {
+            let satisfied = self.model.new_bvar(format!("soft_{}", i + 1));
+            self.enforce_iff(satisfied, constraint);
+            softs.push((satisfied, weight as IntCst));
+        }
+        let weights: Vec<IntCst> = softs.iter().map(|&(_, weight)| weight).collect();
+        let result = if reachable_penalties_at_most(&weights, MAX_PENALTY_VALUES) {
+            let penalty = self.encode_penalty(&softs);
+            self.minimize(penalty)
+        } else {
+            let mut strata: Vec<IntCst> = weights;
+            strata.sort_unstable_by(|a, b| b.cmp(a));
+            strata.dedup();
+            let mut best = None;
+            for weight in strata {
+                let stratum: Vec<(BVar, IntCst)> = softs.iter().copied().filter(|&(_, w)| w == weight).collect();
+                let penalty = self.encode_penalty(&stratum);
+                // the bounds found while minimizing are scoped to keep the penalty of the next strata free
+                self.push();
+                let stratum_result = self.minimize(penalty);
+                self.pop();
+                match stratum_result {
+                    Some((value, solution)) => {
+                        let bounded = self.model.leq(penalty, value);
+                        self.enforce(bounded);
+                        best = Some(solution);
+                    }
+                    None => break,
+                }
+            }
+            best.map(|solution| {
+                let violated = softs
+                    .iter()
+                    .filter(|&&(satisfied, _)| solution.boolean_value_of(satisfied) == Some(false))
+                    .map(|&(_, weight)| weight)
+                    .sum();
+                (violated, solution)
+            })
+        };
+        self.pop();
+        result
+    }
+
+    /// Encodes the total weight of the violated soft constraints as a sequence of partial sums
+    /// (see `solve_maxsat`), where each soft constraint is given by the literal stating it is satisfied.
+    /// Returns the last partial sum.
+    fn encode_penalty(&mut self, softs: &[(BVar, IntCst)]) -> IVar {
+        let mut penalty = self.model.new_ivar(0, 0, "penalty_0");
+        // values that can be taken by the penalty, in increasing order
+        let mut reachable: BTreeSet<IntCst> = BTreeSet::new();
+        reachable.insert(0);
+        for (i, &(satisfied, weight)) in softs.iter().enumerate() {
+            let max_penalty = *reachable.iter().next_back().unwrap();
+            let next = self
+                .model
+                .new_ivar(0, max_penalty + weight, format!("penalty_{}", i + 1));
+            for &k in &reachable {
+                // (p_(i-1) >= k) => (p_i >= k)
+                self.add_clause(&[!Bound::geq(penalty, k), Bound::geq(next, k)]);
+                // (p_(i-1) >= k) && !satisfied => (p_i >= k + w_i)
+                self.add_clause(&[
+                    !Bound::geq(penalty, k),
+                    satisfied.true_lit(),
+                    Bound::geq(next, k + weight),
+                ]);
+            }
+            let shifted: Vec<IntCst> = reachable.iter().map(|&k| k + weight).collect();
+            reachable.extend(shifted);
+            penalty = next;
+        }
+        penalty
+    }
+
+    /// Branch-and-bound optimization: each time a solution is found, the solver is restarted
+    /// with the additional constraint that the next solution must strictly improve on the objective.
+    fn optimize_with(
+        &mut self,
+        objective: IAtom,
+        minimize: bool,
+        mut on_new_solution: impl FnMut(IntCst, &SavedAssignment),
+    ) -> Option<(IntCst, SavedAssignment)> {
         let mut result = None;
         while self.solve() {
-            let lb = self.model.domain_of(objective).0;
+            let (lb, ub) = self.model.domain_of(objective);
+            let value = if minimize { lb } else { ub };
 
             let sol = SavedAssignment::from_model(&self.model);
             if *OPTIMIZE_USES_LNS.get() {
@@ -198,11 +785,15 @@ impl Solver {
                 // around the incumbent solution, only pushed away by the learnt clauses.
                 self.brancher.set_default_values_from(&self.model);
             }
-            on_new_solution(lb, &sol);
-            result = Some((lb, sol));
+            on_new_solution(value, &sol);
+            result = Some((value, sol));
             self.stats.num_restarts += 1;
             self.reset();
-            let improved = self.model.lt(objective, lb);
+            let improved = if minimize {
+                self.model.lt(objective, value)
+            } else {
+                self.model.gt(objective, value)
+            };
             self.enforce_all(&[improved]);
         }
         result
@@ -212,6 +803,51 @@ impl Solver {
         self.save_state();
         self.model.discrete.decide(decision).unwrap();
         self.stats.num_decisions += 1;
+        if let Some(sink) = &mut self.trace {
+            sink(TraceEvent::Decision(decision));
+        }
+    }
+
+    /// Temporarily sets the literal, propagates it and reports the outcome, without making any decision
+    /// or learning any clause. The state of the solver is restored to the one before the probe.
+    ///
+    /// The number of implied literals is the number of bound changes inferred by the propagation of `lit`.
+    /// This is intended for look-ahead heuristics and should be called in a consistent state, e.g.,
+    /// after `propagate_and_backtrack_to_consistent`.
+    pub fn probe(&mut self, lit: Bound) -> ProbeResult {
+        self.save_state();
+        let num_events = self.model.discrete.num_events();
+        let result = if self.model.discrete.decide(lit).is_ok() && self.propagate_to_fixpoint() {
+            let new_events = &self.model.discrete.trail().events()[num_events as usize..];
+            let num_implied = new_events
+                .iter()
+                .filter(|ev| matches!(ev.cause, Cause::Inference(_)))
+                .count();
+            ProbeResult::Implied(num_implied)
+        } else {
+            ProbeResult::Conflict
+        };
+        self.restore_last();
+        result
+    }
+
+    /// Propagates all reasoners until no new inference is made.
+    /// Returns false if a contradiction was found, in which case the solver is left in an inconsistent state.
+    fn propagate_to_fixpoint(&mut self) -> bool {
+        loop {
+            let num_events_at_start = self.model.discrete.num_events();
+            if self.reasoners.sat.propagate(&mut self.model.discrete).is_err() {
+                return false;
+            }
+            for th in &mut self.reasoners.theories {
+                if th.process(&mut self.model.discrete).is_err() {
+                    return false;
+                }
+            }
+            if self.reasoners.theories.is_empty() || num_events_at_start == self.model.discrete.num_events() {
+                return true;
+            }
+        }
     }
 
     /// Determines the appropriate backtrack level for this clause.
@@ -250,6 +886,9 @@ impl Solver {
     /// Returns an error if there is no level at which the clause is not conflicting.
     #[must_use]
     fn add_conflicting_clause_and_backtrack(&mut self, expl: Disjunction) -> bool {
+        if let Some(sink) = &mut self.trace {
+            sink(TraceEvent::Conflict(expl.literals().to_vec()));
+        }
         if let Some(dl) = self.backtrack_level_for_clause(expl.literals()) {
             // backtrack
             self.restore(dl);
@@ -277,7 +916,11 @@ impl Solver {
             let sat_start = StartCycleCount::now();
             self.stats.per_module_propagation_loops[0] += 1;
 
-            match self.reasoners.sat.propagate(&mut self.model.discrete) {
+            let sat_result = self.reasoners.sat.propagate(&mut self.model.discrete);
+            // inferences are reported even if the propagation ends in a conflict, before they are undone.
+            // This includes the asserting literal of the clause learnt from the previous conflict, if any.
+            self.trace_propagations(num_events_at_start);
+            match sat_result {
                 Ok(()) => (),
                 Err(explanation) => {
                     let expl = self.model.discrete.refine_explanation(explanation, &mut self.reasoners);
@@ -301,11 +944,14 @@ impl Solver {
             let mut contradiction_found = false;
             for i in 0..self.reasoners.theories.len() {
                 let theory_propagation_start = StartCycleCount::now();
+                let num_events_before = self.model.discrete.num_events();
                 self.stats.per_module_propagation_loops[i + 1] += 1;
                 debug_assert!(!contradiction_found);
                 let th = &mut self.reasoners.theories[i];
 
-                match th.process(&mut self.model.discrete) {
+                let th_result = th.process(&mut self.model.discrete);
+                self.trace_propagations(num_events_before);
+                match th_result {
                     Ok(()) => (),
                     Err(contradiction) => {
                         contradiction_found = true;
@@ -352,6 +998,11 @@ impl Solver {
         true
     }
 
+    /// Statistics of all theories, in the order in which they were added to the solver.
+    pub fn theory_stats(&self) -> Vec<TheoryStats> {
+        self.reasoners.theories.iter().map(|th| th.stats()).collect()
+    }
+
     pub fn print_stats(&self) {
         println!("{}", self.stats);
         for (i, th) in self.reasoners.theories.iter().enumerate() {
@@ -391,10 +1042,12 @@ impl Backtrack for Solver {
     fn restore_last(&mut self) {
         assert!(self.decision_level > DecLvl::ROOT);
         self.restore(self.decision_level - 1);
-        self.decision_level -= 1;
     }
 
     fn restore(&mut self, saved_id: DecLvl) {
+        if let Some(sink) = &mut self.trace {
+            sink(TraceEvent::Backtrack(saved_id));
+        }
         self.decision_level = saved_id;
         self.model.restore(saved_id);
         self.brancher.restore(saved_id);
@@ -406,6 +1059,50 @@ impl Backtrack for Solver {
     }
 }
 
+/// Limits on the resources that a call to `Solver::solve_with_limit` may use.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SolveLimit {
+    /// Maximum number of conflicts encountered before giving up.
+    pub max_conflicts: Option<u64>,
+    /// Instant after which the search will be interrupted. This is only checked on restarts.
+    pub deadline: Option<Instant>,
+}
+
+/// Result of a resource-limited search.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SolveOutcome {
+    /// A solution was found and is available in the solver's model.
+    Sat,
+    /// The problem has no solution.
+    Unsat,
+    /// The limit was exceeded before a conclusion could be reached.
+    Unknown,
+}
+
+/// Outcome of `Solver::probe`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ProbeResult {
+    /// Setting the literal leads to a contradiction.
+    Conflict,
+    /// Setting the literal is consistent and results in this number of implied literals.
+    Implied(usize),
+}
+
+/// A step of the search, as reported to the sink installed with `Solver::set_trace`.
+#[derive(Clone, Debug)]
+pub enum TraceEvent {
+    /// The literal was set by the solver as a decision.
+    Decision(Bound),
+    /// The literal was inferred by the reasoner identified by `by`.
+    Propagation { lit: Bound, by: InferenceCause },
+    /// A conflict was detected and explained by this clause, that will be learnt if not violated at the root.
+    Conflict(Vec<Bound>),
+    /// The search is restarted from the root.
+    Restart,
+    /// The solver backtracked to this decision level.
+    Backtrack(DecLvl),
+}
+
 // TODO: is this needed
 #[derive(Copy, Clone)]
 pub struct Binding {
@@ -1,5 +1,5 @@
 use crate::solver::stats::Stats;
-use aries_backtrack::{Backtrack, DecLvl, Trail};
+use aries_backtrack::{Backtrack, DecLvl, ObsTrailCursor, Trail};
 use aries_collections::heap::IdxHeap;
 use aries_model::assignments::Assignment;
 use env_param::EnvParam;
@@ -8,36 +8,100 @@ use aries_collections::ref_store::RefMap;
 use aries_model::int_model::IntDomain;
 
 use aries_model::bounds::Bound;
-use aries_model::lang::{IntCst, VarRef};
+use aries_model::int_model::domains::Event;
+use aries_model::lang::{BVar, IntCst, Type, VarRef};
 use aries_model::Model;
 use itertools::Itertools;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
 pub static PREFER_MIN_VALUE: EnvParam<bool> = EnvParam::new("ARIES_SMT_PREFER_MIN_VALUE", "true");
+pub static PHASE_SAVING: EnvParam<bool> = EnvParam::new("ARIES_SMT_PHASE_SAVING", "true");
 pub static INITIALLY_ALLOWED_CONFLICTS: EnvParam<u64> = EnvParam::new("ARIES_SMT_INITIALLY_ALLOWED_CONFLICT", "100");
 pub static INCREASE_RATIO_FOR_ALLOWED_CONFLICTS: EnvParam<f32> =
     EnvParam::new("ARIES_SMT_INCREASE_RATIO_FOR_ALLOWED_CONFLICTS", "1.5");
+pub static SEED: EnvParam<u64> = EnvParam::new("ARIES_SMT_SEED", "0");
 
 pub struct BranchingParams {
     pub prefer_min_value: bool,
+    /// If true, a boolean variable will be branched on the last value it took.
+    pub phase_saving: bool,
+    pub restart_strategy: RestartStrategy,
+    pub int_branching: IntBranching,
     pub allowed_conflicts: u64,
     pub increase_ratio_for_allowed_conflicts: f32,
+    /// Seed of the pseudo-random generator used to break ties between variables of equal activity.
+    /// If 0, ties are broken by order of declaration of the variables.
+    pub seed: u64,
 }
 
 impl Default for BranchingParams {
     fn default() -> Self {
         BranchingParams {
             prefer_min_value: *PREFER_MIN_VALUE.get(),
+            phase_saving: *PHASE_SAVING.get(),
+            restart_strategy: RestartStrategy::Geometric,
+            int_branching: IntBranching::Value,
             allowed_conflicts: *INITIALLY_ALLOWED_CONFLICTS.get(),
             increase_ratio_for_allowed_conflicts: *INCREASE_RATIO_FOR_ALLOWED_CONFLICTS.get(),
+            seed: *SEED.get(),
         }
     }
 }
 
+/// Defines the number of conflicts allowed between two restarts.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RestartStrategy {
+    /// Starts with `allowed_conflicts` and increases it by `increase_ratio_for_allowed_conflicts` after each restart.
+    Geometric,
+    /// The number of conflicts allowed before the i-th restart is `unit * luby(i)`.
+    Luby { unit: u64 },
+}
+
+/// Kind of decisions made on a non-boolean variable, in the absence of a preferred value.
+///
+/// Note that boolean and integer variables share the same activity-based queue, the most active
+/// variable being selected first whatever its type.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum IntBranching {
+    /// Assigns the variable to its minimal (resp. maximal) value, depending on `prefer_min_value`.
+    Value,
+    /// Splits the domain of the variable in two halves, the lower half (resp. upper half) being tried first
+    /// depending on `prefer_min_value`.
+    Split,
+}
+
+/// The Luby sequence `1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8, ...` where `i` starts at 1.
+pub fn luby(i: u64) -> u64 {
+    debug_assert!(i >= 1);
+    // find the smallest k such that i <= 2^k - 1
+    let mut k = 1;
+    while (1u64 << k) - 1 < i {
+        k += 1;
+    }
+    if (1u64 << k) - 1 == i {
+        1u64 << (k - 1)
+    } else {
+        luby(i - (1u64 << (k - 1)) + 1)
+    }
+}
+
 pub struct Brancher {
     pub params: BranchingParams,
+    /// Activity-based queue of all variables, boolean and integer alike.
     heap: VarSelect,
+    /// If non-empty, the first unset variable in this list is selected for the next decision,
+    /// before considering the activity-based queue.
+    static_order: Vec<BVar>,
     default_assignment: DefaultValues,
+    /// Last value taken by each boolean variable. Unlike the rest of the brancher's state,
+    /// saved phases are not undone on backtrack.
+    saved_phases: RefMap<BVar, bool>,
+    /// Cursor on the model's events, used to update the saved phases.
+    model_events: ObsTrailCursor<Event>,
     conflicts_at_last_restart: u64,
+    /// Number of restarts triggered by the brancher.
+    num_restarts: u64,
     num_processed_var: usize,
 }
 
@@ -46,6 +110,10 @@ struct DefaultValues {
     bools: RefMap<VarRef, IntCst>,
 }
 
+/// A decision proposed by the brancher.
+///
+/// A `SetLiteral` decision sets a bound on a variable, that is either a boolean variable (e.g. `x >= 1`)
+/// or an integer variable (e.g. `x <= mid` when splitting its domain).
 pub enum Decision {
     SetLiteral(Bound),
     Restart,
@@ -53,11 +121,18 @@ pub enum Decision {
 
 impl Brancher {
     pub fn new() -> Self {
+        let params = BranchingParams::default();
+        let mut heap = VarSelect::new(Default::default());
+        heap.set_seed(params.seed);
         Brancher {
-            params: Default::default(),
-            heap: VarSelect::new(Default::default()),
+            params,
+            heap,
+            static_order: Vec::new(),
             default_assignment: DefaultValues::default(),
+            saved_phases: Default::default(),
+            model_events: ObsTrailCursor::new(),
             conflicts_at_last_restart: 0,
+            num_restarts: 0,
             num_processed_var: 0,
         }
     }
@@ -82,51 +157,81 @@ impl Brancher {
     /// Returns `None` if no decision is left to be made.
     pub fn next_decision(&mut self, stats: &Stats, model: &Model) -> Option<Decision> {
         self.import_vars(model);
+        self.save_phases(model);
+
+        // first unset variable in the user-provided order, if any
+        let static_next = self
+            .static_order
+            .iter()
+            .map(|&v| VarRef::from(v))
+            .find(|&v| !model.discrete.domains.is_bound(v));
 
         let mut popper = self.heap.extractor();
 
         // extract the highest priority variable that is not set yet.
-        let next_unset = loop {
-            // we are only allowed to remove from the queue variables that are bound.
-            // so peek at the next one an only remove it if it was
-            match popper.peek() {
-                Some(v) => {
-                    if model.discrete.domains.is_bound(v) {
-                        // already bound, drop the peeked variable before proceeding to next
-                        popper.pop().unwrap();
-                    } else {
-                        // not set, select for decision
-                        break Some(v);
+        let next_unset = if static_next.is_some() {
+            static_next
+        } else {
+            loop {
+                // we are only allowed to remove from the queue variables that are bound.
+                // so peek at the next one an only remove it if it was
+                match popper.peek() {
+                    Some(v) => {
+                        if model.discrete.domains.is_bound(v) {
+                            // already bound, drop the peeked variable before proceeding to next
+                            popper.pop().unwrap();
+                        } else {
+                            // not set, select for decision
+                            break Some(v);
+                        }
+                    }
+                    None => {
+                        // no variables left in queue
+                        break None;
                     }
-                }
-                None => {
-                    // no variables left in queue
-                    break None;
                 }
             }
         };
         if let Some(v) = next_unset {
-            if stats.num_conflicts - self.conflicts_at_last_restart >= self.params.allowed_conflicts {
+            if stats.num_conflicts - self.conflicts_at_last_restart >= self.allowed_conflicts() {
                 // we have exceeded the number of allowed conflict, time for a restart
                 self.conflicts_at_last_restart = stats.num_conflicts;
-                // increase the number of allowed conflicts
-                self.params.allowed_conflicts =
-                    (self.params.allowed_conflicts as f32 * self.params.increase_ratio_for_allowed_conflicts) as u64;
+                self.num_restarts += 1;
+                if self.params.restart_strategy == RestartStrategy::Geometric {
+                    // increase the number of allowed conflicts
+                    self.params.allowed_conflicts = (self.params.allowed_conflicts as f32
+                        * self.params.increase_ratio_for_allowed_conflicts)
+                        as u64;
+                }
 
                 Some(Decision::Restart)
             } else {
                 // determine value for literal:
                 // - first from per-variable preferred assignments
+                // - then from the last value taken by a boolean variable (phase saving)
                 // - otherwise from the preferred value for boolean variables
                 let IntDomain { lb, ub } = model.var_domain(v);
                 debug_assert!(lb < ub);
 
-                let value = self
+                let preferred = self
                     .default_assignment
                     .bools
                     .get(v)
                     .copied()
-                    .unwrap_or(if self.params.prefer_min_value { lb } else { ub });
+                    .or_else(|| self.saved_phases.get(BVar::new(v)).map(|&phase| phase as IntCst));
+
+                if preferred.is_none() && self.params.int_branching == IntBranching::Split && ub - lb > 1 {
+                    // split the domain in two halves, mid is in [lb, ub-1]
+                    let mid = lb + (ub - lb) / 2;
+                    let literal = if self.params.prefer_min_value {
+                        Bound::leq(v, mid)
+                    } else {
+                        Bound::gt(v, mid)
+                    };
+                    return Some(Decision::SetLiteral(literal));
+                }
+
+                let value = preferred.unwrap_or(if self.params.prefer_min_value { lb } else { ub });
 
                 let literal = if value < lb || value > ub {
                     if self.params.prefer_min_value {
@@ -151,6 +256,65 @@ impl Brancher {
         }
     }
 
+    /// Sets the seed used for breaking ties between variables. Two runs with the same seed make the same decisions.
+    /// Only affects the variables that are not yet known to the brancher.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.params.seed = seed;
+        self.heap.set_seed(seed);
+    }
+
+    /// Imposes a static order on the variables to branch on: as long as one of the variables in `order` is unset,
+    /// the first one is selected for the next decision. The activity-based heuristic is used for all other variables.
+    pub fn set_static_order(&mut self, order: Vec<BVar>) {
+        self.static_order = order;
+    }
+
+    /// Removes any static order, restoring the pure activity-based selection.
+    pub fn clear_static_order(&mut self) {
+        self.static_order.clear();
+    }
+
+    /// Adds a constant `boost` to the activity of the given variables, so that they tend to be selected
+    /// before other variables (e.g. the presence of chronicles in planning) while still following the
+    /// conflict-driven activity. A later call for the same variable replaces its previous boost.
+    ///
+    /// The boost is expressed in the same unit as the activity: a bump (see `bump_activity`) early in the search
+    /// adds 1 to the activity of a variable. Since the increment of bumps grows with the number of conflicts,
+    /// the boost weighs less and less compared to the activity of variables involved in recent conflicts.
+    pub fn set_priority_group(&mut self, vars: &[BVar], boost: f64) {
+        for &var in vars {
+            self.heap.set_boost(VarRef::from(var), boost as f32);
+        }
+    }
+
+    /// Records the value of all boolean variables that were bound since the last call.
+    /// Integer variables whose domain happens to be `[0, 1]` are not considered.
+    fn save_phases(&mut self, model: &Model) {
+        if !self.params.phase_saving {
+            return;
+        }
+        while let Some(ev) = self.model_events.pop(model.discrete.trail()) {
+            let var = ev.affected_bound.variable();
+            // variables declared without a type are booleans
+            if !matches!(model.types.get(var), None | Some(Type::Bool)) {
+                continue;
+            }
+            match model.discrete.domain_of(var) {
+                (0, 0) => self.saved_phases.insert(BVar::new(var), false),
+                (1, 1) => self.saved_phases.insert(BVar::new(var), true),
+                _ => {}
+            }
+        }
+    }
+
+    /// Number of conflicts allowed before the next restart.
+    fn allowed_conflicts(&self) -> u64 {
+        match self.params.restart_strategy {
+            RestartStrategy::Geometric => self.params.allowed_conflicts,
+            RestartStrategy::Luby { unit } => unit * luby(self.num_restarts + 1),
+        }
+    }
+
     pub fn set_default_value(&mut self, var: VarRef, val: IntCst) {
         self.default_assignment.bools.insert(var, val);
     }
@@ -165,7 +329,10 @@ impl Brancher {
     /// Increase the activity of the variable and perform an reordering in the queue.
     /// The activity is then used to select the next variable.
     pub fn bump_activity(&mut self, bvar: VarRef) {
-        self.heap.var_bump_activity(bvar);
+        // the variable might have been created after the last decision and not be imported yet
+        if self.heap.is_declared(bvar) {
+            self.heap.var_bump_activity(bvar);
+        }
     }
 }
 
@@ -189,9 +356,30 @@ impl Default for BoolHeuristicParams {
 }
 
 /// Heuristic value associated to a variable.
-#[derive(Copy, Clone, PartialEq, PartialOrd)]
+/// Variables are ordered by the sum of their activity and boost.
+#[derive(Copy, Clone)]
 struct BoolVarHeuristicValue {
     activity: f32,
+    /// Constant added to the activity, see `Brancher::set_priority_group`
+    boost: f32,
+}
+
+impl BoolVarHeuristicValue {
+    fn score(&self) -> f32 {
+        self.activity + self.boost
+    }
+}
+
+impl PartialEq for BoolVarHeuristicValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.score() == other.score()
+    }
+}
+
+impl PartialOrd for BoolVarHeuristicValue {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.score().partial_cmp(&other.score())
+    }
 }
 
 type Heap = IdxHeap<VarRef, BoolVarHeuristicValue>;
@@ -212,6 +400,10 @@ pub struct VarSelect {
     /// Stage in which each variable appears.
     stages: RefMap<VarRef, u8>,
     trail: Trail<HeapEvent>,
+    /// Boost of the variables given to `set_boost`, including the ones that are not declared yet.
+    boosts: RefMap<VarRef, f32>,
+    /// If set, used to slightly perturb the initial activity of variables, to break ties randomly.
+    rng: Option<StdRng>,
 }
 
 impl VarSelect {
@@ -221,9 +413,21 @@ impl VarSelect {
             heaps: Vec::new(),
             stages: Default::default(),
             trail: Trail::default(),
+            boosts: Default::default(),
+            rng: None,
         }
     }
 
+    /// Sets the seed for breaking ties between variables of the same activity.
+    /// A seed of 0 disables randomization: ties are broken by order of declaration.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = if seed == 0 {
+            None
+        } else {
+            Some(StdRng::seed_from_u64(seed))
+        };
+    }
+
     pub fn is_declared(&self, v: VarRef) -> bool {
         self.stages.contains(v)
     }
@@ -233,9 +437,13 @@ impl VarSelect {
     /// Variables with the lowest stage are considered first.
     pub fn add_variable(&mut self, v: VarRef, stage: u8) {
         debug_assert!(!self.is_declared(v));
-        let hvalue = BoolVarHeuristicValue {
-            activity: self.params.var_inc,
+        let activity = match &mut self.rng {
+            // perturbation small enough to never override an activity bump
+            Some(rng) => self.params.var_inc * (1.0 + rng.gen_range(0.0..1e-3)),
+            None => self.params.var_inc,
         };
+        let boost = self.boosts.get(v).copied().unwrap_or(0.0);
+        let hvalue = BoolVarHeuristicValue { activity, boost };
         let priority = stage as usize;
         while priority >= self.heaps.len() {
             self.heaps.push(IdxHeap::new());
@@ -281,6 +489,15 @@ impl VarSelect {
         }
     }
 
+    /// Sets the constant added to the activity of the variable when ordering the queue.
+    /// If the variable is not declared yet, the boost will be applied when it is.
+    pub fn set_boost(&mut self, var: VarRef, boost: f32) {
+        self.boosts.insert(var, boost);
+        if self.is_declared(var) {
+            self.heap_of(var).change_priority(var, |p| p.boost = boost);
+        }
+    }
+
     pub fn decay_activities(&mut self) {
         self.params.var_inc /= self.params.var_decay;
     }
@@ -289,7 +506,13 @@ impl VarSelect {
         // here we scale the activity of all variables, to avoid overflowing
         // this can not change the relative order in the heap, since activities are scaled by the same amount.
         for heap in &mut self.heaps {
-            heap.change_all_priorities_in_place(|p| p.activity *= 1e-30_f32);
+            heap.change_all_priorities_in_place(|p| {
+                p.activity *= 1e-30_f32;
+                p.boost *= 1e-30_f32;
+            });
+        }
+        for boost in self.boosts.values_mut() {
+            *boost *= 1e-30_f32;
         }
 
         self.params.var_inc *= 1e-30_f32;
@@ -359,3 +582,212 @@ impl Backtrack for Brancher {
         self.heap.restore_last()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn luby_sequence() {
+        let expected = [1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8, 1];
+        let computed: Vec<u64> = (1..=expected.len() as u64).map(luby).collect();
+        assert_eq!(computed, expected);
+    }
+
+    #[test]
+    fn luby_restarts() {
+        let mut brancher = Brancher::new();
+        brancher.params.restart_strategy = RestartStrategy::Luby { unit: 32 };
+        let thresholds: Vec<u64> = (0..7)
+            .map(|_| {
+                let allowed = brancher.allowed_conflicts();
+                brancher.num_restarts += 1;
+                allowed
+            })
+            .collect();
+        assert_eq!(thresholds, vec![32, 32, 64, 32, 32, 64, 128]);
+    }
+
+    #[test]
+    fn phase_saving() {
+        let mut model = Model::new();
+        let a = model.new_bvar("a");
+        let stats = Stats::new();
+        let mut brancher = Brancher::new();
+        brancher.params.phase_saving = true;
+        brancher.params.prefer_min_value = true;
+
+        model.save_state();
+        brancher.save_state();
+        model.discrete.decide(a.true_lit()).unwrap();
+        assert!(brancher.next_decision(&stats, &model).is_none());
+
+        // a is unset, it should be branched on its last value even though the min value is preferred
+        model.restore_last();
+        brancher.restore_last();
+        match brancher.next_decision(&stats, &model) {
+            Some(Decision::SetLiteral(lit)) => assert_eq!(lit, a.true_lit()),
+            _ => panic!("Expected a decision on a"),
+        }
+    }
+
+    #[test]
+    fn phase_saving_ignores_ints() {
+        let mut model = Model::new();
+        let x = model.new_ivar(0, 1, "x");
+        let y = model.new_ivar(0, 10, "y");
+        let stats = Stats::new();
+        let mut brancher = Brancher::new();
+        brancher.params.phase_saving = true;
+        brancher.params.prefer_min_value = true;
+        brancher.params.int_branching = IntBranching::Split;
+
+        model.save_state();
+        brancher.save_state();
+        model.discrete.decide(Bound::geq(x, 1)).unwrap();
+        model.discrete.decide(Bound::leq(y, 1)).unwrap();
+        model.discrete.decide(Bound::geq(y, 1)).unwrap();
+        assert!(brancher.next_decision(&stats, &model).is_none());
+
+        // integer variables are branched on as if they were never bound
+        model.restore_last();
+        brancher.restore_last();
+        let mut decisions = Vec::new();
+        while let Some(Decision::SetLiteral(lit)) = brancher.next_decision(&stats, &model) {
+            decisions.push(lit);
+            if lit.variable() == y.into() {
+                break;
+            }
+            model.discrete.decide(lit).unwrap();
+        }
+        assert!(decisions.contains(&Bound::leq(x, 0)));
+        assert!(decisions.contains(&Bound::leq(y, 5)));
+    }
+
+    #[test]
+    fn int_branching() {
+        let mut model = Model::new();
+        let x = model.new_ivar(0, 10, "x");
+        let stats = Stats::new();
+        let mut brancher = Brancher::new();
+        brancher.params.prefer_min_value = true;
+
+        brancher.params.int_branching = IntBranching::Value;
+        match brancher.next_decision(&stats, &model) {
+            Some(Decision::SetLiteral(lit)) => assert_eq!(lit, Bound::leq(x, 0)),
+            _ => panic!("Expected a decision on x"),
+        }
+
+        brancher.params.int_branching = IntBranching::Split;
+        match brancher.next_decision(&stats, &model) {
+            Some(Decision::SetLiteral(lit)) => assert_eq!(lit, Bound::leq(x, 5)),
+            _ => panic!("Expected a decision on x"),
+        }
+        brancher.params.prefer_min_value = false;
+        match brancher.next_decision(&stats, &model) {
+            Some(Decision::SetLiteral(lit)) => assert_eq!(lit, Bound::gt(x, 5)),
+            _ => panic!("Expected a decision on x"),
+        }
+    }
+
+    #[test]
+    fn priority_group() {
+        let mut model = Model::new();
+        let a = model.new_bvar("a");
+        let b = model.new_bvar("b");
+        let c = model.new_bvar("c");
+        let stats = Stats::new();
+        let decision_var = |brancher: &mut Brancher| match brancher.next_decision(&stats, &model) {
+            Some(Decision::SetLiteral(lit)) => lit.variable(),
+            _ => panic!("Expected a decision"),
+        };
+
+        // all variables have the same base activity,
+        // with a boost set before the variables are known to the brancher
+        let mut brancher = Brancher::new();
+        brancher.set_priority_group(&[b], 2.0);
+        assert_eq!(decision_var(&mut brancher), b.into());
+
+        // boost of a variable already in the queue
+        brancher.set_priority_group(&[c], 3.0);
+        assert_eq!(decision_var(&mut brancher), c.into());
+
+        // conflict-driven activity still applies on top of the boost
+        for _ in 0..5 {
+            brancher.bump_activity(a.into());
+        }
+        assert_eq!(decision_var(&mut brancher), a.into());
+    }
+
+    #[test]
+    fn priority_group_rescaled() {
+        let mut model = Model::new();
+        let a = model.new_bvar("a");
+        let stats = Stats::new();
+        let mut brancher = Brancher::new();
+        brancher.import_vars(&model);
+
+        // boost of a variable unknown to the brancher when the activities are rescaled
+        let b = model.new_bvar("b");
+        brancher.set_priority_group(&[b], 2.0);
+        brancher.heap.var_rescale_activity();
+        brancher.import_vars(&model);
+
+        // after rescaling, a few bumps still outweigh the boost
+        for _ in 0..5 {
+            brancher.bump_activity(a.into());
+        }
+        match brancher.next_decision(&stats, &model) {
+            Some(Decision::SetLiteral(lit)) => assert_eq!(lit.variable(), a.into()),
+            _ => panic!("Expected a decision"),
+        }
+    }
+
+    #[test]
+    fn static_order() {
+        let mut model = Model::new();
+        let a = model.new_bvar("a");
+        let b = model.new_bvar("b");
+        let stats = Stats::new();
+        let mut brancher = Brancher::new();
+        brancher.import_vars(&model);
+        for _ in 0..10 {
+            brancher.bump_activity(a.into());
+        }
+        let decision_var = |brancher: &mut Brancher| match brancher.next_decision(&stats, &model) {
+            Some(Decision::SetLiteral(lit)) => lit.variable(),
+            _ => panic!("Expected a decision"),
+        };
+        assert_eq!(decision_var(&mut brancher), a.into());
+
+        brancher.set_static_order(vec![b, a]);
+        assert_eq!(decision_var(&mut brancher), b.into());
+
+        brancher.clear_static_order();
+        assert_eq!(decision_var(&mut brancher), a.into());
+    }
+
+    #[test]
+    fn seeded_tie_breaking() {
+        let mut model = Model::new();
+        for i in 0..20 {
+            model.new_bvar(format!("v{}", i));
+        }
+        let stats = Stats::new();
+        let decisions = |seed: u64| {
+            let mut model = model.clone();
+            let mut brancher = Brancher::new();
+            brancher.set_seed(seed);
+            let mut decisions = Vec::new();
+            while let Some(Decision::SetLiteral(lit)) = brancher.next_decision(&stats, &model) {
+                model.discrete.decide(lit).unwrap();
+                decisions.push(lit);
+            }
+            decisions
+        };
+        assert_eq!(decisions(0).len(), 20);
+        assert_eq!(decisions(0), decisions(0));
+        assert_eq!(decisions(42), decisions(42));
+        assert_ne!(decisions(42), decisions(0));
+    }
+}
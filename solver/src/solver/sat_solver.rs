@@ -59,6 +59,10 @@ pub struct SearchParams {
     db_expansion_ratio: f64,
     /// ratio by which we will increase the number of allowed conflict before doing a new DB increase
     increase_ratio_of_conflicts_before_db_expansion: f64,
+    /// If set, the learnt clauses are reduced on restarts when there are more than this number of them.
+    max_learnt: Option<usize>,
+    /// Fraction of the removable learnt clauses that are deleted on a reduction triggered by `max_learnt`.
+    reduction_factor: f64,
 }
 impl Default for SearchParams {
     fn default() -> Self {
@@ -67,6 +71,8 @@ impl Default for SearchParams {
             init_learnt_base: 1000_f64,
             db_expansion_ratio: 1.1_f64,
             increase_ratio_of_conflicts_before_db_expansion: 1.5_f64,
+            max_learnt: None,
+            reduction_factor: 0.5_f64,
         }
     }
 }
@@ -151,6 +157,35 @@ impl SatSolver {
         self.add_clause_impl(clause.into(), true);
     }
 
+    /// Bounds the number of learnt clauses in the database: on each restart and as long as there are more than
+    /// `max_learnt` of them, the `reduction_factor` (between 0 and 1) least active ones are deleted.
+    /// Clauses that are needed to explain the current value of a literal are always kept.
+    pub fn set_clause_db_policy(&mut self, max_learnt: usize, reduction_factor: f32) {
+        assert!(reduction_factor > 0.0 && reduction_factor <= 1.0);
+        self.params.max_learnt = Some(max_learnt);
+        self.params.reduction_factor = reduction_factor as f64;
+    }
+
+    /// Applies the policy set with `set_clause_db_policy`. Intended to be called on restarts.
+    pub fn reduce_learnt_clauses(&mut self) {
+        if let Some(max_learnt) = self.params.max_learnt {
+            while self.clauses.num_learnt() > max_learnt {
+                let before = self.clauses.num_learnt();
+                self.reduce_db(self.params.reduction_factor);
+                if self.clauses.num_learnt() == before {
+                    break; // all remaining clauses are locked
+                }
+            }
+        }
+    }
+
+    /// Returns the literals of all learnt clauses currently in the database.
+    pub fn learnt_clauses(&self) -> impl Iterator<Item = Vec<Bound>> + '_ {
+        self.clauses
+            .learnt_clauses()
+            .map(move |cl| self.clauses[cl].literals().collect())
+    }
+
     fn add_clause_impl(&mut self, clause: Disjunction, learnt: bool) -> ClauseId {
         let cl_id = self.clauses.add_clause(Clause::new(clause), learnt);
         self.pending_clauses.push_back(cl_id);
@@ -471,16 +506,22 @@ impl SatSolver {
                         * self.params.increase_ratio_of_conflicts_before_db_expansion) as u64;
             } else {
                 // reduce the database size
-                let locks = &self.locks;
-                let watches = &mut self.watches;
-                let mut remove_watch = |clause: ClauseId, watched: Bound| {
-                    watches.remove_watch(clause, watched);
-                };
-                self.clauses.reduce_db(|cl| locks.contains(cl), &mut remove_watch);
+                self.reduce_db(0.5);
             }
         }
     }
 
+    /// Removes the least active fraction of the learnt clauses that are not locked.
+    fn reduce_db(&mut self, fraction_removed: f64) {
+        let locks = &self.locks;
+        let watches = &mut self.watches;
+        let mut remove_watch = |clause: ClauseId, watched: Bound| {
+            watches.remove_watch(clause, watched);
+        };
+        self.clauses
+            .reduce_db(|cl| locks.contains(cl), fraction_removed, &mut remove_watch);
+    }
+
     pub fn bind(
         &mut self,
         reif: Bound,
@@ -1,5 +1,5 @@
 use crate::solver::{Binding, BindingResult};
-use crate::{Contradiction, Theory};
+use crate::{Contradiction, Theory, TheoryStats};
 use aries_backtrack::ObsTrail;
 use aries_backtrack::{Backtrack, DecLvl};
 use aries_model::bounds::Bound;
@@ -34,6 +34,10 @@ impl TheorySolver {
     pub fn print_stats(&self) {
         self.theory.print_stats()
     }
+
+    pub fn stats(&self) -> TheoryStats {
+        self.theory.stats()
+    }
 }
 
 impl Backtrack for TheorySolver {
@@ -1,7 +1,7 @@
 use aries_model::lang::IntCst;
 
 pub struct RangeSet {
-    values: Vec<i32>,
+    values: Vec<IntCst>,
 }
 
 impl RangeSet {
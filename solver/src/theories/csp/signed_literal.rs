@@ -7,7 +7,7 @@ pub struct Bounds {
 }
 
 impl From<(IntCst, IntCst)> for Bounds {
-    fn from((lb, ub): (i32, i32)) -> Self {
+    fn from((lb, ub): (IntCst, IntCst)) -> Self {
         Bounds { lb, ub }
     }
 }
@@ -15,6 +15,14 @@ fn propagate_bounds(mut stn: STN, updates: &[Bound]) {
     }
 }
 
+/// Decides all updates before running a single propagation, resulting in many pending edge activations.
+fn propagate_bounds_batched(mut stn: STN, updates: &[Bound]) {
+    for &b in updates {
+        stn.model.discrete.decide(b).unwrap();
+    }
+    stn.propagate_all().unwrap();
+}
+
 fn left_right_linear_graph() -> (GraphName, STN, Vec<Bound>) {
     let mut stn = STN::new();
 
@@ -102,6 +110,17 @@ pub fn criterion_benchmark(c: &mut Criterion) {
         edge_activations_random_graph(),
     ];
 
+    for (name, stn, bounds) in &graphs {
+        let mut bellman_ford = stn.clone();
+        bellman_ford.set_bellman_ford_threshold(Some(0));
+        c.bench_function(&format!("stn-{}-batched-cesta", name), |b| {
+            b.iter(|| propagate_bounds_batched(black_box(stn.clone()), black_box(bounds)))
+        });
+        c.bench_function(&format!("stn-{}-batched-bellman-ford", name), |b| {
+            b.iter(|| propagate_bounds_batched(black_box(bellman_ford.clone()), black_box(bounds)))
+        });
+    }
+
     for (name, stn, bounds) in graphs {
         c.bench_function(&format!("stn-{}-lb-ub", name), |b| {
             b.iter(|| propagate_bounds(black_box(stn.clone()), black_box(&bounds)))
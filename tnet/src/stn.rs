@@ -117,6 +117,18 @@ impl Edge {
     }
 }
 
+impl std::fmt::Display for Edge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} - {} <= {}",
+            usize::from(self.target),
+            usize::from(self.source),
+            self.weight
+        )
+    }
+}
+
 #[derive(Clone)]
 struct Constraint {
     /// True if the constraint active (participates in propagation)
@@ -128,6 +140,9 @@ struct Constraint {
     /// A set of enablers for this constraint.
     /// The edge becomes active once one of its enablers becomes true
     enablers: Vec<Bound>,
+    /// True if the edge was explicitly inserted and not hidden.
+    /// False if the constraint only exists as the negation of an inserted edge.
+    inserted: bool,
 }
 impl Constraint {
     pub fn new(active: bool, edge: Edge) -> Constraint {
@@ -136,6 +151,7 @@ impl Constraint {
             edge,
             always_active: false,
             enablers: Vec::new(),
+            inserted: false,
         }
     }
 }
@@ -174,6 +190,10 @@ struct ConstraintDB {
     constraints: RefVec<EdgeID, Constraint>,
     /// Maps each canonical edge to its location
     lookup: HashMap<Edge, u32>,
+    /// Associates each pair of timepoints `(source, target)` to all edges from `source` to `target`
+    edges_between: HashMap<(Timepoint, Timepoint), Vec<EdgeID>>,
+    /// User provided tags, indexed by the base_id of the tagged edge.
+    tags: HashMap<u32, u64>,
     watches: Watches<EdgeID>,
 }
 impl ConstraintDB {
@@ -181,6 +201,8 @@ impl ConstraintDB {
         ConstraintDB {
             constraints: Default::default(),
             lookup: HashMap::new(),
+            edges_between: HashMap::new(),
+            tags: HashMap::new(),
             watches: Default::default(),
         }
     }
@@ -198,6 +220,21 @@ impl ConstraintDB {
         }
     }
 
+    /// Returns an always active edge with the same source and target as `edge` and a smaller or equal weight, if any.
+    fn dominating(&self, edge: &Edge) -> Option<EdgeID> {
+        self.edges_between
+            .get(&(edge.source, edge.target))?
+            .iter()
+            .copied()
+            .find(|&id| self[id].always_active && self[id].edge.weight <= edge.weight)
+    }
+
+    /// Returns true if the edge is dominated by an always active edge of the DB, i.e., an edge with the same
+    /// source and target and a smaller or equal weight. Such an edge is redundant for propagation.
+    pub fn dominated(&self, edge: &Edge) -> bool {
+        self.dominating(edge).is_some()
+    }
+
     /// Adds a new edge and return a pair (created, edge_id) where:
     ///  - created is false if NO new edge was inserted (it was merge with an identical edge already in the DB)
     ///  - edge_id is the id of the edge
@@ -210,6 +247,7 @@ impl ConstraintDB {
             Some(id) => {
                 // edge already exists in the DB, return its id and say it wasn't created
                 debug_assert_eq!(self[id].edge, edge);
+                self[id].inserted |= !hidden;
                 (false, id)
             }
             None => {
@@ -220,9 +258,14 @@ impl ConstraintDB {
                 let id1 = self.constraints.push(pair.base);
                 let id2 = self.constraints.push(pair.negated);
                 self.lookup.insert(base, id1.base_id());
+                for &id in &[id1, id2] {
+                    let e = self[id].edge;
+                    self.edges_between.entry((e.source, e.target)).or_default().push(id);
+                }
                 debug_assert_eq!(id1.base_id(), id2.base_id());
                 let edge_id = if edge.is_negated() { id2 } else { id1 };
                 debug_assert_eq!(self[edge_id].edge, edge);
+                self[edge_id].inserted = !hidden;
                 (true, edge_id)
             }
         }
@@ -231,9 +274,29 @@ impl ConstraintDB {
     /// Removes the last created ConstraintPair in the DB. Note that this will remove the last edge that was
     /// push THAT WAS NOT UNIFIED with an existing edge (i.e. edge_push returned : (true, _)).
     pub fn pop_last(&mut self) {
-        self.constraints.pop();
+        if let Some(c) = self.constraints.pop() {
+            self.forget_edge(&c.edge);
+        }
         if let Some(c) = self.constraints.pop() {
             self.lookup.remove(&c.edge);
+            self.forget_edge(&c.edge);
+        }
+        self.tags.remove(&(self.constraints.len() as u32 >> 1));
+    }
+
+    /// Associates a tag to the edge, unless it (or its negation) already has one.
+    pub fn set_tag(&mut self, id: EdgeID, tag: u64) {
+        self.tags.entry(id.base_id()).or_insert(tag);
+    }
+
+    pub fn tag(&self, id: EdgeID) -> Option<u64> {
+        self.tags.get(&id.base_id()).copied()
+    }
+
+    /// Removes the last edge recorded between the source and target of `edge`.
+    fn forget_edge(&mut self, edge: &Edge) {
+        if let Some(ids) = self.edges_between.get_mut(&(edge.source, edge.target)) {
+            ids.pop();
         }
     }
 
@@ -274,6 +337,10 @@ struct Distance {
 struct Stats {
     num_propagations: u64,
     distance_updates: u64,
+    num_bindings: u64,
+    num_conflicts: u64,
+    /// Largest size reached by the internal propagation queue.
+    max_queue_len: usize,
 }
 
 /// STN that supports:
@@ -292,6 +359,7 @@ struct Stats {
 /// of the caller to ensure that no overflow occurs when adding an absolute and relative time,
 /// either by the choice of an appropriate type (e.g. saturating add) or by the choice of
 /// appropriate initial bounds.
+/// For debugging, the checked arithmetic mode (see `set_checked_arithmetic`) reports any such overflow.
 #[derive(Clone)]
 pub struct IncSTN {
     constraints: ConstraintDB,
@@ -311,6 +379,13 @@ pub struct IncSTN {
     explanation: Vec<EdgeID>,
     /// Internal data structure used by the `propagate` method to keep track of pending work.
     internal_propagate_queue: VecDeque<VarBound>,
+    /// If set, pending activations are propagated all at once with a Bellman-Ford algorithm
+    /// (instead of one at a time with [Cesta96]) when their number exceeds this threshold.
+    bellman_ford_threshold: Option<usize>,
+    /// If true, an overflow when computing a new bound results in a panic.
+    checked_arithmetic: bool,
+    /// If true, always active edges that are dominated by another always active edge are not recorded.
+    skip_dominated_edges: bool,
 }
 
 #[derive(Copy, Clone)]
@@ -341,6 +416,43 @@ impl IncSTN {
             model_events: ObsTrailCursor::new(),
             explanation: vec![],
             internal_propagate_queue: Default::default(),
+            bellman_ford_threshold: None,
+            checked_arithmetic: false,
+            skip_dominated_edges: false,
+        }
+    }
+
+    /// Sets the number of pending activations above which all pending edges are propagated at once,
+    /// with a Bellman-Ford algorithm. If `None` (the default), edges are always propagated one by one.
+    ///
+    /// Both propagation modes result in the same bounds and detect the same inconsistencies.
+    pub fn set_bellman_ford_threshold(&mut self, threshold: Option<usize>) {
+        self.bellman_ford_threshold = threshold;
+    }
+
+    /// If set, the bounds derived from edges are computed with checked arithmetic and any overflow
+    /// results in a panic identifying the faulty edge, regardless of the build profile.
+    /// Otherwise (the default), it is the responsibility of the caller to avoid overflows.
+    ///
+    /// This is intended for debugging: an overflowing bound may otherwise mask a negative cycle.
+    pub fn set_checked_arithmetic(&mut self, checked: bool) {
+        self.checked_arithmetic = checked;
+    }
+
+    /// If set, an always active edge is not recorded if it is dominated by an always active edge
+    /// with the same source and target and a smaller or equal weight. The id of the dominating edge
+    /// is returned instead.
+    pub fn set_skip_dominated_edges(&mut self, skip: bool) {
+        self.skip_dominated_edges = skip;
+    }
+
+    /// Returns the bound resulting from the propagation of the bound `value` through the `edge`.
+    #[inline]
+    fn shifted(&self, value: BoundValue, weight: BoundValueAdd, edge: EdgeID) -> BoundValue {
+        if self.checked_arithmetic {
+            value.checked_add(weight).unwrap_or_else(|| overflow(edge))
+        } else {
+            value + weight
         }
     }
     pub fn num_nodes(&self) -> u32 {
@@ -353,6 +465,50 @@ impl IncSTN {
         self.active_propagators.push(Vec::new());
     }
 
+    /// Pre-creates the timepoints of all given variables, so that adding edges on them later
+    /// (e.g. when binding constraints) does not require growing the network one timepoint at a time.
+    ///
+    /// The domains of the timepoints remain those of the variables in the model.
+    pub fn register_variables(&mut self, vars: &[IVar]) {
+        if let Some(last) = vars.iter().map(|&v| u32::from(VarRef::from(v))).max() {
+            let missing = (last + 1).saturating_sub(self.num_nodes());
+            self.active_propagators.reserve(2 * missing as usize);
+            for _ in 0..missing {
+                self.reserve_timepoint();
+            }
+        }
+    }
+
+    /// Removes all timepoints whose index is greater than or equal to `keep`.
+    ///
+    /// This is not a backtracking operation and is intended for removing temporary timepoints that
+    /// were reserved speculatively.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the removed timepoints is involved in an edge, be it active or not.
+    pub fn truncate_timepoints(&mut self, keep: u32) {
+        let removed = |tp: Timepoint| u32::from(tp) >= keep;
+        if let Some((&(source, target), _)) = self
+            .constraints
+            .edges_between
+            .iter()
+            .find(|(&(source, target), ids)| !ids.is_empty() && (removed(source) || removed(target)))
+        {
+            panic!(
+                "Removing timepoints involved in an edge from {:?} to {:?}",
+                source, target
+            );
+        }
+        while self.num_nodes() > keep {
+            // removed timepoints have no edges, hence no propagators
+            for _ in 0..2 {
+                let propagators = self.active_propagators.pop().unwrap();
+                debug_assert!(propagators.is_empty());
+            }
+        }
+    }
+
     pub fn add_reified_edge(
         &mut self,
         literal: Bound,
@@ -361,9 +517,14 @@ impl IncSTN {
         weight: W,
         model: &Model,
     ) -> EdgeID {
-        let e = self
-            .add_inactive_constraint(source.into(), target.into(), weight, false)
-            .0;
+        let (source, target) = (source.into(), target.into());
+        if self.skip_dominated_edges && model.entails(literal) {
+            // the edge would be always active, skip it if it is redundant with another always active edge
+            if let Some(dominating) = self.constraints.dominating(&Edge::new(source, target, weight)) {
+                return dominating;
+            }
+        }
+        let e = self.add_inactive_constraint(source, target, weight, false).0;
 
         if model.entails(literal) {
             assert_eq!(model.discrete.entailing_level(literal), DecLvl::ROOT);
@@ -377,6 +538,49 @@ impl IncSTN {
         e
     }
 
+    /// Same as `add_reified_edge` but additionally associates the given tag to the edge, which can later
+    /// be retrieved with `edge_tag`.
+    ///
+    /// An edge and its negation share the same tag. If the edge is unified with an existing one that already
+    /// has a tag, the first tag is kept and the new one is ignored.
+    pub fn add_reified_edge_tagged(
+        &mut self,
+        literal: Bound,
+        source: impl Into<Timepoint>,
+        target: impl Into<Timepoint>,
+        weight: W,
+        tag: u64,
+        model: &Model,
+    ) -> EdgeID {
+        let e = self.add_reified_edge(literal, source, target, weight, model);
+        self.constraints.set_tag(e, tag);
+        e
+    }
+
+    /// Returns the tag associated to the edge (or to its negation), if any.
+    pub fn edge_tag(&self, edge: EdgeID) -> Option<u64> {
+        self.constraints.tag(edge)
+    }
+
+    /// Adds a precedence constraint `a <= b` (i.e. `a - b <= 0`) for each pair `(a, b)` and marks it as active,
+    /// returning the ids of the corresponding edges.
+    ///
+    /// Precedences added at the root decision level are always active. No changes are committed to the network
+    /// until a call to `propagate_all()`.
+    pub fn add_precedences(&mut self, pairs: &[(Timepoint, Timepoint)]) -> Vec<EdgeID> {
+        let mut edges = Vec::with_capacity(pairs.len());
+        for &(a, b) in pairs {
+            // edge b -> a encodes the constraint a - b <= 0
+            let (e, _) = self.add_inactive_constraint(b, a, 0, false);
+            if self.trail.current_decision_level() == DecLvl::ROOT {
+                self.constraints[e].always_active = true;
+            }
+            self.mark_active(e);
+            edges.push(e);
+        }
+        edges
+    }
+
     /// Marks an edge as active and enqueue it for propagation.
     /// No changes are committed to the network by this function until a call to `propagate_all()`
     pub fn mark_active(&mut self, edge: EdgeID) {
@@ -385,6 +589,183 @@ impl IncSTN {
         self.trail.push(Event::NewPendingActivation);
     }
 
+    /// Returns the current length of the trail, to be used as a snapshot for `edges_activated_since`.
+    ///
+    /// Note that events are only recorded on the trail once a first backtrack point has been set.
+    pub fn trail_len(&self) -> usize {
+        self.trail.trail.len()
+    }
+
+    /// Returns all edges that were activated (and propagated) since the trail had the given length.
+    /// The trail point must have been obtained from `trail_len()` with no backtrack in between.
+    pub fn edges_activated_since(&self, trail_point: usize) -> impl Iterator<Item = EdgeID> + '_ {
+        self.trail.trail[trail_point..].iter().filter_map(|ev| match ev {
+            EdgeActivated(e) => Some(*e),
+            _ => None,
+        })
+    }
+
+    /// Returns the edge whose propagation caused a bound change, if the change was made by this STN.
+    ///
+    /// Each bound change made by the STN records the id of the propagated edge in its cause, which is also
+    /// what the solver provides to `Theory::explain` when asking for a justification of the change.
+    pub fn edge_of_cause(&self, cause: Cause) -> Option<(EdgeID, Edge)> {
+        match cause {
+            Cause::Inference(inference) if inference.writer == self.identity => {
+                let id = EdgeID::from(inference.payload);
+                Some((id, self.constraints[id].edge))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the critical path leading to the current lower bound of `to` (e.g. the makespan when `to` is the
+    /// horizon), i.e., the chain of tight edges that forced this lower bound, ordered from its origin to `to`.
+    ///
+    /// The chain is built by following the causes of the lower bounds, recorded in the model, back from `to`
+    /// until reaching a timepoint whose lower bound was not set by this STN (it is the initial one or comes from
+    /// a decision or another propagator). Only edges with no slack (`lb(source) == lb(target) - weight`)
+    /// are part of the path.
+    pub fn critical_path(&self, model: &DiscreteModel, to: Timepoint) -> Vec<EdgeID> {
+        let mut path = Vec::new();
+        let mut current = to;
+        while let Some((id, edge)) = model.cause_of_lb(current).and_then(|cause| self.edge_of_cause(cause)) {
+            debug_assert_eq!(edge.source, current);
+            if model.lb(edge.source) != model.lb(edge.target) - edge.weight {
+                break;
+            }
+            path.push(id);
+            current = edge.target;
+        }
+        path.reverse();
+        path
+    }
+
+    /// Returns the slack of an active edge `target - source <= weight`, i.e., by how much its weight could be
+    /// decreased before it would further restrict the current bounds of its endpoints.
+    /// This is the minimum of the slack on the upper bound of the target (`ub(source) + weight - ub(target)`) and
+    /// on the lower bound of the source (`lb(source) + weight - lb(target)`).
+    ///
+    /// A zero slack means that the edge is currently binding: it determines one of these bounds and is
+    /// thus on a critical path (see `critical_path`). Returns `None` if the edge is not active.
+    pub fn slack(&self, model: &DiscreteModel, edge: EdgeID) -> Option<W> {
+        if !self.active(edge) {
+            return None;
+        }
+        let e = self.constraints[edge].edge;
+        let ub_slack = model.ub(e.source) + e.weight - model.ub(e.target);
+        let lb_slack = model.lb(e.source) + e.weight - model.lb(e.target);
+        Some(ub_slack.min(lb_slack))
+    }
+
+    /// Renders the edge as the constraint `target - source <= weight` it represents, where timepoints are
+    /// designated by their label in the model, or by their index if they have none.
+    /// This is typically used to display the edges of an inconsistent cycle to the user.
+    pub fn format_edge(&self, model: &DiscreteModel, edge: EdgeID) -> String {
+        let e = self.constraints[edge].edge;
+        let name = |tp: Timepoint| match model.label(tp) {
+            Some(label) => label.to_string(),
+            None => usize::from(tp).to_string(),
+        };
+        format!("{} - {} <= {}", name(e.target), name(e.source), e.weight)
+    }
+
+    /// Returns all edges that were explicitly inserted in the network (leaving out the negations of
+    /// inserted edges that are only represented internally), together with their id and a flag that
+    /// is true if the edge is currently active.
+    pub fn user_constraints(&self) -> impl Iterator<Item = (EdgeID, Edge, bool)> + '_ {
+        (0..self.constraints.constraints.len())
+            .map(EdgeID::from)
+            .filter(move |&id| self.constraints[id].inserted)
+            .map(move |id| {
+                let c = &self.constraints[id];
+                (id, c.edge, c.active)
+            })
+    }
+
+    /// Returns true if the active edges of the network force `a` to be before (or at the same time as) `b`,
+    /// i.e., if there is a path from `b` to `a` whose total weight is non-positive, entailing `a - b <= 0`.
+    ///
+    /// Only the edges that were propagated are considered, the bounds of the timepoints are ignored.
+    pub fn necessarily_before(&self, a: Timepoint, b: Timepoint) -> bool {
+        matches!(self.shortest_distance(b, a), Some(dist) if dist <= 0)
+    }
+
+    /// Returns true if the graph of active edges has no cycle, ignoring edges from a timepoint to itself.
+    ///
+    /// As for `necessarily_before`, only the edges that were propagated are considered.
+    pub fn active_graph_is_acyclic(&self) -> bool {
+        #[derive(Copy, Clone, Eq, PartialEq)]
+        enum Mark {
+            Unvisited,
+            OnStack,
+            Done,
+        }
+        let mut marks = vec![Mark::Unvisited; self.num_nodes() as usize];
+        // depth first search, where each element of the stack is a node and the index of its next successor to visit
+        let mut stack: Vec<(Timepoint, usize)> = Vec::new();
+        for root in 0..self.num_nodes() {
+            let root = Timepoint::from(root);
+            if marks[usize::from(root)] != Mark::Unvisited {
+                continue;
+            }
+            marks[usize::from(root)] = Mark::OnStack;
+            stack.push((root, 0));
+            while let Some(&(node, next)) = stack.last() {
+                let successors = &self.active_propagators[VarBound::ub(node)];
+                if next == successors.len() {
+                    marks[usize::from(node)] = Mark::Done;
+                    stack.pop();
+                    continue;
+                }
+                stack.last_mut().unwrap().1 += 1;
+                let succ = self.constraints[successors[next].id].edge.target;
+                match marks[usize::from(succ)] {
+                    _ if succ == node => {}
+                    Mark::OnStack => return false,
+                    Mark::Done => {}
+                    Mark::Unvisited => {
+                        marks[usize::from(succ)] = Mark::OnStack;
+                        stack.push((succ, 0));
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    /// Returns the length of the shortest path from `from` to `to` in the graph of active edges,
+    /// or None if there is no such path.
+    /// The network is assumed to be consistent (no negative cycle).
+    fn shortest_distance(&self, from: Timepoint, to: Timepoint) -> Option<W> {
+        if from == to {
+            return Some(0);
+        }
+        if !self.has_edges(from) || !self.has_edges(to) {
+            return None;
+        }
+        // label correcting algorithm, required as edges might have negative weights
+        let mut distances: HashMap<Timepoint, W> = HashMap::new();
+        let mut queue = VecDeque::new();
+        distances.insert(from, 0);
+        queue.push_back(from);
+        while let Some(curr) = queue.pop_front() {
+            let dist = distances[&curr];
+            for prop in &self.active_propagators[VarBound::ub(curr)] {
+                let edge = &self.constraints[prop.id].edge;
+                let candidate = dist + edge.weight;
+                match distances.get(&edge.target) {
+                    Some(&previous) if previous <= candidate => {}
+                    _ => {
+                        distances.insert(edge.target, candidate);
+                        queue.push_back(edge.target);
+                    }
+                }
+            }
+        }
+        distances.get(&to).copied()
+    }
+
     fn build_contradiction(&self, culprits: &[EdgeID], model: &DiscreteModel) -> Contradiction {
         let mut expl = Explanation::with_capacity(culprits.len());
         for &edge in culprits {
@@ -475,46 +856,64 @@ impl IncSTN {
                 }
                 self.propagate_bound_change(literal, model)?;
             }
+            let batch = matches!(self.bellman_ford_threshold, Some(t) if self.pending_activations.len() > t);
+            let mut new_edges = Vec::new();
             while let Some(event) = self.pending_activations.pop_front() {
                 let ActivationEvent::ToActivate(edge) = event;
-                let c = &mut self.constraints[edge];
-                if !c.active {
-                    c.active = true;
-                    let Edge { source, target, weight } = c.edge;
-                    if source == target {
-                        // we are in a self loop, that must must handled separately since they are trivial
-                        // to handle and not supported by the propagation loop
-                        if weight < 0 {
-                            // negative self loop: inconsistency
-                            self.explanation.clear();
-                            self.explanation.push(edge);
-                            return Err(self.build_contradiction(&self.explanation, model));
-                        } else {
-                            // positive self loop : useless edge that we can ignore
-                        }
+                if self.activate(edge, model)? {
+                    if batch {
+                        new_edges.push(edge);
                     } else {
-                        // source <= X   =>   target <= X + weight
-                        self.active_propagators[VarBound::ub(source)].push(Propagator {
-                            target: VarBound::ub(target),
-                            weight: BoundValueAdd::on_ub(weight),
-                            id: edge,
-                        });
-                        // target >= X   =>   source >= X - weight
-                        self.active_propagators[VarBound::lb(target)].push(Propagator {
-                            target: VarBound::lb(source),
-                            weight: BoundValueAdd::on_lb(-weight),
-                            id: edge,
-                        });
-                        self.trail.push(EdgeActivated(edge));
                         self.propagate_new_edge(edge, model)?;
                     }
                 }
             }
+            if !new_edges.is_empty() {
+                self.propagate_bellman_ford(&new_edges, model)?;
+            }
         }
 
         Ok(())
     }
 
+    /// Activates the edge, making it participate in future propagations.
+    /// Returns true if the edge was newly activated and its effect should be propagated.
+    fn activate(&mut self, edge: EdgeID, model: &DiscreteModel) -> Result<bool, Contradiction> {
+        let c = &mut self.constraints[edge];
+        if c.active {
+            return Ok(false);
+        }
+        c.active = true;
+        let Edge { source, target, weight } = c.edge;
+        if source == target {
+            // we are in a self loop, that must must handled separately since they are trivial
+            // to handle and not supported by the propagation loop
+            if weight < 0 {
+                // negative self loop: inconsistency
+                self.explanation.clear();
+                self.explanation.push(edge);
+                return Err(self.build_contradiction(&self.explanation, model));
+            } else {
+                // positive self loop : useless edge that we can ignore
+                return Ok(false);
+            }
+        }
+        // source <= X   =>   target <= X + weight
+        self.active_propagators[VarBound::ub(source)].push(Propagator {
+            target: VarBound::ub(target),
+            weight: BoundValueAdd::on_ub(weight),
+            id: edge,
+        });
+        // target >= X   =>   source >= X - weight
+        self.active_propagators[VarBound::lb(target)].push(Propagator {
+            target: VarBound::lb(source),
+            weight: BoundValueAdd::on_lb(-weight),
+            id: edge,
+        });
+        self.trail.push(EdgeActivated(edge));
+        Ok(true)
+    }
+
     /// Creates a new backtrack point that represents the STN at the point of the method call,
     /// just before the insertion of the backtrack point.
     pub fn set_backtrack_point(&mut self) -> BacktrackLevel {
@@ -612,16 +1011,96 @@ impl IncSTN {
 
         let source_ub = model.ub(source);
         let target_lb = model.lb(target);
-        if model.set_ub(target, source_ub + weight, cause)? {
+        let (target_ub, source_lb) = if self.checked_arithmetic {
+            (
+                source_ub.checked_add(weight).unwrap_or_else(|| overflow(new_edge)),
+                target_lb.checked_sub(weight).unwrap_or_else(|| overflow(new_edge)),
+            )
+        } else {
+            (source_ub + weight, target_lb - weight)
+        };
+        if model.set_ub(target, target_ub, cause)? {
             self.run_propagation_loop(VarBound::ub(target), model, true)?;
         }
-        if model.set_lb(source, target_lb - weight, cause)? {
-            self.run_propagation_loop(VarBound::lb(target), model, true)?;
+        if model.set_lb(source, source_lb, cause)? {
+            self.run_propagation_loop(VarBound::lb(source), model, true)?;
         }
 
         Ok(())
     }
 
+    /// Propagates a set of **newly activated** edges in a **consistent** STN with a queue-based Bellman-Ford algorithm.
+    ///
+    /// Negative cycles are detected with the "walk to root" strategy: before updating a bound, we check that
+    /// it is not an ancestor of the bound causing the update in the tree of updates made by this propagation.
+    fn propagate_bellman_ford(&mut self, new_edges: &[EdgeID], model: &mut DiscreteModel) -> Result<(), Contradiction> {
+        self.clean_up_propagation_state();
+        self.stats.num_propagations += 1;
+
+        // for each bound updated by this propagation, the bound and edge that caused its last update
+        let mut parents: RefMap<VarBound, (VarBound, EdgeID)> = RefMap::default();
+        for &edge in new_edges {
+            let Edge { source, target, .. } = self.constraints[edge].edge;
+            for vb in [VarBound::ub(source), VarBound::lb(target)].iter().copied() {
+                if !self.pending_updates.contains(vb) {
+                    self.internal_propagate_queue.push_back(vb);
+                    self.pending_updates.insert(vb);
+                }
+            }
+        }
+
+        while let Some(source) = self.internal_propagate_queue.pop_front() {
+            self.record_queue_len();
+            if !self.pending_updates.contains(source) {
+                continue;
+            }
+            self.pending_updates.remove(source);
+            let source_bound = model.domains.get_bound(source);
+
+            for e in &self.active_propagators[source] {
+                let target = e.target;
+                let candidate = self.shifted(source_bound, e.weight, e.id);
+                if model.domains.get_bound(target).stronger(candidate) {
+                    continue;
+                }
+                // the update would be made from `source`: if `target` is an ancestor of `source` we have a negative cycle
+                let mut curr = source;
+                while let Some(&(parent, _)) = parents.get(curr) {
+                    if parent == target {
+                        // gather all edges of the cycle: target -> ... -> source -> target
+                        let mut culprits = vec![e.id];
+                        let mut curr = source;
+                        while curr != target {
+                            let (parent, edge) = parents[curr];
+                            culprits.push(edge);
+                            curr = parent;
+                        }
+                        return Err(self.build_contradiction(&culprits, model));
+                    }
+                    curr = parent;
+                }
+
+                model.domains.set_bound(target, candidate, self.identity.cause(e.id))?;
+                self.stats.distance_updates += 1;
+                parents.insert(target, (source, e.id));
+                if !self.pending_updates.contains(target) {
+                    self.internal_propagate_queue.push_back(target);
+                    self.pending_updates.insert(target);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Records the size of the propagation queue, just after an element was popped from it.
+    #[inline]
+    fn record_queue_len(&mut self) {
+        let len = self.internal_propagate_queue.len() + 1;
+        if len > self.stats.max_queue_len {
+            self.stats.max_queue_len = len;
+        }
+    }
+
     fn run_propagation_loop(
         &mut self,
         original: VarBound,
@@ -635,6 +1114,7 @@ impl IncSTN {
         self.pending_updates.insert(original);
 
         while let Some(source) = self.internal_propagate_queue.pop_front() {
+            self.record_queue_len();
             let source_bound = model.domains.get_bound(source);
             if !self.pending_updates.contains(source) {
                 // bound was already updated
@@ -649,7 +1129,7 @@ impl IncSTN {
                 let cause = self.identity.cause(e.id);
                 let target = e.target;
                 debug_assert_ne!(source, target);
-                let candidate = source_bound + e.weight;
+                let candidate = self.shifted(source_bound, e.weight, e.id);
 
                 if model.domains.set_bound(target, candidate, cause)? {
                     self.stats.distance_updates += 1;
@@ -664,19 +1144,35 @@ impl IncSTN {
         Ok(())
     }
 
+    /// Builds the explanation of the negative cycle through `vb`, that was just detected by the propagation.
     fn extract_cycle(&self, vb: VarBound, model: &DiscreteModel) -> Explanation {
+        match self.try_extract_cycle(vb, model) {
+            Ok(expl) => expl,
+            Err(msg) => panic!("{}", msg),
+        }
+    }
+
+    /// Follows the causes of the bounds, starting from `vb`, until coming back to `vb` and returns the literals
+    /// enabling the edges of the corresponding negative cycle.
+    ///
+    /// Returns an error if the causes do not lead back to `vb`. Since a cycle goes through at most once by each
+    /// node, the number of steps is bounded by the number of nodes, which prevents looping forever on a malformed
+    /// chain of causes.
+    fn try_extract_cycle(&self, vb: VarBound, model: &DiscreteModel) -> Result<Explanation, String> {
         let mut expl = Explanation::with_capacity(4);
         let mut curr = vb;
         let mut cycle_length = 0;
-        loop {
+        for _ in 0..=self.num_nodes() {
             let value = model.domains.get_bound(curr);
             let lit = Bound::from_parts(curr, value);
             debug_assert!(model.entails(lit));
-            let ev = model.implying_event(lit).unwrap();
+            let ev = model
+                .implying_event(lit)
+                .ok_or_else(|| format!("No event implying the bound {:?}", lit))?;
             debug_assert_eq!(model.trail().decision_level(ev), self.trail.current_decision_level());
             let ev = model.get_event(ev);
             let edge = match ev.cause {
-                Cause::Decision => panic!(),
+                Cause::Decision => return Err(format!("The bound {:?} is a decision and not part of a cycle", lit)),
                 Cause::Inference(cause) => EdgeID::from(cause.payload),
             };
             let c = &self.constraints[edge];
@@ -693,9 +1189,56 @@ impl IncSTN {
             }
             if curr == vb {
                 debug_assert!(cycle_length < 0);
-                break expl;
+                return Ok(expl);
             }
         }
+        Err(format!("The causes of {:?} do not form a cycle", vb))
+    }
+
+    /// Given a set of edges containing a negative cycle, returns a subset of it that still contains a negative
+    /// cycle and from which no edge can be removed without losing this property.
+    ///
+    /// Edges are greedily removed in order. This is quadratic in the size of the cycle and
+    /// is intended to be used to produce smaller explanations of inconsistencies.
+    pub fn minimize_cycle(&self, cycle: &[EdgeID]) -> Vec<EdgeID> {
+        debug_assert!(self.has_negative_cycle(cycle));
+        let mut kept = cycle.to_vec();
+        let mut i = 0;
+        while i < kept.len() {
+            let removed = kept.remove(i);
+            if !self.has_negative_cycle(&kept) {
+                // edge is necessary, restore it
+                kept.insert(i, removed);
+                i += 1;
+            }
+        }
+        kept
+    }
+
+    /// Returns true if the graph made of the given edges contains a negative cycle.
+    fn has_negative_cycle(&self, edges: &[EdgeID]) -> bool {
+        // Bellman-Ford from a virtual source with a null edge to all nodes
+        let mut distances: HashMap<Timepoint, W> = HashMap::new();
+        for &e in edges {
+            let edge = &self.constraints[e].edge;
+            distances.insert(edge.source, 0);
+            distances.insert(edge.target, 0);
+        }
+        for _ in 0..=distances.len() {
+            let mut updated = false;
+            for &e in edges {
+                let edge = &self.constraints[e].edge;
+                let candidate = distances[&edge.source] + edge.weight;
+                if candidate < distances[&edge.target] {
+                    distances.insert(edge.target, candidate);
+                    updated = true;
+                }
+            }
+            if !updated {
+                return false;
+            }
+        }
+        true
     }
 
     pub fn print_stats(&self) {
@@ -703,6 +1246,7 @@ impl IncSTN {
         println!("# constraints: {}", self.constraints.constraints.len());
         println!("# propagations: {}", self.stats.num_propagations);
         println!("# domain updates: {}", self.stats.distance_updates);
+        println!("# max queue length: {}", self.stats.max_queue_len);
     }
 }
 
@@ -710,16 +1254,16 @@ use aries_backtrack::{DecLvl, ObsTrail, ObsTrailCursor, Trail};
 use aries_model::lang::{Fun, IAtom, IVar, IntCst, VarRef};
 use aries_solver::solver::{Binding, BindingResult};
 
-use aries_solver::{Contradiction, Theory};
+use aries_solver::{Contradiction, Theory, TheoryStats};
 use std::hash::Hash;
 use std::ops::Index;
 
 type ModelEvent = aries_model::int_model::domains::Event;
 
 use aries_backtrack::Backtrack;
-use aries_collections::ref_store::RefVec;
+use aries_collections::ref_store::{RefMap, RefVec};
 use aries_collections::set::RefSet;
-use aries_model::bounds::{Bound, BoundValueAdd, Relation, VarBound, Watches};
+use aries_model::bounds::{Bound, BoundValue, BoundValueAdd, Relation, VarBound, Watches};
 use aries_model::expressions::ExprHandle;
 use aries_model::int_model::{Cause, DiscreteModel, EmptyDomain, Explanation};
 use aries_model::{Model, WModel, WriterId};
@@ -727,6 +1271,12 @@ use std::collections::hash_map::Entry;
 use std::convert::*;
 use std::num::NonZeroU32;
 
+/// Reports an overflow in the computation of a bound derived from `edge`, in checked arithmetic mode.
+#[cold]
+fn overflow(edge: EdgeID) -> ! {
+    panic!("Overflow in the STN while propagating edge {:?}", edge)
+}
+
 impl Theory for IncSTN {
     fn identity(&self) -> WriterId {
         self.identity
@@ -739,6 +1289,7 @@ impl Theory for IncSTN {
         model: &mut Model,
         queue: &mut ObsTrail<Binding>,
     ) -> BindingResult {
+        self.stats.num_bindings += 1;
         let expr = model.expressions.get(expr);
         match expr.fun {
             Fun::Leq => {
@@ -772,7 +1323,11 @@ impl Theory for IncSTN {
     }
 
     fn propagate(&mut self, model: &mut DiscreteModel) -> Result<(), Contradiction> {
-        self.propagate_all(model)
+        let result = self.propagate_all(model);
+        if result.is_err() {
+            self.stats.num_conflicts += 1;
+        }
+        result
     }
 
     fn explain(&mut self, event: Bound, context: u32, model: &DiscreteModel, out_explanation: &mut Explanation) {
@@ -783,6 +1338,15 @@ impl Theory for IncSTN {
     fn print_stats(&self) {
         self.print_stats()
     }
+
+    fn stats(&self) -> TheoryStats {
+        TheoryStats {
+            num_bindings: self.stats.num_bindings,
+            num_propagations: self.stats.num_propagations,
+            num_conflicts: self.stats.num_conflicts,
+            max_queue_len: self.stats.max_queue_len,
+        }
+    }
 }
 
 impl Backtrack for IncSTN {
@@ -814,6 +1378,18 @@ impl STN {
         STN { stn, model, tautology }
     }
 
+    pub fn set_bellman_ford_threshold(&mut self, threshold: Option<usize>) {
+        self.stn.set_bellman_ford_threshold(threshold);
+    }
+
+    pub fn set_checked_arithmetic(&mut self, checked: bool) {
+        self.stn.set_checked_arithmetic(checked);
+    }
+
+    pub fn set_skip_dominated_edges(&mut self, skip: bool) {
+        self.stn.set_skip_dominated_edges(skip);
+    }
+
     pub fn add_timepoint(&mut self, lb: W, ub: W) -> Timepoint {
         self.model.new_ivar(lb, ub, "").into()
     }
@@ -831,10 +1407,19 @@ impl STN {
             .add_reified_edge(self.tautology, source, target, weight, &self.model)
     }
 
+    pub fn add_edge_tagged(&mut self, source: Timepoint, target: Timepoint, weight: W, tag: u64) -> EdgeID {
+        self.stn
+            .add_reified_edge_tagged(self.tautology, source, target, weight, tag, &self.model)
+    }
+
     pub fn add_reified_edge(&mut self, literal: Bound, source: Timepoint, target: Timepoint, weight: W) -> EdgeID {
         self.stn.add_reified_edge(literal, source, target, weight, &self.model)
     }
 
+    pub fn add_precedences(&mut self, pairs: &[(Timepoint, Timepoint)]) -> Vec<EdgeID> {
+        self.stn.add_precedences(pairs)
+    }
+
     pub fn add_inactive_edge(&mut self, source: Timepoint, target: Timepoint, weight: W) -> Bound {
         let v = self
             .model
@@ -844,6 +1429,20 @@ impl STN {
         activation
     }
 
+    pub fn add_inactive_edge_tagged(&mut self, source: Timepoint, target: Timepoint, weight: W, tag: u64) -> Bound {
+        let v = self
+            .model
+            .new_bvar(format!("reif({:?} -- {} --> {:?})", source, weight, target));
+        let activation = v.true_lit();
+        self.stn
+            .add_reified_edge_tagged(activation, source, target, weight, tag, &self.model);
+        activation
+    }
+
+    pub fn edge_tag(&self, edge: EdgeID) -> Option<u64> {
+        self.stn.edge_tag(edge)
+    }
+
     pub fn mark_active(&mut self, edge: Bound) {
         self.model.discrete.decide(edge).unwrap();
     }
@@ -909,6 +1508,156 @@ mod tests {
         check_rountrip2(EdgeID::new(1, false));
     }
 
+    #[test]
+    fn test_dominated_edges() {
+        let s = &mut STN::new();
+        let a = s.add_timepoint(0, 10);
+        let b = s.add_timepoint(0, 10);
+
+        let e5 = s.add_edge(a, b, 5);
+        let db = &s.stn.constraints;
+        assert!(db.dominated(&Edge::new(a, b, 7)));
+        assert!(db.dominated(&Edge::new(a, b, 5)));
+        assert!(!db.dominated(&Edge::new(a, b, 3)));
+        assert!(!db.dominated(&Edge::new(b, a, 7)));
+        // an optional edge does not dominate other edges
+        s.add_inactive_edge(b, a, 2);
+        assert!(!s.stn.constraints.dominated(&Edge::new(b, a, 7)));
+
+        // without the flag, the dominated edge is recorded
+        let e7 = s.add_edge(a, b, 7);
+        assert_ne!(e5, e7);
+        s.set_skip_dominated_edges(true);
+        assert_eq!(s.add_edge(a, b, 6), e5);
+        assert_ne!(s.add_edge(a, b, 4), e5);
+        s.assert_consistent();
+        assert_eq!(s.model.bounds(IVar::new(b)), (0, 10));
+        s.set_ub(a, 2);
+        s.assert_consistent();
+        assert_eq!(s.model.bounds(IVar::new(b)), (0, 6));
+    }
+
+    #[test]
+    fn test_user_constraints() {
+        let s = &mut STN::new();
+        let a = s.add_timepoint(0, 10);
+        let b = s.add_timepoint(0, 10);
+        s.set_lb(b, 2);
+        s.set_ub(a, 8);
+
+        let ab = s.add_edge(a, b, 5);
+        let ba = s.add_inactive_edge(b, a, -1);
+        s.assert_consistent();
+        let constraints: Vec<_> = s.stn.user_constraints().collect();
+        assert_eq!(constraints.len(), 2);
+        assert_eq!(constraints[0], (ab, Edge::new(a, b, 5), true));
+        let (ba_id, ba_edge, ba_active) = constraints[1];
+        assert_eq!(ba_edge, Edge::new(b, a, -1));
+        assert!(!ba_active);
+
+        s.set_backtrack_point();
+        s.mark_active(ba);
+        s.assert_consistent();
+        assert!(s.stn.user_constraints().any(|c| c == (ba_id, ba_edge, true)));
+        s.undo_to_last_backtrack_point();
+        assert!(s.stn.user_constraints().any(|c| c == (ba_id, ba_edge, false)));
+    }
+
+    #[test]
+    fn test_bound_explanation() {
+        let s = &mut STN::new();
+        let a = s.add_timepoint(0, 10);
+        let b = s.add_timepoint(0, 10);
+        // x => b - a <= -3
+        let x = s.add_inactive_edge(a, b, -3);
+        s.assert_consistent();
+        assert_eq!(s.model.bounds(IVar::new(b)), (0, 10));
+
+        s.mark_active(x);
+        s.assert_consistent();
+        assert_eq!(s.model.bounds(IVar::new(b)), (0, 7));
+        assert_eq!(s.model.bounds(IVar::new(a)), (3, 10));
+
+        // the new upper bound of b is caused by the edge enabled by x
+        let cause = s.model.discrete.cause_of_ub(b).unwrap();
+        let (edge_id, edge) = s.stn.edge_of_cause(cause).unwrap();
+        assert_eq!(edge, Edge::new(a, b, -3));
+        assert_eq!(s.stn.edge_of_cause(Cause::Decision), None);
+
+        // b <= 7 holds because a <= 10 and x
+        let mut explanation = Explanation::new();
+        s.stn
+            .explain(Bound::leq(b, 7), edge_id.into(), &s.model.discrete, &mut explanation);
+        let mut literals: Vec<Bound> = explanation.literals().to_vec();
+        literals.sort();
+        let mut expected = vec![Bound::leq(a, 10), x];
+        expected.sort();
+        assert_eq!(literals, expected);
+    }
+
+    #[test]
+    fn test_critical_path() {
+        let s = &mut STN::new();
+        let origin = s.add_timepoint(0, 100);
+        let a = s.add_timepoint(0, 100);
+        let b = s.add_timepoint(0, 100);
+        let c = s.add_timepoint(0, 100);
+        let horizon = s.add_timepoint(0, 100);
+        // critical chain: origin -(2)-> a -(5)-> b -(3)-> horizon
+        let origin_a = s.add_edge(a, origin, -2);
+        let a_b = s.add_edge(b, a, -5);
+        let b_horizon = s.add_edge(horizon, b, -3);
+        // shorter chain: origin -(1)-> c -(4)-> horizon
+        let origin_c = s.add_edge(c, origin, -1);
+        s.add_edge(horizon, c, -4);
+        s.assert_consistent();
+        assert_eq!(s.model.bounds(IVar::new(horizon)), (10, 100));
+
+        assert_eq!(
+            s.stn.critical_path(&s.model.discrete, horizon),
+            vec![origin_a, a_b, b_horizon]
+        );
+        assert_eq!(s.stn.critical_path(&s.model.discrete, c), vec![origin_c]);
+        assert!(s.stn.critical_path(&s.model.discrete, origin).is_empty());
+
+        // a later release date of `b` cuts the path
+        s.set_lb(b, 20);
+        s.assert_consistent();
+        assert_eq!(s.stn.critical_path(&s.model.discrete, horizon), vec![b_horizon]);
+    }
+
+    #[test]
+    fn test_precedences() {
+        let s = &mut STN::new();
+        let a = s.add_timepoint(0, 10);
+        let b = s.add_timepoint(0, 10);
+        let c = s.add_timepoint(0, 10);
+        let bounds =
+            |stn: &STN| -> Vec<(W, W)> { [a, b, c].iter().map(|&tp| stn.model.bounds(IVar::new(tp))).collect() };
+
+        // chain a <= b <= c
+        let edges = s.add_precedences(&[(a, b), (b, c)]);
+        assert_eq!(edges.len(), 2);
+        s.assert_consistent();
+        assert_eq!(bounds(s), vec![(0, 10), (0, 10), (0, 10)]);
+
+        s.set_lb(a, 3);
+        s.assert_consistent();
+        assert_eq!(bounds(s), vec![(3, 10), (3, 10), (3, 10)]);
+
+        s.set_ub(c, 5);
+        s.assert_consistent();
+        assert_eq!(bounds(s), vec![(3, 5), (3, 5), (3, 5)]);
+
+        // c before a closes a cycle, which is only consistent if all timepoints are equal
+        s.set_backtrack_point();
+        s.add_precedences(&[(c, a)]);
+        s.assert_consistent();
+        s.set_lb(b, 4);
+        s.assert_consistent();
+        assert_eq!(bounds(s), vec![(4, 5), (4, 5), (4, 5)]);
+    }
+
     #[test]
     fn test_propagation() {
         let s = &mut STN::new();
@@ -955,13 +1704,13 @@ mod tests {
         assert_bounds(s, 0, 1, 0, 10);
         s.set_backtrack_point();
 
-        let ab = s.add_edge(a, b, 5i32);
+        let ab = s.add_edge(a, b, 5);
         s.assert_consistent();
         assert_bounds(s, 0, 1, 0, 6);
 
         s.set_backtrack_point();
 
-        let ba = s.add_edge(b, a, -6i32);
+        let ba = s.add_edge(b, a, -6);
         s.assert_inconsistent(vec![ab, ba]);
 
         s.undo_to_last_backtrack_point();
@@ -970,12 +1719,290 @@ mod tests {
         s.undo_to_last_backtrack_point();
         assert_bounds(s, 0, 1, 0, 10);
 
-        let x = s.add_inactive_edge(a, b, 5i32);
+        let x = s.add_inactive_edge(a, b, 5);
         s.mark_active(x);
         s.assert_consistent();
         assert_bounds(s, 0, 1, 0, 6);
     }
 
+    #[test]
+    fn test_slack() {
+        // network of `test_backtracking`
+        let s = &mut STN::new();
+        let a = s.add_timepoint(0, 10);
+        let b = s.add_timepoint(0, 10);
+        s.set_ub(a, 1);
+        let ab = s.add_edge(a, b, 5);
+        let loose = s.add_edge(a, b, 8);
+        let x = s.model.new_bvar("x");
+        let inactive = s.add_reified_edge(x.true_lit(), b, a, 2);
+        s.assert_consistent();
+        assert_eq!(s.model.bounds(IVar::new(b)), (0, 6));
+
+        let slack = |s: &STN, e: EdgeID| s.stn.slack(&s.model.discrete, e);
+        // b <= a + 5 sets the upper bound of b
+        assert_eq!(slack(s, ab), Some(0));
+        // b <= a + 8 could be tightened by 3 before it gets binding
+        assert_eq!(slack(s, loose), Some(3));
+        assert_eq!(slack(s, inactive), None);
+    }
+
+    #[test]
+    fn test_active_graph_is_acyclic() {
+        let s = &mut STN::new();
+        let a = s.add_timepoint(0, 10);
+        let b = s.add_timepoint(0, 10);
+        let c = s.add_timepoint(0, 10);
+        s.add_edge(a, b, 2);
+        s.add_edge(b, c, 3);
+        s.add_edge(c, c, 0);
+        let x = s.model.new_bvar("x");
+        s.add_reified_edge(x.true_lit(), c, a, 1);
+        s.propagate_all().unwrap();
+        // the back edge is not active, self loops are ignored
+        assert!(s.stn.active_graph_is_acyclic());
+
+        s.model.discrete.decide(x.true_lit()).unwrap();
+        s.propagate_all().unwrap();
+        assert!(!s.stn.active_graph_is_acyclic());
+    }
+
+    #[test]
+    fn test_format_edge() {
+        let s = &mut STN::new();
+        let start = s.model.new_ivar(0, 10, "start").into();
+        let end = s.model.new_ivar(0, 10, "end").into();
+        let other = s.add_timepoint(0, 10);
+        // start + 3 <= end <= start + 2
+        let min_duration = s.add_edge(end, start, -3);
+        let max_duration = s.add_edge(start, end, 2);
+        let unlabeled = s.add_edge(start, other, 4);
+        s.assert_inconsistent(vec![min_duration, max_duration]);
+
+        let explanation: Vec<String> = [min_duration, max_duration]
+            .iter()
+            .map(|&e| s.stn.format_edge(&s.model.discrete, e))
+            .collect();
+        assert_eq!(explanation, vec!["start - end <= -3", "end - start <= 2"]);
+        let expected = format!("{} - start <= 4", usize::from(other));
+        assert_eq!(s.stn.format_edge(&s.model.discrete, unlabeled), expected);
+        assert_eq!(
+            Edge::new(start, other, 4).to_string(),
+            format!("{} - {} <= 4", usize::from(other), usize::from(start))
+        );
+    }
+
+    #[test]
+    fn test_necessarily_before() {
+        let s = &mut STN::new();
+        let a = s.add_timepoint(0, 10);
+        let b = s.add_timepoint(0, 10);
+        let c = s.add_timepoint(0, 10);
+        let d = s.add_timepoint(0, 10);
+        let add = |s: &mut STN, e: Edge| s.add_edge(e.source, e.target, e.weight);
+
+        add(s, crate::before_eq(a, b));
+        add(s, crate::strictly_before(b, c));
+        // d is at most 2 time units after c, it may however be placed before it
+        add(s, crate::max_delay(c, d, 2));
+        s.assert_consistent();
+
+        assert!(s.stn.necessarily_before(a, b));
+        assert!(s.stn.necessarily_before(b, c));
+        assert!(s.stn.necessarily_before(a, c));
+        assert!(!s.stn.necessarily_before(c, a));
+        assert!(!s.stn.necessarily_before(c, d));
+        assert!(!s.stn.necessarily_before(d, c));
+
+        // with a minimal delay, d must be after a
+        add(s, crate::min_delay(c, d, -1));
+        s.assert_consistent();
+        assert!(s.stn.necessarily_before(a, d));
+        assert!(!s.stn.necessarily_before(c, d));
+    }
+
+    #[test]
+    fn test_bind_wide_domains() {
+        use aries_model::lang::{BAtom, BExpr};
+        // timepoints are not restricted to 32 bits integers
+        const MAX: W = 1 << 40;
+        let mut model = Model::new();
+        let a = model.new_ivar(0, MAX, "a");
+        let b = model.new_ivar(MAX / 2, MAX, "b");
+        let mut stn = IncSTN::new(model.new_write_token());
+
+        let expr = match model.leq(a, b) {
+            BAtom::Expr(BExpr { expr, negated: false }) => expr,
+            _ => panic!("expected a LEQ expression"),
+        };
+        let lit = model.new_bvar("a <= b").true_lit();
+        let mut queue = ObsTrail::default();
+        let res = stn.bind(lit, expr, &mut model, &mut queue);
+        assert!(matches!(res, BindingResult::Enforced));
+
+        model.discrete.decide(Bound::leq(b, MAX / 2 + 3)).unwrap();
+        model.discrete.decide(lit).unwrap();
+        stn.propagate_all(&mut model.discrete).unwrap();
+        assert_eq!(model.bounds(a), (0, MAX / 2 + 3));
+    }
+
+    #[test]
+    fn test_checked_arithmetic() {
+        let mut stn = STN::new();
+        stn.set_checked_arithmetic(true);
+        let a = stn.add_timepoint(0, 10);
+        let b = stn.add_timepoint(0, 10);
+        // weights beyond the range of 32 bits integers are fine as long as bounds do not overflow
+        stn.add_edge(b, a, -(1 << 40));
+        stn.assert_inconsistent::<EdgeID>(vec![]);
+
+        let mut stn = STN::new();
+        stn.set_checked_arithmetic(true);
+        let a = stn.add_timepoint(0, 10);
+        let b = stn.add_timepoint(0, 10);
+        // the upper bound of `b` would overflow
+        stn.add_edge(a, b, W::MAX - 5);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| stn.propagate_all()));
+        let message = match result {
+            Ok(_) => panic!("Expected an overflow"),
+            Err(payload) => payload.downcast::<String>().unwrap(),
+        };
+        assert!(message.starts_with("Overflow in the STN"), "{}", message);
+    }
+
+    #[test]
+    fn test_cycle_minimization() {
+        let s = &mut STN::new();
+        let a = s.add_timepoint(0, 10);
+        let b = s.add_timepoint(0, 10);
+        let c = s.add_timepoint(0, 10);
+        let add = |s: &mut STN, source, target, weight| {
+            let lit = s.model.new_bvar("").true_lit();
+            s.add_reified_edge(lit, source, target, weight)
+        };
+        let ab = add(s, a, b, 1);
+        let bc = add(s, b, c, 1);
+        let ca = add(s, c, a, -5);
+        // chord, not needed for the cycle a -> b -> c -> a
+        let ac = add(s, a, c, 10);
+
+        let minimized = s.stn.minimize_cycle(&[ab, ac, bc, ca]);
+        assert_eq!(minimized, vec![ab, bc, ca]);
+        assert!(s.stn.has_negative_cycle(&minimized));
+
+        // an already minimal cycle is left untouched
+        assert_eq!(s.stn.minimize_cycle(&[ab, bc, ca]), vec![ab, bc, ca]);
+    }
+
+    #[test]
+    fn test_max_queue_len() {
+        const NUM_LEAVES: usize = 20;
+        let s = &mut STN::new();
+        let center = s.add_timepoint(0, 100);
+        for _ in 0..NUM_LEAVES {
+            let leaf = s.add_timepoint(0, 100);
+            s.add_edge(center, leaf, 1);
+        }
+        s.propagate_all().unwrap();
+        assert!(s.stn.stats().max_queue_len < NUM_LEAVES);
+
+        // updating the center results in an update of all leaves
+        s.set_ub(center, 10);
+        s.propagate_all().unwrap();
+        assert_eq!(s.stn.stats().max_queue_len, NUM_LEAVES);
+    }
+
+    #[test]
+    fn test_register_variables() {
+        use aries_model::lang::{BAtom, BExpr};
+        let mut model = Model::new();
+        let a = model.new_ivar(0, 10, "a");
+        let b = model.new_ivar(0, 10, "b");
+        let c = model.new_ivar(0, 10, "c");
+        let mut stn = IncSTN::new(model.new_write_token());
+        stn.register_variables(&[c, a]);
+        let num_nodes = stn.num_nodes();
+        assert_eq!(num_nodes, u32::from(VarRef::from(c)) + 1);
+
+        // registering known variables again is a no-op
+        stn.register_variables(&[b]);
+        assert_eq!(stn.num_nodes(), num_nodes);
+
+        let mut queue = ObsTrail::default();
+        for (x, y) in [(a, b), (b, c)].iter() {
+            let expr = match model.leq(*x, *y) {
+                BAtom::Expr(BExpr { expr, negated: false }) => expr,
+                _ => panic!("expected a LEQ expression"),
+            };
+            let lit = model.new_bvar("leq").true_lit();
+            let res = stn.bind(lit, expr, &mut model, &mut queue);
+            assert!(matches!(res, BindingResult::Enforced));
+        }
+        // the bindings reused the pre-created timepoints
+        assert_eq!(stn.num_nodes(), num_nodes);
+    }
+
+    #[test]
+    fn test_truncate_timepoints() {
+        let s = &mut STN::new();
+        let a = s.add_timepoint(0, 10);
+        let b = s.add_timepoint(0, 10);
+        s.add_edge(a, b, 2);
+        s.propagate_all().unwrap();
+        let num_nodes = s.stn.num_nodes();
+
+        for _ in 0..3 {
+            s.stn.reserve_timepoint();
+        }
+        assert_eq!(s.stn.num_nodes(), num_nodes + 3);
+        s.stn.truncate_timepoints(num_nodes);
+        assert_eq!(s.stn.num_nodes(), num_nodes);
+
+        // retained timepoints and their edges are intact
+        s.set_lb(b, 9);
+        s.propagate_all().unwrap();
+        assert_eq!(s.model.bounds(IVar::new(a)), (7, 10));
+
+        // timepoints involved in an edge cannot be removed, even if the edge is inactive
+        let c = s.add_timepoint(0, 10);
+        s.add_inactive_edge(a, c, 1);
+        let keep = u32::from(c);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| s.stn.truncate_timepoints(keep)));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_edges_activated_since() {
+        let s = &mut STN::new();
+        let a = s.add_timepoint(0, 10);
+        let b = s.add_timepoint(0, 10);
+        let c = s.add_timepoint(0, 10);
+        let add = |s: &mut STN, source, target, weight| {
+            let lit = s.model.new_bvar("").true_lit();
+            (lit, s.add_reified_edge(lit, source, target, weight))
+        };
+        let (ab_lit, ab) = add(s, a, b, 2);
+        let (bc_lit, bc) = add(s, b, c, 3);
+        let (ac_lit, _) = add(s, a, c, 4);
+        s.propagate_all().unwrap();
+        s.set_backtrack_point();
+
+        let snapshot = s.stn.trail_len();
+        assert_eq!(s.stn.edges_activated_since(snapshot).count(), 0);
+        s.mark_active(ab_lit);
+        s.mark_active(bc_lit);
+        s.propagate_all().unwrap();
+        let delta: Vec<EdgeID> = s.stn.edges_activated_since(snapshot).collect();
+        assert_eq!(delta.len(), 2);
+        assert!(delta.contains(&ab));
+        assert!(delta.contains(&bc));
+
+        let snapshot = s.stn.trail_len();
+        s.mark_active(ac_lit);
+        s.propagate_all().unwrap();
+        assert_eq!(s.stn.edges_activated_since(snapshot).count(), 1);
+    }
+
     #[test]
     fn test_unification() {
         // build base stn
@@ -1001,7 +2028,17 @@ mod tests {
 
     #[test]
     fn test_explanation() {
+        explanation_scenarios(None);
+    }
+
+    #[test]
+    fn test_bellman_ford_explanation() {
+        explanation_scenarios(Some(0));
+    }
+
+    fn explanation_scenarios(bellman_ford_threshold: Option<usize>) {
         let mut stn = &mut STN::new();
+        stn.set_bellman_ford_threshold(bellman_ford_threshold);
         let a = stn.add_timepoint(0, 10);
         let b = stn.add_timepoint(0, 10);
         let c = stn.add_timepoint(0, 10);
@@ -1035,4 +2072,100 @@ mod tests {
         let ca = stn.add_edge(c, a, -5);
         stn.assert_inconsistent(vec![ab, bc, ca]);
     }
+
+    #[test]
+    fn test_propagation_modes_agree() {
+        use rand::rngs::StdRng;
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(4398079847);
+        let mut cesta = STN::new();
+        let mut timepoints = Vec::new();
+        let mut activations = Vec::new();
+        for _ in 0..20 {
+            timepoints.push(cesta.add_timepoint(0, 100));
+        }
+        for _ in 0..60 {
+            let source = timepoints[rng.gen_range(0..timepoints.len())];
+            let target = timepoints[rng.gen_range(0..timepoints.len())];
+            let weight = rng.gen_range(-10..20);
+            activations.push(cesta.add_inactive_edge(source, target, weight));
+        }
+        let mut bellman_ford = cesta.clone();
+        bellman_ford.set_bellman_ford_threshold(Some(0));
+
+        let mut num_inconsistent = 0;
+        for batch in activations.chunks(4) {
+            for stn in &mut [&mut cesta, &mut bellman_ford] {
+                stn.set_backtrack_point();
+                for &edge in batch {
+                    stn.mark_active(edge);
+                }
+            }
+            let res1 = cesta.propagate_all();
+            let res2 = bellman_ford.propagate_all();
+            assert_eq!(res1.is_ok(), res2.is_ok());
+            if res1.is_ok() {
+                for &tp in &timepoints {
+                    assert_eq!(
+                        cesta.model.bounds(IVar::new(tp)),
+                        bellman_ford.model.bounds(IVar::new(tp))
+                    );
+                }
+            } else {
+                // undo the inconsistent batch
+                num_inconsistent += 1;
+                cesta.undo_to_last_backtrack_point();
+                bellman_ford.undo_to_last_backtrack_point();
+            }
+        }
+        // make sure both outcomes were exercised
+        assert!(num_inconsistent > 0);
+        assert!(num_inconsistent < activations.len() / 4);
+    }
+
+    #[test]
+    fn test_edge_tags() {
+        let s = &mut STN::new();
+        let a = s.add_timepoint(0, 10);
+        let b = s.add_timepoint(0, 10);
+        let ab = s.add_edge_tagged(a, b, 3, 1);
+        let untagged = s.add_edge(b, a, 5);
+        assert_eq!(s.edge_tag(ab), Some(1));
+        assert_eq!(s.edge_tag(!ab), Some(1));
+        assert_eq!(s.edge_tag(untagged), None);
+
+        // unified with the previous edge: the first tag is kept
+        let ab2 = s.add_edge_tagged(a, b, 3, 2);
+        assert_eq!(ab2, ab);
+        assert_eq!(s.edge_tag(ab2), Some(1));
+
+        // unified with an untagged edge: the tag is recorded
+        s.add_edge_tagged(b, a, 5, 3);
+        assert_eq!(s.edge_tag(untagged), Some(3));
+    }
+
+    #[test]
+    fn test_try_extract_cycle() {
+        let s = &mut STN::new();
+        let a = s.add_timepoint(0, 10);
+        let b = s.add_timepoint(0, 10);
+        let c = s.add_timepoint(0, 10);
+        let ac = s.add_edge(a, c, 1);
+        let ab = s.add_edge(a, b, 1);
+        let ba = s.add_edge(b, a, 1);
+        s.propagate_all().unwrap();
+
+        // causes go from `c` to a cycle between `a` and `b`, that never comes back to `c`
+        let cause = |e: EdgeID| s.stn.identity.cause(e);
+        let (ac, ab, ba) = (cause(ac), cause(ab), cause(ba));
+        s.model.discrete.set_ub(c, 5, ac).unwrap();
+        s.model.discrete.set_ub(a, 8, ba).unwrap();
+        s.model.discrete.set_ub(b, 9, ab).unwrap();
+        assert!(s.stn.try_extract_cycle(VarBound::ub(c), &s.model.discrete).is_err());
+
+        // a decision is never part of a cycle
+        s.set_lb(c, 2);
+        assert!(s.stn.try_extract_cycle(VarBound::lb(c), &s.model.discrete).is_err());
+    }
 }